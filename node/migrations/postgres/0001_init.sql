@@ -0,0 +1,36 @@
+-- Ethereum blocks of the indexed substrate blocks. Forks are kept, `is_canon` marks the ones
+-- on the best chain.
+CREATE TABLE blocks (
+	substrate_block_hash BYTEA PRIMARY KEY,
+	ethereum_block_hash BYTEA NOT NULL,
+	block_number BIGINT NOT NULL,
+	ethereum_storage_schema BYTEA NOT NULL,
+	is_canon BOOLEAN NOT NULL
+);
+CREATE INDEX blocks_ethereum_block_hash_idx ON blocks (ethereum_block_hash);
+CREATE INDEX blocks_canon_number_idx ON blocks (block_number) WHERE is_canon;
+
+CREATE TABLE transactions (
+	ethereum_transaction_hash BYTEA NOT NULL,
+	substrate_block_hash BYTEA NOT NULL REFERENCES blocks ON DELETE CASCADE,
+	ethereum_block_hash BYTEA NOT NULL,
+	ethereum_transaction_index INTEGER NOT NULL,
+	PRIMARY KEY (ethereum_transaction_hash, substrate_block_hash)
+);
+
+CREATE TABLE logs (
+	substrate_block_hash BYTEA NOT NULL REFERENCES blocks ON DELETE CASCADE,
+	transaction_index INTEGER NOT NULL,
+	log_index INTEGER NOT NULL,
+	address BYTEA NOT NULL,
+	topic_1 BYTEA,
+	topic_2 BYTEA,
+	topic_3 BYTEA,
+	topic_4 BYTEA,
+	PRIMARY KEY (substrate_block_hash, transaction_index, log_index)
+);
+CREATE INDEX logs_address_idx ON logs (address);
+CREATE INDEX logs_topic_1_idx ON logs (topic_1);
+CREATE INDEX logs_topic_2_idx ON logs (topic_2);
+CREATE INDEX logs_topic_3_idx ON logs (topic_3);
+CREATE INDEX logs_topic_4_idx ON logs (topic_4);
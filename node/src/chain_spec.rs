@@ -1,18 +1,12 @@
-use cumulus_primitives_core::ParaId;
-use fintradex_runtime::{AccountId, AuraId, Signature, EXISTENTIAL_DEPOSIT};
+use fintradex_runtime::{AccountId, Signature, WASM_BINARY};
 use sc_chain_spec::{ChainSpecExtension, ChainSpecGroup};
 use sc_service::ChainType;
 use serde::{Deserialize, Serialize};
-use sp_core::{sr25519, Pair, Public, H160, U256};
+use sp_core::{Pair, Public};
 use sp_runtime::traits::{IdentifyAccount, Verify};
-use std::{collections::BTreeMap, marker::PhantomData, str::FromStr};
 
 /// Specialized `ChainSpec` for the normal parachain runtime.
-pub type ChainSpec =
-	sc_service::GenericChainSpec<fintradex_runtime::RuntimeGenesisConfig, Extensions>;
-
-/// The default XCM version to set in genesis config.
-const SAFE_XCM_VERSION: u32 = xcm::prelude::XCM_VERSION;
+pub type ChainSpec = sc_service::GenericChainSpec<Extensions>;
 
 /// Helper function to generate a crypto pair from seed
 pub fn get_from_seed<TPublic: Public>(seed: &str) -> <TPublic::Pair as Pair>::Public {
@@ -40,13 +34,6 @@ impl Extensions {
 
 type AccountPublic = <Signature as Verify>::Signer;
 
-/// Generate collator keys from seed.
-///
-/// This function's return type must always match the session keys of the chain in tuple format.
-pub fn get_collator_keys_from_seed(seed: &str) -> AuraId {
-	get_from_seed::<AuraId>(seed)
-}
-
 /// Helper function to generate an account ID from seed
 pub fn get_account_id_from_seed<TPublic: Public>(seed: &str) -> AccountId
 where
@@ -55,272 +42,110 @@ where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
-/// Generate the session keys from individual elements.
-///
-/// The input must be a tuple of individual keys (a single arg for now since we have just one key).
-pub fn template_session_keys(keys: AuraId) -> fintradex_runtime::SessionKeys {
-	fintradex_runtime::SessionKeys { aura: keys }
-}
-
-pub fn development_config() -> ChainSpec {
+/// Token of the chain, shown by wallets and explorers.
+fn properties() -> sc_chain_spec::Properties {
 	// Give your base currency a unit name and decimal places
 	let mut properties = sc_chain_spec::Properties::new();
 	properties.insert("tokenSymbol".into(), "Fintra".into());
 	properties.insert("tokenDecimals".into(), 12.into());
 	properties.insert("ss58Format".into(), 42.into());
+	properties
+}
 
-	ChainSpec::from_genesis(
-		// Name
-		"Development",
-		// ID
-		"dev",
-		ChainType::Development,
-		move || {
-			testnet_genesis(
-				// initial collators.
-				vec![
-					(
-						get_account_id_from_seed::<sr25519::Public>("Alice"),
-						get_collator_keys_from_seed("Alice"),
-					),
-					(
-						get_account_id_from_seed::<sr25519::Public>("Bob"),
-						get_collator_keys_from_seed("Bob"),
-					),
-				],
-				vec![
-					get_account_id_from_seed::<sr25519::Public>("Alice"),
-					get_account_id_from_seed::<sr25519::Public>("Bob"),
-					get_account_id_from_seed::<sr25519::Public>("Charlie"),
-					get_account_id_from_seed::<sr25519::Public>("Dave"),
-					get_account_id_from_seed::<sr25519::Public>("Eve"),
-					get_account_id_from_seed::<sr25519::Public>("Ferdie"),
-					get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Charlie//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Dave//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
-				],
-				// Give Alice root privileges
-				Some(get_account_id_from_seed::<sr25519::Public>("Alice")),
-				1000.into(),
-			)
-		},
-		Vec::new(),
-		None,
-		None,
-		None,
-		None,
+pub fn development_config() -> ChainSpec {
+	ChainSpec::builder(
+		WASM_BINARY.expect("WASM binary was not built, please build it!"),
 		Extensions {
 			relay_chain: "rococo-local".into(), // You MUST set this to the correct network!
 			para_id: 1000,
 		},
 	)
+	.with_name("Development")
+	.with_id("dev")
+	.with_chain_type(ChainType::Development)
+	.with_genesis_config_preset_name(sp_genesis_builder::DEV_RUNTIME_PRESET)
+	.with_properties(properties())
+	.build()
 }
 
 pub fn local_testnet_config() -> ChainSpec {
-	// Give your base currency a unit name and decimal places
-	let mut properties = sc_chain_spec::Properties::new();
-	properties.insert("tokenSymbol".into(), "Fintra".into());
-	properties.insert("tokenDecimals".into(), 12.into());
-	properties.insert("ss58Format".into(), 42.into());
-
-	ChainSpec::from_genesis(
-		// Name
-		"Local Testnet",
-		// ID
-		"local_testnet",
-		ChainType::Local,
-		move || {
-			testnet_genesis(
-				// initial collators.
-				vec![
-					(
-						get_account_id_from_seed::<sr25519::Public>("Alice"),
-						get_collator_keys_from_seed("Alice"),
-					),
-					(
-						get_account_id_from_seed::<sr25519::Public>("Bob"),
-						get_collator_keys_from_seed("Bob"),
-					),
-				],
-				vec![
-					get_account_id_from_seed::<sr25519::Public>("Alice"),
-					get_account_id_from_seed::<sr25519::Public>("Bob"),
-					get_account_id_from_seed::<sr25519::Public>("Charlie"),
-					get_account_id_from_seed::<sr25519::Public>("Dave"),
-					get_account_id_from_seed::<sr25519::Public>("Eve"),
-					get_account_id_from_seed::<sr25519::Public>("Ferdie"),
-					get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Charlie//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Dave//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
-					get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
-				],
-				// Give Alice root privileges
-				Some(get_account_id_from_seed::<sr25519::Public>("Alice")),
-				1000.into(),
-			)
-		},
-		// Bootnodes
-		Vec::new(),
-		// Telemetry
-		None,
-		// Protocol ID
-		Some("template-local"),
-		// Fork ID
-		None,
-		// Properties
-		Some(properties),
-		// Extensions
+	ChainSpec::builder(
+		WASM_BINARY.expect("WASM binary was not built, please build it!"),
 		Extensions {
 			relay_chain: "rococo-local".into(), // You MUST set this to the correct network!
 			para_id: 1000,
 		},
 	)
+	.with_name("Local Testnet")
+	.with_id("local_testnet")
+	.with_chain_type(ChainType::Local)
+	.with_genesis_config_preset_name(sp_genesis_builder::LOCAL_TESTNET_RUNTIME_PRESET)
+	.with_protocol_id("template-local")
+	.with_properties(properties())
+	.build()
 }
 
-fn testnet_genesis(
-	invulnerables: Vec<(AccountId, AuraId)>,
-	endowed_accounts: Vec<AccountId>,
-	root_key: Option<AccountId>,
-	id: ParaId,
-) -> fintradex_runtime::RuntimeGenesisConfig {
-	let alice = get_from_seed::<sr25519::Public>("Alice");
-	let bob = get_from_seed::<sr25519::Public>("Bob");
+/// Chain spec of the network of the runtime preset `preset`, described in `runtime/presets`.
+pub fn network_config(preset: &str) -> Result<ChainSpec, String> {
+	let network = fintradex_runtime::genesis_config_presets::network_config(preset)
+		.ok_or_else(|| format!("{preset} is not a network preset"))??;
 
-	fintradex_runtime::RuntimeGenesisConfig {
-		system: Default::default(),
-		// Configure additional assets here
-		// For example, this configures asset "ALT1" & "ALT2" with owners, alice and bob, respectively
-		assets: fintradex_runtime::AssetsConfig {
-			assets: vec![
-				(1, alice.into(), true, 10_000_000_0000),
-				(2, bob.into(), true, 10_000_000_0000),
-			],
-			// Genesis metadata: Vec<(id, name, symbol, decimals)>
-			metadata: vec![
-				(1, "asset-1".into(), "ALT1".into(), 10),
-				(2, "asset-2".into(), "ALT2".into(), 10),
-			],
-			next_asset_id: 3,
-			// Genesis accounts: Vec<(id, account_id, balance)>
-			accounts: vec![(1, alice.into(), 50_000_000_0000), (2, bob.into(), 50_000_000_0000)],
-		},
-		balances: fintradex_runtime::BalancesConfig {
-			balances: endowed_accounts.iter().cloned().map(|k| (k, 1 << 60)).collect(),
-			dev_accounts: endowed_accounts.iter().cloned().map(|k| (k, 1 << 60)).collect(),
-		},
-		council: fintradex_runtime::CouncilConfig {
-			phantom: PhantomData,
-			members: endowed_accounts
-				.iter()
-				.enumerate()
-				.filter_map(|(idx, acc)| if idx % 2 == 0 { Some(acc.clone()) } else { None })
-				.collect::<Vec<_>>(),
-		},
-		parachain_info: fintradex_runtime::ParachainInfoConfig {
-			parachain_id: id,
-			..Default::default()
-		},
-		collator_selection: fintradex_runtime::CollatorSelectionConfig {
-			invulnerables: invulnerables.iter().cloned().map(|(acc, _)| acc).collect(),
-			candidacy_bond: EXISTENTIAL_DEPOSIT * 16,
-			..Default::default()
-		},
-		session: fintradex_runtime::SessionConfig {
-			keys: invulnerables
-				.into_iter()
-				.map(|(acc, aura)| {
-					(
-						acc.clone(),                 // account id
-						acc,                         // validator id
-						template_session_keys(aura), // session keys
-					)
-				})
-				.collect(),
-			non_authority_keys: vec![],
-		},
-		// no need to pass anything to aura, in fact it will panic if we do. Session will take care
-		// of this.
-		aura: Default::default(),
-		aura_ext: Default::default(),
-		parachain_system: Default::default(),
-		polkadot_xcm: fintradex_runtime::PolkadotXcmConfig {
-			safe_xcm_version: Some(SAFE_XCM_VERSION),
-			..Default::default()
-		},
-		sudo: fintradex_runtime::SudoConfig { key: root_key },
-		transaction_payment: Default::default(),
-		// EVM compatibility
-		evm_chain_id: fintradex_runtime::EVMChainIdConfig {
-			chain_id: 1000,
-			..Default::default()
-		},
-		evm: fintradex_runtime::EVMConfig {
-			accounts: {
-				let mut map = BTreeMap::new();
-				map.insert(
-					// H160 address of Alice dev account
-					// Derived from SS58 (42 prefix) address
-					// SS58: 5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY
-					// hex: 0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d
-					// Using the full hex key, truncating to the first 20 bytes (the first 40 hex chars)
-					H160::from_str("d43593c715fdd31c61141abd04a99fd6822c8558")
-						.expect("internal H160 is valid; qed"),
-					fp_evm::GenesisAccount {
-						balance: U256::from_str("0xffffffffffffffffffffffffffffffff")
-							.expect("internal U256 is valid; qed"),
-						code: Default::default(),
-						nonce: Default::default(),
-						storage: Default::default(),
-					},
-				);
-				map.insert(
-					// H160 address of CI test runner account
-					H160::from_str("6be02d1d3665660d22ff9624b7be0551ee1ac91b")
-						.expect("internal H160 is valid; qed"),
-					fp_evm::GenesisAccount {
-						balance: U256::from_str("0xffffffffffffffffffffffffffffffff")
-							.expect("internal U256 is valid; qed"),
-						code: Default::default(),
-						nonce: Default::default(),
-						storage: Default::default(),
-					},
-				);
-				map.insert(
-					// H160 address for benchmark usage
-					H160::from_str("1000000000000000000000000000000000000001")
-						.expect("internal H160 is valid; qed"),
-					fp_evm::GenesisAccount {
-						nonce: U256::from(1),
-						balance: U256::from(1_000_000_000_000_000_000_000_000u128),
-						storage: Default::default(),
-						code: vec![0x00],
-					},
-				);
-				map.insert(
-					// H160 address of dev account
-					// Private key : 0xb9d2ea9a615f3165812e8d44de0d24da9bbd164b65c4f0573e1ce2c8dbd9c8df
-					H160::from_str("C0F0f4ab324C46e55D02D0033343B4Be8A55532d")
-						.expect("internal H160 is valid; qed"),
-					fp_evm::GenesisAccount {
-						balance: U256::from_str("0xef000000000000000000000000000")
-							.expect("internal U256 is valid; qed"),
-						code: Default::default(),
-						nonce: Default::default(),
-						storage: Default::default(),
-					},
-				);
-				map
-			},
-			..Default::default()
+	Ok(ChainSpec::builder(
+		WASM_BINARY.ok_or("the runtime wasm binary is not available")?,
+		Extensions {
+			relay_chain: network.relay_chain,
+			para_id: network.para_id,
 		},
-		ethereum: Default::default(),
-		//dynamic_fee: Default::default(),
-		base_fee: Default::default(),
-		..Default::default()
+	)
+	.with_name(&network.name)
+	.with_id(preset)
+	.with_chain_type(ChainType::Live)
+	.with_genesis_config_preset_name(preset)
+	.with_protocol_id(preset)
+	.with_properties(properties())
+	.build())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use fintradex_runtime::genesis_config_presets::{
+		network_config as network, preset_names, KUSAMA_RUNTIME_PRESET, POLKADOT_RUNTIME_PRESET,
+		STAGING_RUNTIME_PRESET,
+	};
+	use sc_service::ChainSpec as _;
+	use sp_runtime::BuildStorage;
+
+	#[test]
+	fn each_preset_builds_a_genesis_state() {
+		for id in preset_names() {
+			let id: &str = id.as_ref();
+			if let Some(Err(e)) = network(id) {
+				panic!("{e}");
+			}
+			let chain_spec = ChainSpec::builder(
+				WASM_BINARY.expect("WASM binary was not built, please build it!"),
+				Extensions { relay_chain: "rococo-local".into(), para_id: 1000 },
+			)
+			.with_genesis_config_preset_name(id)
+			.build();
+			let genesis = chain_spec.build_storage().unwrap_or_else(|e| panic!("{id}: {e}"));
+			assert!(genesis.top.contains_key(sp_core::storage::well_known_keys::CODE), "{id}");
+		}
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn staging_chain_spec_loads() {
+		let chain_spec = network_config(STAGING_RUNTIME_PRESET).unwrap();
+		assert_eq!(chain_spec.chain_type(), ChainType::Live);
+		chain_spec.build_storage().unwrap();
+	}
+
+	#[test]
+	fn pending_networks_do_not_load() {
+		for id in [KUSAMA_RUNTIME_PRESET, POLKADOT_RUNTIME_PRESET] {
+			let e = network_config(id).err().unwrap();
+			assert!(e.contains(&format!("runtime/presets/{id}.json")), "{e}");
+		}
+	}
+}
@@ -0,0 +1,366 @@
+//! `chain-spec generate`: the chain spec of a new network from a TOML file instead of Rust code.
+//!
+//! The file describes the network (see `node/network.example.toml`): collators and their session
+//! keys, endowed accounts, assets, governance and the contracts deployed in the EVM at genesis.
+//! The genesis is the runtime's network genesis, as for the presets of `runtime/presets`, plus the
+//! EVM accounts, and the command writes the plain and the raw chain spec.
+
+use std::{
+	collections::BTreeMap,
+	path::{Path, PathBuf},
+	str::FromStr,
+};
+
+use fintradex_runtime::{
+	genesis_config_presets::{network_genesis, Asset, Collator, NetworkConfig, SystemContract},
+	AccountId, AuraId, UNIT, WASM_BINARY,
+};
+use sc_chain_spec::ChainSpec as _;
+use sc_network::config::MultiaddrWithPeerId;
+use sc_service::ChainType;
+use serde::Deserialize;
+use sp_core::{crypto::Ss58Codec, H160, H256, U256};
+
+use crate::chain_spec::{ChainSpec, Extensions};
+
+/// Chain spec operations.
+#[derive(Debug, clap::Subcommand)]
+pub enum ChainSpecCmd {
+	/// Write the plain and raw chain specs of a network described in a TOML file.
+	Generate(GenerateCmd),
+}
+
+impl ChainSpecCmd {
+	pub fn run(&self) -> sc_cli::Result<()> {
+		match self {
+			Self::Generate(cmd) => cmd.run(),
+		}
+	}
+}
+
+/// Write `<id>.json` and `<id>-raw.json` for the network described by `--config`.
+#[derive(Debug, clap::Parser)]
+pub struct GenerateCmd {
+	/// The TOML file describing the network.
+	#[arg(long, value_name = "PATH")]
+	pub config: PathBuf,
+
+	/// Directory the chain specs are written to.
+	#[arg(long, value_name = "DIR", default_value = ".")]
+	pub output: PathBuf,
+}
+
+fn error(message: impl Into<String>) -> sc_cli::Error {
+	sc_cli::Error::Input(message.into())
+}
+
+/// Token of the chain, shown by wallets and explorers.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TokenFile {
+	symbol: String,
+	decimals: u8,
+	ss58_format: u16,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CollatorFile {
+	account: String,
+	/// Aura session key, as an SS58 address.
+	aura: String,
+}
+
+/// A balance in whole units.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EndowmentFile {
+	account: String,
+	balance: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AssetFile {
+	id: u32,
+	owner: String,
+	name: String,
+	symbol: String,
+	decimals: u8,
+	min_balance: u64,
+	#[serde(default)]
+	is_sufficient: bool,
+	/// Initial holders, in the smallest unit of the asset.
+	#[serde(default)]
+	balances: Vec<EndowmentFile>,
+}
+
+/// A contract deployed in the EVM at genesis, from its runtime bytecode.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ContractFile {
+	address: H160,
+	/// The runtime bytecode, hex encoded.
+	#[serde(default)]
+	code: Option<String>,
+	/// A file holding the hex-encoded runtime bytecode, relative to the TOML file.
+	#[serde(default)]
+	code_file: Option<PathBuf>,
+	/// Balance in whole units.
+	#[serde(default)]
+	balance: u64,
+	#[serde(default)]
+	nonce: u64,
+	#[serde(default)]
+	storage: BTreeMap<H256, H256>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NetworkFile {
+	name: String,
+	id: String,
+	/// `Development`, `Local` or `Live`, the default.
+	#[serde(default)]
+	chain_type: ChainType,
+	relay_chain: String,
+	para_id: u32,
+	evm_chain_id: u64,
+	#[serde(default)]
+	protocol_id: Option<String>,
+	#[serde(default)]
+	boot_nodes: Vec<String>,
+	token: TokenFile,
+	collators: Vec<CollatorFile>,
+	#[serde(default)]
+	endowed: Vec<EndowmentFile>,
+	/// Balance of the treasury account, in whole units.
+	#[serde(default)]
+	treasury: u64,
+	#[serde(default)]
+	council: Vec<String>,
+	#[serde(default)]
+	technical_committee: Vec<String>,
+	#[serde(default)]
+	sudo: Option<String>,
+	#[serde(default)]
+	assets: Vec<AssetFile>,
+	/// Contracts deployed at their canonical addresses, from the artifacts of the runtime.
+	#[serde(default)]
+	system_contracts: Vec<SystemContract>,
+	#[serde(default)]
+	evm_contracts: Vec<ContractFile>,
+}
+
+/// An account of the file, given as an SS58 or an H160 address.
+enum Account {
+	Native(AccountId),
+	/// An EVM address, endowed in the EVM.
+	Evm(H160),
+}
+
+fn parse_account(s: &str) -> Result<Account, String> {
+	if s.starts_with("0x") {
+		return H160::from_str(s).map(Account::Evm).map_err(|_| format!("`{s}` is not an H160"));
+	}
+	AccountId::from_ss58check(s)
+		.map(Account::Native)
+		.map_err(|e| format!("`{s}` is not an SS58 address: {e:?}"))
+}
+
+fn native_account(s: &str) -> Result<AccountId, String> {
+	match parse_account(s)? {
+		Account::Native(account) => Ok(account),
+		Account::Evm(_) => Err(format!("`{s}` is an EVM address, not an account of the chain")),
+	}
+}
+
+fn empty_evm_account() -> fp_evm::GenesisAccount {
+	fp_evm::GenesisAccount {
+		nonce: U256::zero(),
+		balance: U256::zero(),
+		storage: BTreeMap::new(),
+		code: Vec::new(),
+	}
+}
+
+fn parse_hex(hex: &str) -> Result<Vec<u8>, String> {
+	sp_core::bytes::from_hex(hex.trim()).map_err(|e| format!("invalid hex bytecode: {e}"))
+}
+
+impl ContractFile {
+	/// The contract's bytecode, `code_file` being relative to `dir`.
+	fn code(&self, dir: &Path) -> Result<Vec<u8>, String> {
+		match (&self.code, &self.code_file) {
+			(Some(code), None) => parse_hex(code),
+			(None, Some(path)) => {
+				let path = dir.join(path);
+				let code = std::fs::read_to_string(&path)
+					.map_err(|e| format!("{}: {e}", path.display()))?;
+				parse_hex(&code).map_err(|e| format!("{}: {e}", path.display()))
+			},
+			(None, None) => Ok(Vec::new()),
+			(Some(_), Some(_)) => Err(format!("{:?}: both `code` and `code_file`", self.address)),
+		}
+	}
+}
+
+impl NetworkFile {
+	/// The network's chain spec, the files it references being relative to `dir`.
+	fn chain_spec(self, dir: &Path) -> Result<ChainSpec, String> {
+		let mut endowments = Vec::new();
+		let mut evm_accounts = BTreeMap::<H160, fp_evm::GenesisAccount>::new();
+		for endowment in &self.endowed {
+			match parse_account(&endowment.account)? {
+				Account::Native(account) => endowments.push((account, endowment.balance)),
+				Account::Evm(address) =>
+					evm_accounts.entry(address).or_insert_with(empty_evm_account).balance +=
+						U256::from(endowment.balance) * U256::from(UNIT),
+			}
+		}
+		for contract in &self.evm_contracts {
+			let account = evm_accounts.entry(contract.address).or_insert_with(empty_evm_account);
+			if !account.code.is_empty() {
+				return Err(format!("{:?} is deployed twice", contract.address));
+			}
+			account.code = contract.code(dir)?;
+			account.nonce = contract.nonce.into();
+			account.balance += U256::from(contract.balance) * U256::from(UNIT);
+			account.storage = contract.storage.clone();
+		}
+
+		let network = NetworkConfig {
+			name: self.name,
+			relay_chain: self.relay_chain,
+			para_id: Some(self.para_id),
+			evm_chain_id: self.evm_chain_id,
+			collators: self
+				.collators
+				.iter()
+				.map(|collator| {
+					Ok(Collator {
+						account: native_account(&collator.account)?,
+						aura: AuraId::from_ss58check(&collator.aura)
+							.map_err(|e| format!("`{}` is not an Aura key: {e:?}", collator.aura))?,
+					})
+				})
+				.collect::<Result<_, String>>()?,
+			endowments,
+			treasury: self.treasury,
+			council: self.council.iter().map(|s| native_account(s)).collect::<Result<_, _>>()?,
+			technical_committee: self
+				.technical_committee
+				.iter()
+				.map(|s| native_account(s))
+				.collect::<Result<_, _>>()?,
+			sudo: self.sudo.as_deref().map(native_account).transpose()?,
+			assets: self
+				.assets
+				.into_iter()
+				.map(|asset| {
+					Ok(Asset {
+						id: asset.id,
+						owner: native_account(&asset.owner)?,
+						name: asset.name,
+						symbol: asset.symbol,
+						decimals: asset.decimals,
+						min_balance: asset.min_balance.into(),
+						is_sufficient: asset.is_sufficient,
+						balances: asset
+							.balances
+							.iter()
+							.map(|b| Ok((native_account(&b.account)?, b.balance.into())))
+							.collect::<Result<_, String>>()?,
+					})
+				})
+				.collect::<Result<_, String>>()?,
+			system_contracts: self.system_contracts,
+		};
+		network.validate()?;
+		// Test networks may run on the well-known development keys.
+		if !matches!(self.chain_type, ChainType::Development | ChainType::Local) {
+			network.validate_dev_keys()?;
+		}
+
+		let mut properties = sc_chain_spec::Properties::new();
+		properties.insert("tokenSymbol".into(), self.token.symbol.into());
+		properties.insert("tokenDecimals".into(), self.token.decimals.into());
+		properties.insert("ss58Format".into(), self.token.ss58_format.into());
+
+		let boot_nodes = self
+			.boot_nodes
+			.iter()
+			.map(|addr| {
+				MultiaddrWithPeerId::from_str(addr).map_err(|e| format!("boot node {addr}: {e}"))
+			})
+			.collect::<Result<_, _>>()?;
+
+		let extensions =
+			Extensions { relay_chain: network.relay_chain.clone(), para_id: self.para_id };
+		let name = network.name.clone();
+		let mut genesis = network_genesis(network);
+		let accounts = genesis["evm"]["accounts"]
+			.as_object_mut()
+			.ok_or("the runtime genesis has no EVM accounts")?;
+		for (address, account) in evm_accounts {
+			let account = serde_json::to_value(account).map_err(|e| e.to_string())?;
+			let key = format!("{address:?}");
+			if accounts.insert(key, account).is_some() {
+				return Err(format!("{address:?} is a system contract"));
+			}
+		}
+
+		let mut builder = ChainSpec::builder(
+			WASM_BINARY.ok_or("the runtime wasm binary is not available")?,
+			extensions,
+		)
+		.with_name(&name)
+		.with_id(&self.id)
+		.with_chain_type(self.chain_type)
+		.with_genesis_config_patch(genesis)
+		.with_properties(properties)
+		.with_boot_nodes(boot_nodes);
+		if let Some(protocol_id) = &self.protocol_id {
+			builder = builder.with_protocol_id(protocol_id);
+		}
+		Ok(builder.build())
+	}
+}
+
+impl GenerateCmd {
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let file = std::fs::read_to_string(&self.config)?;
+		let network: NetworkFile = toml::from_str(&file)
+			.map_err(|e| error(format!("{}: {e}", self.config.display())))?;
+		let id = network.id.clone();
+		let dir = self.config.parent().unwrap_or(Path::new("."));
+		let chain_spec = network
+			.chain_spec(dir)
+			.map_err(|e| error(format!("{}: {e}", self.config.display())))?;
+
+		std::fs::create_dir_all(&self.output)?;
+		for (raw, file) in [(false, format!("{id}.json")), (true, format!("{id}-raw.json"))] {
+			let json = chain_spec.as_json(raw).map_err(error)?;
+			let path = self.output.join(file);
+			std::fs::write(&path, json)?;
+			println!("{}", path.display());
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_runtime::BuildStorage;
+
+	#[test]
+	fn example_network_builds_a_genesis_state() {
+		let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+		let file = std::fs::read_to_string(dir.join("network.example.toml")).unwrap();
+		let network: NetworkFile = toml::from_str(&file).unwrap();
+		let chain_spec = network.chain_spec(dir).unwrap();
+		chain_spec.build_storage().unwrap();
+		assert!(chain_spec.as_json(true).is_ok());
+	}
+}
@@ -46,6 +46,13 @@ pub enum Subcommand {
 
 	/// Db meta columns information.
 	FrontierDb(fc_cli::FrontierDbCmd),
+
+	/// Re-index a range of blocks in the Frontier mapping database.
+	FrontierDbReindex(crate::frontier_db::FrontierDbReindexCmd),
+
+	/// Generate chain specs from a network description.
+	#[command(subcommand)]
+	ChainSpec(crate::chain_spec_cmd::ChainSpecCmd),
 }
 
 const AFTER_HELP_EXAMPLE: &str = color_print::cstr!(
@@ -58,6 +65,15 @@ const AFTER_HELP_EXAMPLE: &str = color_print::cstr!(
            Launch a full node with default parachain <italic>local-testnet</> and relay chain <italic>rococo-local</>.
    <bold> frontier-parachain-node --collator</>
            Launch a collator with default parachain <italic>local-testnet</> and relay chain <italic>rococo-local</>.
+   <bold> frontier-parachain-node --collator --relay-chain-rpc-url wss://rpc-a.example:443 --relay-chain-rpc-url wss://rpc-b.example:443</>
+           Launch a collator following the relay chain through external RPC nodes instead of an embedded
+           relay chain node, the healthy ones first and moving on to the next one when a node fails.
+   <bold> frontier-parachain-node --collator --relay-chain-light-client -- --chain paseo</>
+           Launch a collator following the relay chain <italic>paseo</> through an embedded light client.
+   <bold> frontier-parachain-node --dev --sealing instant</>
+           Launch a standalone development chain sealing a block for each transaction.
+   <bold> frontier-parachain-node chain-spec generate --config network.toml --output specs</>
+           Write the plain and raw chain specs of the network described in network.toml.
  "#
 );
 
@@ -68,6 +84,9 @@ const AFTER_HELP_EXAMPLE: &str = color_print::cstr!(
 	subcommand_negates_reqs = true
 )]
 #[command(after_help = AFTER_HELP_EXAMPLE)]
+// The single-state transaction pool drops or stalls the transactions of the blocks forked away
+// from the unincluded segment, so the fork-aware one is the default.
+#[command(mut_arg("pool_type", |arg| arg.default_value("fork-aware")))]
 pub struct Cli {
 	#[command(subcommand)]
 	pub subcommand: Option<Subcommand>,
@@ -85,6 +104,19 @@ pub struct Cli {
 	#[arg(long)]
 	pub no_hardware_benchmarks: bool,
 
+	/// Run the runtime standalone, without a relay chain, sealing a block for each transaction
+	/// (`instant`), for each `engine_createBlock` call only (`manual`) or every given number of
+	/// milliseconds. Requires `--dev` or a development chain spec.
+	#[arg(long, value_name = "instant|manual|MILLISECONDS")]
+	pub sealing: Option<crate::dev::Sealing>,
+
+	/// Check the relay chain RPC nodes when the relay chain followed through
+	/// `--relay-chain-rpc-url` or `--relay-chain-light-client` has no new block for this many
+	/// seconds, counted from its first block so that syncing is not a stall, and switch to the
+	/// healthy one furthest ahead. A relay chain stalled on every node is only logged.
+	#[arg(long, value_name = "SECONDS", default_value = "60")]
+	pub relay_chain_stall_timeout: u64,
+
 	/// Relay chain arguments
 	#[arg(raw = true)]
 	pub relay_chain_args: Vec<String>,
@@ -1,11 +1,14 @@
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use codec::Encode;
 use cumulus_client_cli::generate_genesis_block;
 use cumulus_primitives_core::ParaId;
 use fc_db::kv::frontier_database_dir;
 use frame_benchmarking_cli::{BenchmarkCmd, SUBSTRATE_REFERENCE_HARDWARE};
-use fintradex_runtime::Block;
+use fintradex_runtime::{
+	genesis_config_presets::{KUSAMA_RUNTIME_PRESET, POLKADOT_RUNTIME_PRESET, STAGING_RUNTIME_PRESET},
+	Block,
+};
 use log::info;
 use sc_cli::{
 	ChainSpec, CliConfiguration, DefaultConfigurationValues, ImportParams, KeystoreParams,
@@ -13,25 +16,27 @@ use sc_cli::{
 };
 use sc_service::{
 	config::{BasePath, PrometheusConfig},
-	DatabaseSource, PartialComponents,
+	ChainType, DatabaseSource, PartialComponents,
 };
 use sp_core::hexdisplay::HexDisplay;
 use sp_runtime::traits::{AccountIdConversion, Block as BlockT};
 
-#[cfg(feature = "try-runtime")]
-use crate::service::ParachainNativeExecutor;
 use crate::{
 	chain_spec,
 	cli::{Cli, RelayChainCli, Subcommand},
-	eth::db_config_dir,
+	eth::{db_config_dir, FrontierBackend},
 	service::new_partial,
 };
 
+type FullBackend = sc_service::TFullBackend<fintradex_runtime::opaque::Block>;
+
 fn load_spec(id: &str) -> std::result::Result<Box<dyn ChainSpec>, String> {
 	Ok(match id {
 		"dev" => Box::new(chain_spec::development_config()),
 		"template-rococo" => Box::new(chain_spec::local_testnet_config()),
 		"" | "local" => Box::new(chain_spec::local_testnet_config()),
+		preset @ (STAGING_RUNTIME_PRESET | KUSAMA_RUNTIME_PRESET | POLKADOT_RUNTIME_PRESET) =>
+			Box::new(chain_spec::network_config(preset)?),
 		path => Box::new(chain_spec::ChainSpec::from_json_file(std::path::PathBuf::from(path))?),
 	})
 }
@@ -197,6 +202,9 @@ pub fn run() -> Result<()> {
 							},
 						};
 					},
+					crate::eth::BackendType::Postgres => {
+						eprintln!("The Postgres database may be shared with other nodes, not purged.");
+					},
 				};
 
 				let polkadot_cli = RelayChainCli::new(
@@ -273,17 +281,12 @@ pub fn run() -> Result<()> {
 		},
 		#[cfg(feature = "try-runtime")]
 		Some(Subcommand::TryRuntime(cmd)) => {
+			use crate::service::HostFunctions;
 			use fintradex_runtime::constants::time::MILLISECS_PER_BLOCK;
-			use sc_executor::{sp_wasm_interface::ExtendedHostFunctions, NativeExecutionDispatch};
 			use try_runtime_cli::block_building_info::timestamp_with_aura_info;
 
 			let runner = cli.create_runner(cmd)?;
 
-			type HostFunctionsOf<E> = ExtendedHostFunctions<
-				sp_io::SubstrateHostFunctions,
-				<E as NativeExecutionDispatch>::ExtendHostFunctions,
-			>;
-
 			// grab the task manager.
 			let registry = &runner.config().prometheus_config.as_ref().map(|cfg| &cfg.registry);
 			let task_manager =
@@ -293,9 +296,7 @@ pub fn run() -> Result<()> {
 
 			runner.async_run(|_| {
 				Ok((
-					cmd.run::<Block, HostFunctionsOf<ParachainNativeExecutor>, _>(Some(
-						info_provider,
-					)),
+					cmd.run::<Block, HostFunctions, _>(Some(info_provider)),
 					task_manager,
 				))
 			})
@@ -310,11 +311,44 @@ pub fn run() -> Result<()> {
 				let PartialComponents { client, other, .. } =
 					crate::service::new_partial(&config, &cli.eth)?;
 				let (_, _, _, frontier_backend, _) = other;
-				let frontier_backend = match frontier_backend {
-					fc_db::Backend::KeyValue(kv) => Arc::new(kv),
-					_ => panic!("Only fc_db::Backend::KeyValue supported"),
-				};
-				cmd.run(client, frontier_backend)
+				match frontier_backend {
+					FrontierBackend::KeyValue(kv) => cmd.run(client, Arc::new(kv)),
+					FrontierBackend::Sql(sql) => {
+						crate::frontier_db::run_sql::<_, FullBackend>(cmd, client, &sql)
+					},
+					FrontierBackend::Postgres(_) => Err("the postgres backend is shared, inspect \
+						it with a Postgres client or use frontier-db-reindex"
+						.into()),
+				}
+			})
+		},
+		Some(Subcommand::FrontierDbReindex(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, other, .. } =
+					crate::service::new_partial(&config, &cli.eth)?;
+				let (_, _, _, frontier_backend, overrides) = other;
+				crate::frontier_db::run_reindex::<_, FullBackend>(
+					cmd,
+					client,
+					frontier_backend,
+					overrides,
+				)
+			})
+		},
+		Some(Subcommand::ChainSpec(cmd)) => cmd.run(),
+		None if cli.sealing.is_some() => {
+			let runner = cli.create_runner(&cli.run.normalize())?;
+			let sealing = cli.sealing.expect("checked by the match guard; qed");
+			if !cli.run.base.shared_params.is_dev() &&
+				runner.config().chain_spec.chain_type() != ChainType::Development
+			{
+				return Err("`--sealing` runs the chain without a relay chain, it requires `--dev` \
+					or a development chain spec"
+					.into());
+			}
+			runner.run_node_until_exit(|config| async move {
+				crate::dev::start_dev_node(config, eth_cfg, sealing).await.map_err(Into::into)
 			})
 		},
 		None => {
@@ -367,6 +401,7 @@ pub fn run() -> Result<()> {
 					collator_options,
 					id,
 					hwbench,
+					Duration::from_secs(cli.relay_chain_stall_timeout),
 				)
 				.await
 				.map(|r| r.0)
@@ -0,0 +1,380 @@
+//! Standalone development node, sealing blocks with `sc-consensus-manual-seal`.
+//!
+//! With `--sealing`, the parachain runtime runs without a relay chain. The relay chain state it
+//! expects comes from [`MockValidationDataInherentDataProvider`]: every block gets a relay chain
+//! slot of its own, and the timestamp advances by at least one slot per block so that Aura and the
+//! consensus hook accept blocks sealed faster than the slot duration.
+
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use codec::{Decode, Encode};
+use cumulus_client_parachain_inherent::{MockValidationDataInherentDataProvider, MockXcmConfig};
+use cumulus_primitives_core::{
+	relay_chain::{well_known_keys, HeadData, UpgradeGoAhead},
+	ParaId,
+};
+use fintradex_runtime::{
+	opaque::{Block, Hash},
+	TransactionConverter,
+};
+use futures::{channel::mpsc, stream, StreamExt};
+use sc_client_api::StorageProvider;
+use sc_consensus_manual_seal::{
+	consensus::aura::AuraConsensusDataProvider, EngineCommand, ManualSealParams,
+};
+use sc_service::{Configuration, PartialComponents, TaskManager};
+use sp_blockchain::HeaderBackend;
+use sp_consensus_aura::Slot;
+use sp_core::{hashing::twox_128, storage::StorageKey, U256};
+use sp_runtime::traits::Header as HeaderT;
+
+use crate::{
+	chain_spec,
+	eth::{
+		new_frontier_partial, spawn_evm_history_indexer, spawn_frontier_tasks, spawn_trace_indexer,
+		EthApi, EthConfiguration, FrontierPartialComponents,
+	},
+	private_pool::{spawn_private_pool_pruner, PrivatePool, ProposerPool},
+	service::{new_partial_with, ParachainBlockImport, ParachainClient},
+	tx_lanes::{LanePool, TxLanes},
+};
+
+/// When the development node seals blocks, see `--sealing`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Sealing {
+	/// A block for each transaction entering the pool.
+	Instant,
+	/// A block for each `engine_createBlock` call only.
+	Manual,
+	/// A block every given number of milliseconds.
+	Interval(u64),
+}
+
+impl FromStr for Sealing {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"instant" => Ok(Self::Instant),
+			"manual" => Ok(Self::Manual),
+			s => match s.parse() {
+				Ok(0) | Err(_) => Err(format!(
+					"`{s}` is neither `instant`, `manual` nor a number of milliseconds"
+				)),
+				Ok(millis) => Ok(Self::Interval(millis)),
+			},
+		}
+	}
+}
+
+/// Import sealed blocks as they are, there is no relay chain to check them against.
+fn build_import_queue(
+	_client: Arc<ParachainClient>,
+	block_import: ParachainBlockImport,
+	config: &Configuration,
+	_eth_config: &EthConfiguration,
+	_telemetry: Option<sc_telemetry::TelemetryHandle>,
+	task_manager: &TaskManager,
+) -> Result<sc_consensus::DefaultImportQueue<Block>, sc_service::Error> {
+	Ok(sc_consensus_manual_seal::import_queue(
+		Box::new(block_import),
+		&task_manager.spawn_essential_handle(),
+		config.prometheus_registry(),
+	))
+}
+
+/// The value of the storage item `pallet::item` at block `at`.
+fn storage_value<T: Decode>(
+	client: &ParachainClient,
+	at: Hash,
+	pallet: &[u8],
+	item: &[u8],
+) -> sp_blockchain::Result<Option<T>> {
+	let key = StorageKey([twox_128(pallet), twox_128(item)].concat());
+	client
+		.storage(at, &key)?
+		.map(|value| {
+			T::decode(&mut &value.0[..]).map_err(|e| sp_blockchain::Error::Backend(e.to_string()))
+		})
+		.transpose()
+}
+
+/// Start a development node sealing blocks as `sealing` says.
+pub async fn start_dev_node(
+	mut config: Configuration,
+	eth_config: EthConfiguration,
+	sealing: Sealing,
+) -> sc_service::error::Result<TaskManager> {
+	let para_id = chain_spec::Extensions::try_get(&*config.chain_spec)
+		.map(|e| ParaId::from(e.para_id))
+		.ok_or("Could not find parachain ID in chain-spec.")?;
+
+	let PartialComponents {
+		client,
+		backend,
+		mut task_manager,
+		import_queue,
+		keystore_container,
+		transaction_pool,
+		other: (block_import, mut telemetry, _, frontier_backend, overrides),
+		..
+	} = new_partial_with(&config, &eth_config, build_import_queue)?;
+
+	let FrontierPartialComponents { filter_pool, fee_history_cache, fee_history_cache_limit } =
+		new_frontier_partial(&eth_config)?;
+
+	let prometheus_registry = config.prometheus_registry().cloned();
+	let net_config = sc_network::config::FullNetworkConfiguration::new(&config.network);
+	let (network, system_rpc_tx, tx_handler_controller, start_network, sync_service) =
+		sc_service::build_network(sc_service::BuildNetworkParams {
+			config: &config,
+			net_config,
+			client: client.clone(),
+			transaction_pool: transaction_pool.clone(),
+			spawn_handle: task_manager.spawn_handle(),
+			import_queue,
+			block_announce_validator_builder: None,
+			warp_sync_params: None,
+			block_relay: None,
+		})?;
+
+	let pubsub_notification_sinks: Arc<
+		fc_mapping_sync::EthereumBlockNotificationSinks<
+			fc_mapping_sync::EthereumBlockNotification<Block>,
+		>,
+	> = Default::default();
+	let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
+	let target_gas_price = eth_config.target_gas_price;
+	let private_pool = Arc::new(PrivatePool::new(eth_config.private_pool_capacity));
+	let tx_lanes = Arc::new(TxLanes::new(
+		eth_config.tx_lane.clone(),
+		eth_config.tx_lane_sender_limit,
+		eth_config.tx_lane_pool_limit,
+	));
+	let rpc_pool =
+		Arc::new(LanePool::new(transaction_pool.clone(), client.clone(), tx_lanes.clone()));
+	let (command_sink, commands_stream) = mpsc::channel(1024);
+
+	config.rpc_id_provider = Some(Box::new(fc_rpc::EthereumSubIdProvider));
+
+	let eth_rpc_params = crate::rpc::EthDeps {
+		client: client.clone(),
+		pool: rpc_pool.clone(),
+		converter: Some(TransactionConverter),
+		is_authority: true,
+		enable_dev_signer: eth_config.enable_dev_signer,
+		network: network.clone(),
+		sync: sync_service.clone(),
+		frontier_backend: frontier_backend.api(),
+		overrides: overrides.clone(),
+		block_data_cache: Arc::new(fc_rpc::EthBlockDataCacheTask::new(
+			task_manager.spawn_handle(),
+			overrides.clone(),
+			eth_config.eth_log_block_cache,
+			eth_config.eth_statuses_cache,
+			prometheus_registry.clone(),
+		)),
+		filter_pool: filter_pool.clone(),
+		max_past_logs: eth_config.max_past_logs,
+		fee_history_cache: fee_history_cache.clone(),
+		fee_history_cache_limit,
+		execute_gas_limit_multiplier: eth_config.execute_gas_limit_multiplier,
+		forced_parent_hashes: None,
+		pending_create_inherent_data_providers: move |_, ()| async move {
+			let current = sp_timestamp::InherentDataProvider::from_system_time();
+			let next_slot = current.timestamp().as_millis() + slot_duration.as_millis();
+			let timestamp = sp_timestamp::InherentDataProvider::new(next_slot.into());
+			let slot = sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
+				*timestamp,
+				slot_duration,
+			);
+			let dynamic_fee = fp_dynamic_fee::InherentDataProvider(U256::from(target_gas_price));
+			Ok((slot, timestamp, dynamic_fee))
+		},
+		ethapi: eth_config.ethapi.clone(),
+		tracing_permits: Arc::new(tokio::sync::Semaphore::new(
+			eth_config.ethapi_max_permits as usize,
+		)),
+		trace_limits: crate::rpc::TraceLimits {
+			max_count: eth_config.ethapi_trace_max_count,
+			max_block_range: eth_config.ethapi_trace_max_block_range,
+			cache_size: eth_config.ethapi_trace_cache_size,
+		},
+		private_pool: private_pool.clone(),
+		private_tx_lifetime: eth_config.private_tx_lifetime,
+		evm_state_history: eth_config.evm_state_history.is_some(),
+		sync_lag_alarm: eth_config.frontier_sync_lag_alarm,
+	};
+
+	let rpc_builder = {
+		let client = client.clone();
+		let pubsub_notification_sinks = pubsub_notification_sinks.clone();
+
+		Box::new(move |deny_unsafe, subscription_task_executor| {
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: rpc_pool.clone(),
+				deny_unsafe,
+				command_sink: Some(command_sink.clone()),
+				eth: eth_rpc_params.clone(),
+			};
+
+			crate::rpc::create_full(
+				deps,
+				subscription_task_executor,
+				pubsub_notification_sinks.clone(),
+			)
+			.map_err(Into::into)
+		})
+	};
+
+	sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+		config,
+		client: client.clone(),
+		backend: backend.clone(),
+		task_manager: &mut task_manager,
+		keystore: keystore_container.keystore(),
+		transaction_pool: transaction_pool.clone(),
+		rpc_builder,
+		network,
+		system_rpc_tx,
+		tx_handler_controller,
+		sync_service: sync_service.clone(),
+		telemetry: telemetry.as_mut(),
+	})?;
+
+	spawn_frontier_tasks(
+		&task_manager,
+		&eth_config,
+		prometheus_registry.as_ref(),
+		client.clone(),
+		backend.clone(),
+		frontier_backend,
+		filter_pool,
+		overrides,
+		fee_history_cache,
+		fee_history_cache_limit,
+		sync_service,
+		pubsub_notification_sinks,
+	)
+	.await;
+
+	if eth_config.ethapi.contains(&EthApi::Trace) {
+		spawn_trace_indexer(&task_manager, client.clone(), eth_config.ethapi_trace_retention);
+	}
+
+	if let Some(history) = eth_config.evm_state_history {
+		spawn_evm_history_indexer(&task_manager, client.clone(), history);
+	}
+
+	if eth_config.ethapi.contains(&EthApi::Private) {
+		spawn_private_pool_pruner(&task_manager, client.clone(), private_pool.clone());
+	}
+
+	let proposer_factory = sc_basic_authorship::ProposerFactory::new(
+		task_manager.spawn_handle(),
+		client.clone(),
+		Arc::new(ProposerPool::new(
+			client.clone(),
+			transaction_pool.clone(),
+			private_pool,
+			tx_lanes,
+		)),
+		prometheus_registry.as_ref(),
+		telemetry.as_ref().map(|telemetry| telemetry.handle()),
+	);
+
+	let seal = |create_empty| EngineCommand::SealNewBlock {
+		create_empty,
+		finalize: true,
+		parent_hash: None,
+		sender: None,
+	};
+	let commands_stream = match sealing {
+		Sealing::Manual => commands_stream.boxed(),
+		Sealing::Instant => stream::select(
+			commands_stream,
+			transaction_pool.import_notification_stream().map(move |_| seal(false)),
+		)
+		.boxed(),
+		Sealing::Interval(millis) => {
+			let interval = Duration::from_millis(millis);
+			let ticks = stream::unfold((), move |()| async move {
+				tokio::time::sleep(interval).await;
+				Some((seal(true), ()))
+			});
+			stream::select(commands_stream, ticks).boxed()
+		},
+	};
+
+	let create_inherent_data_providers = {
+		let client = client.clone();
+		move |parent: Hash, ()| {
+			let client = client.clone();
+			async move {
+				let header = client.header(parent)?.ok_or("parent block not found")?;
+
+				// One slot past the parent at least, on the slot of the wall clock when idle.
+				let slot_millis = slot_duration.as_millis();
+				let parent_millis: u64 =
+					storage_value(&client, parent, b"Timestamp", b"Now")?.unwrap_or_default();
+				let now = sp_timestamp::Timestamp::current().as_millis();
+				let millis = (parent_millis + slot_millis).max(now) / slot_millis * slot_millis;
+				let timestamp = sp_timestamp::InherentDataProvider::new(millis.into());
+
+				let relay_slot =
+					Slot::from(millis / fintradex_runtime::RELAY_CHAIN_SLOT_DURATION_MILLIS as u64);
+				let pending_code: Option<Vec<u8>> =
+					storage_value(&client, parent, b"ParachainSystem", b"PendingValidationCode")?;
+				let parachain = MockValidationDataInherentDataProvider {
+					current_para_block: *header.number(),
+					para_id,
+					current_para_block_head: Some(HeadData(header.encode())),
+					relay_offset: 1000,
+					relay_blocks_per_para_block: 1,
+					para_blocks_per_relay_epoch: 10,
+					relay_randomness_config: (),
+					xcm_config: MockXcmConfig::new(&*client, parent, Default::default()),
+					raw_downward_messages: vec![],
+					raw_horizontal_messages: vec![],
+					additional_key_values: Some(vec![(
+						well_known_keys::CURRENT_SLOT.to_vec(),
+						relay_slot.encode(),
+					)]),
+					// Runtime upgrades are enacted right away.
+					upgrade_go_ahead: pending_code
+						.is_some_and(|code| !code.is_empty())
+						.then_some(UpgradeGoAhead::GoAhead),
+				};
+
+				let dynamic_fee =
+					fp_dynamic_fee::InherentDataProvider(U256::from(target_gas_price));
+				Ok::<_, Box<dyn std::error::Error + Send + Sync>>((
+					timestamp,
+					parachain,
+					dynamic_fee,
+				))
+			}
+		}
+	};
+
+	task_manager.spawn_essential_handle().spawn_blocking(
+		"manual-seal",
+		None,
+		sc_consensus_manual_seal::run_manual_seal(ManualSealParams {
+			block_import,
+			env: proposer_factory,
+			client: client.clone(),
+			pool: transaction_pool,
+			commands_stream,
+			select_chain: sc_consensus::LongestChain::new(backend),
+			consensus_data_provider: Some(Box::new(AuraConsensusDataProvider::new(client))),
+			create_inherent_data_providers,
+		}),
+	);
+
+	log::info!("Sealing blocks {sealing:?} for parachain {para_id:?}, without a relay chain");
+	start_network.start_network();
+
+	Ok(task_manager)
+}
@@ -0,0 +1,550 @@
+//! Postgres backend for the Frontier mapping and log index.
+//!
+//! Several RPC nodes can share one database. Every node reads from it, and one of them at a time
+//! indexes imported blocks into it: the indexer holds a session-level advisory lock, so when its
+//! node goes away the lock is released and another node's indexer takes over. Nodes started with
+//! `--frontier-postgres-read-only` never index and never migrate the schema.
+//!
+//! The indexer checks the connection holding the lock every [`LOCK_CHECK_INTERVAL`]; when it is
+//! lost, the lock may be taken by another node, so the indexer stops and waits for the lock again.
+//! On taking the lock it indexes the canonical blocks missing from the database.
+
+use std::{
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+use codec::{Decode, Encode};
+use fc_api::{FilteredLog, LogIndexerBackend, TransactionMetadata};
+use fc_rpc::OverrideHandle;
+use fintradex_runtime::opaque::Block;
+use fp_storage::EthereumStorageSchema;
+use futures::StreamExt;
+use jsonrpsee::core::async_trait;
+use sc_client_api::{backend::Backend as BackendT, BlockchainEvents, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_core::{H160, H256};
+use sqlx::{
+	migrate::Migrator,
+	postgres::{PgConnection, PgPool, PgPoolOptions, PgRow},
+	Postgres, QueryBuilder, Row,
+};
+
+/// Schema migrations, applied on startup by nodes that may index.
+pub static MIGRATOR: Migrator = sqlx::migrate!("migrations/postgres");
+
+/// Key of the advisory lock held by the indexing node.
+const INDEXER_LOCK: i64 = 0x6672_6f6e_7469_6572;
+
+/// Interval between the checks of the connection holding the indexer lock.
+const LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A block as stored in the index.
+#[derive(Clone, Debug)]
+pub struct IndexedBlock {
+	pub substrate_block_hash: H256,
+	pub ethereum_block_hash: H256,
+	pub number: u32,
+	pub schema: EthereumStorageSchema,
+	pub is_canon: bool,
+	/// Hash and logs of each Ethereum transaction, in block order.
+	pub transactions: Vec<(H256, Vec<ethereum::Log>)>,
+}
+
+#[derive(Clone)]
+pub struct Backend {
+	pool: PgPool,
+	read_only: bool,
+	/// Maximum number of logs a filter returns, `--max-past-logs`.
+	max_logs: u32,
+}
+
+fn hash(row: &PgRow, column: &str) -> Result<H256, sqlx::Error> {
+	let bytes: Vec<u8> = row.try_get(column)?;
+	if bytes.len() != 32 {
+		return Err(sqlx::Error::Decode(format!("{column} is not a 32-byte hash").into()));
+	}
+	Ok(H256::from_slice(&bytes))
+}
+
+impl Backend {
+	/// Connect to `url`, then migrate the schema, or check that it is migrated when `read_only`.
+	pub async fn connect(
+		url: &str,
+		pool_size: u32,
+		read_only: bool,
+		max_logs: u32,
+	) -> Result<Self, sqlx::Error> {
+		let pool = PgPoolOptions::new().max_connections(pool_size).connect(url).await?;
+		if read_only {
+			let latest = MIGRATOR.iter().map(|migration| migration.version).max();
+			let applied: Option<i64> =
+				sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success")
+					.fetch_one(&pool)
+					.await?;
+			if applied < latest {
+				return Err(sqlx::Error::Configuration(
+					"the schema is not migrated, start an indexing node first".into(),
+				));
+			}
+		} else {
+			MIGRATOR.run(&pool).await?;
+		}
+		Ok(Self { pool, read_only, max_logs })
+	}
+
+	pub fn is_read_only(&self) -> bool {
+		self.read_only
+	}
+
+	/// Store `block`, replacing what was indexed for it before.
+	pub async fn insert_block(&self, block: &IndexedBlock) -> Result<(), sqlx::Error> {
+		let hash = block.substrate_block_hash.as_bytes();
+		let mut tx = self.pool.begin().await?;
+		// Cascades to the transactions and logs of the block.
+		sqlx::query("DELETE FROM blocks WHERE substrate_block_hash = $1")
+			.bind(hash)
+			.execute(&mut *tx)
+			.await?;
+		sqlx::query(
+			"INSERT INTO blocks (substrate_block_hash, ethereum_block_hash, block_number, \
+			 ethereum_storage_schema, is_canon) VALUES ($1, $2, $3, $4, $5)",
+		)
+		.bind(hash)
+		.bind(block.ethereum_block_hash.as_bytes())
+		.bind(i64::from(block.number))
+		.bind(block.schema.encode())
+		.bind(block.is_canon)
+		.execute(&mut *tx)
+		.await?;
+
+		for (index, (transaction_hash, logs)) in block.transactions.iter().enumerate() {
+			sqlx::query(
+				"INSERT INTO transactions (ethereum_transaction_hash, substrate_block_hash, \
+				 ethereum_block_hash, ethereum_transaction_index) VALUES ($1, $2, $3, $4)",
+			)
+			.bind(transaction_hash.as_bytes())
+			.bind(hash)
+			.bind(block.ethereum_block_hash.as_bytes())
+			.bind(index as i32)
+			.execute(&mut *tx)
+			.await?;
+
+			if logs.is_empty() {
+				continue;
+			}
+			let mut insert = QueryBuilder::<Postgres>::new(
+				"INSERT INTO logs (substrate_block_hash, transaction_index, log_index, address, \
+				 topic_1, topic_2, topic_3, topic_4) ",
+			);
+			insert.push_values(logs.iter().enumerate(), |mut row, (log_index, log)| {
+				let topic = |i: usize| log.topics.get(i).map(|topic| topic.as_bytes().to_vec());
+				row.push_bind(hash)
+					.push_bind(index as i32)
+					.push_bind(log_index as i32)
+					.push_bind(log.address.as_bytes().to_vec())
+					.push_bind(topic(0))
+					.push_bind(topic(1))
+					.push_bind(topic(2))
+					.push_bind(topic(3));
+			});
+			insert.build().execute(&mut *tx).await?;
+		}
+		tx.commit().await
+	}
+
+	/// Move the canonical chain off `retracted` and onto `enacted`.
+	pub async fn canonicalize(
+		&self,
+		retracted: &[H256],
+		enacted: &[H256],
+	) -> Result<(), sqlx::Error> {
+		let bytes =
+			|hashes: &[H256]| hashes.iter().map(|h| h.as_bytes().to_vec()).collect::<Vec<_>>();
+		let mut tx = self.pool.begin().await?;
+		sqlx::query("UPDATE blocks SET is_canon = FALSE WHERE substrate_block_hash = ANY($1)")
+			.bind(bytes(retracted))
+			.execute(&mut *tx)
+			.await?;
+		sqlx::query("UPDATE blocks SET is_canon = TRUE WHERE substrate_block_hash = ANY($1)")
+			.bind(bytes(enacted))
+			.execute(&mut *tx)
+			.await?;
+		tx.commit().await
+	}
+
+	/// Numbers up to `to` without a canonical block in the index.
+	pub async fn missing_canon_numbers(&self, to: u32) -> Result<Vec<u32>, sqlx::Error> {
+		let numbers: Vec<i64> = sqlx::query_scalar(
+			"SELECT n FROM generate_series(0, $1::BIGINT) n WHERE NOT EXISTS \
+			 (SELECT 1 FROM blocks WHERE is_canon AND block_number = n) ORDER BY n",
+		)
+		.bind(i64::from(to))
+		.fetch_all(&self.pool)
+		.await?;
+		Ok(numbers.into_iter().map(|number| number as u32).collect())
+	}
+
+	async fn canon_block_hash(&self, order: &str) -> Result<H256, String> {
+		let row = sqlx::query(&format!(
+			"SELECT substrate_block_hash FROM blocks WHERE is_canon \
+			 ORDER BY block_number {order} LIMIT 1"
+		))
+		.fetch_optional(&self.pool)
+		.await
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| "no block indexed".to_string())?;
+		hash(&row, "substrate_block_hash").map_err(|e| e.to_string())
+	}
+}
+
+#[async_trait]
+impl fc_api::Backend<Block> for Backend {
+	async fn block_hash(&self, ethereum_block_hash: &H256) -> Result<Option<Vec<H256>>, String> {
+		let rows =
+			sqlx::query("SELECT substrate_block_hash FROM blocks WHERE ethereum_block_hash = $1")
+				.bind(ethereum_block_hash.as_bytes())
+				.fetch_all(&self.pool)
+				.await
+				.map_err(|e| e.to_string())?;
+		let hashes = rows
+			.iter()
+			.map(|row| hash(row, "substrate_block_hash"))
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|e| e.to_string())?;
+		Ok((!hashes.is_empty()).then_some(hashes))
+	}
+
+	async fn transaction_metadata(
+		&self,
+		ethereum_transaction_hash: &H256,
+	) -> Result<Vec<TransactionMetadata<Block>>, String> {
+		let rows = sqlx::query(
+			"SELECT substrate_block_hash, ethereum_block_hash, ethereum_transaction_index \
+			 FROM transactions WHERE ethereum_transaction_hash = $1",
+		)
+		.bind(ethereum_transaction_hash.as_bytes())
+		.fetch_all(&self.pool)
+		.await
+		.map_err(|e| e.to_string())?;
+		rows.iter()
+			.map(|row| {
+				Ok(TransactionMetadata {
+					substrate_block_hash: hash(row, "substrate_block_hash")?,
+					ethereum_block_hash: hash(row, "ethereum_block_hash")?,
+					ethereum_index: row.try_get::<i32, _>("ethereum_transaction_index")? as u32,
+				})
+			})
+			.collect::<Result<_, sqlx::Error>>()
+			.map_err(|e| e.to_string())
+	}
+
+	fn log_indexer(&self) -> &dyn LogIndexerBackend<Block> {
+		self
+	}
+
+	async fn first_block_hash(&self) -> Result<H256, String> {
+		self.canon_block_hash("ASC").await
+	}
+
+	async fn latest_block_hash(&self) -> Result<H256, String> {
+		self.canon_block_hash("DESC").await
+	}
+}
+
+#[async_trait]
+impl LogIndexerBackend<Block> for Backend {
+	fn is_indexed(&self) -> bool {
+		true
+	}
+
+	async fn filter_logs(
+		&self,
+		from_block: u64,
+		to_block: u64,
+		addresses: Vec<H160>,
+		topics: Vec<Vec<Option<H256>>>,
+	) -> Result<Vec<FilteredLog<Block>>, String> {
+		let mut query = QueryBuilder::<Postgres>::new(
+			"SELECT l.substrate_block_hash, b.ethereum_block_hash, b.block_number, \
+			 b.ethereum_storage_schema, l.transaction_index, l.log_index \
+			 FROM logs l JOIN blocks b ON b.substrate_block_hash = l.substrate_block_hash \
+			 WHERE b.is_canon AND b.block_number BETWEEN ",
+		);
+		query.push_bind(from_block as i64).push(" AND ").push_bind(to_block as i64);
+		if !addresses.is_empty() {
+			query
+				.push(" AND l.address = ANY(")
+				.push_bind(addresses.iter().map(|a| a.as_bytes().to_vec()).collect::<Vec<_>>())
+				.push(")");
+		}
+		// Each entry is one combination of topics, any of them matches.
+		let combinations: Vec<_> =
+			topics.iter().filter(|topics| topics.iter().any(Option::is_some)).collect();
+		if !combinations.is_empty() {
+			query.push(" AND (");
+			for (i, combination) in combinations.into_iter().enumerate() {
+				query.push(if i == 0 { "(TRUE" } else { " OR (TRUE" });
+				for (position, topic) in combination.iter().enumerate().take(4) {
+					if let Some(topic) = topic {
+						query
+							.push(format!(" AND l.topic_{} = ", position + 1))
+							.push_bind(topic.as_bytes().to_vec());
+					}
+				}
+				query.push(")");
+			}
+			query.push(")");
+		}
+		// One more than allowed, to tell a full result from a truncated one.
+		query
+			.push(" ORDER BY b.block_number, l.transaction_index, l.log_index LIMIT ")
+			.push_bind(i64::from(self.max_logs) + 1);
+
+		let rows = query.build().fetch_all(&self.pool).await.map_err(|e| e.to_string())?;
+		if rows.len() > self.max_logs as usize {
+			return Err(format!("query returned more than {} results", self.max_logs));
+		}
+		rows.iter()
+			.map(|row| {
+				let schema: Vec<u8> = row.try_get("ethereum_storage_schema")?;
+				Ok(FilteredLog {
+					substrate_block_hash: hash(row, "substrate_block_hash")?,
+					ethereum_block_hash: hash(row, "ethereum_block_hash")?,
+					block_number: row.try_get::<i64, _>("block_number")? as u32,
+					ethereum_storage_schema: EthereumStorageSchema::decode(&mut &schema[..])
+						.map_err(|e| sqlx::Error::Decode(e.into()))?,
+					transaction_index: row.try_get::<i32, _>("transaction_index")? as u32,
+					log_index: row.try_get::<i32, _>("log_index")? as u32,
+				})
+			})
+			.collect::<Result<_, sqlx::Error>>()
+			.map_err(|e| e.to_string())
+	}
+}
+
+/// The Ethereum block of `hash`, `None` if it has none.
+pub fn read_block<C, BE>(
+	client: &C,
+	overrides: &OverrideHandle<Block>,
+	hash: H256,
+	is_canon: bool,
+) -> Option<IndexedBlock>
+where
+	C: StorageProvider<Block, BE>,
+	BE: BackendT<Block>,
+{
+	let schema = fc_storage::onchain_storage_schema::<Block, C, BE>(client, hash);
+	let handler = overrides.schemas.get(&schema).unwrap_or(&overrides.fallback);
+	let block = handler.current_block(hash)?;
+	let statuses = handler.current_transaction_statuses(hash).unwrap_or_default();
+	Some(IndexedBlock {
+		substrate_block_hash: hash,
+		ethereum_block_hash: block.header.hash(),
+		number: block.header.number.low_u32(),
+		schema,
+		is_canon,
+		transactions: statuses
+			.into_iter()
+			.map(|status| (status.transaction_hash, status.logs))
+			.collect(),
+	})
+}
+
+/// Take the indexer lock, returning the connection holding it.
+async fn lock(backend: &Backend) -> PgConnection {
+	loop {
+		let acquired = async {
+			let mut connection = backend.pool.acquire().await?;
+			let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+				.bind(INDEXER_LOCK)
+				.fetch_one(&mut *connection)
+				.await?;
+			// The lock lives as long as the connection, which the pool must not recycle.
+			Ok::<_, sqlx::Error>(locked.then(|| connection.detach()))
+		};
+		match acquired.await {
+			Ok(Some(connection)) => return connection,
+			Ok(None) => log::debug!(target: "frontier-postgres", "Another node is indexing"),
+			Err(e) => log::warn!(target: "frontier-postgres", "Cannot take the indexer lock: {e}"),
+		}
+		tokio::time::sleep(Duration::from_secs(30)).await;
+	}
+}
+
+/// Index the imported blocks while this node holds the indexer lock.
+pub async fn run_indexer<C, BE>(
+	client: Arc<C>,
+	overrides: Arc<OverrideHandle<Block>>,
+	backend: Backend,
+) where
+	C: StorageProvider<Block, BE> + HeaderBackend<Block> + BlockchainEvents<Block>,
+	C: Send + Sync + 'static,
+	BE: BackendT<Block> + 'static,
+{
+	let index = |hash: H256, is_canon: bool| {
+		let backend = backend.clone();
+		let block = read_block::<C, BE>(client.as_ref(), &overrides, hash, is_canon);
+		async move {
+			if let Some(block) = block {
+				if let Err(e) = backend.insert_block(&block).await {
+					log::warn!(target: "frontier-postgres", "Cannot index block {hash}: {e}");
+				}
+			}
+		}
+	};
+
+	loop {
+		let mut lock = lock(&backend).await;
+		log::info!(target: "frontier-postgres", "Indexing into Postgres");
+
+		// Subscribe first so no block falls between the catch-up and the notifications.
+		let mut imports = client.import_notification_stream();
+		let missing = match backend.missing_canon_numbers(client.info().best_number).await {
+			Ok(missing) => missing,
+			Err(e) => {
+				log::error!(target: "frontier-postgres", "Cannot read the indexed blocks: {e}");
+				return;
+			},
+		};
+		for number in missing {
+			if let Ok(Some(hash)) = client.hash(number) {
+				index(hash, true).await;
+			}
+		}
+
+		let mut checked = Instant::now();
+		loop {
+			match tokio::time::timeout(LOCK_CHECK_INTERVAL, imports.next()).await {
+				Ok(Some(notification)) => {
+					index(notification.hash, notification.is_new_best).await;
+					if let Some(tree_route) = &notification.tree_route {
+						let retracted: Vec<_> =
+							tree_route.retracted().iter().map(|b| b.hash).collect();
+						let enacted: Vec<_> = tree_route.enacted().iter().map(|b| b.hash).collect();
+						if let Err(e) = backend.canonicalize(&retracted, &enacted).await {
+							log::warn!(
+								target: "frontier-postgres",
+								"Cannot update the canonical chain: {e}",
+							);
+						}
+					}
+				},
+				Ok(None) => {
+					// Imports only end with the node, let another one take over.
+					let _ = sqlx::query("SELECT pg_advisory_unlock($1)")
+						.bind(INDEXER_LOCK)
+						.execute(&mut lock)
+						.await;
+					return;
+				},
+				Err(_) => {},
+			}
+			if checked.elapsed() >= LOCK_CHECK_INTERVAL {
+				checked = Instant::now();
+				if let Err(e) = sqlx::query("SELECT 1").execute(&mut lock).await {
+					log::warn!(
+						target: "frontier-postgres",
+						"Lost the connection holding the indexer lock: {e}",
+					);
+					break;
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use fc_api::Backend as _;
+
+	/// The tests share the database, one at a time.
+	static DATABASE: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+	/// Runs against the database at `FRONTIER_POSTGRES_TEST_URL`, skipped when unset.
+	async fn backend() -> Option<(tokio::sync::MutexGuard<'static, ()>, Backend)> {
+		let url = std::env::var("FRONTIER_POSTGRES_TEST_URL").ok()?;
+		let guard = DATABASE.lock().await;
+		let backend = Backend::connect(&url, 4, false, 3).await.expect("connects to Postgres");
+		sqlx::query("TRUNCATE blocks CASCADE").execute(&backend.pool).await.unwrap();
+		Some((guard, backend))
+	}
+
+	fn block(seed: u8, number: u32, is_canon: bool, logs: Vec<ethereum::Log>) -> IndexedBlock {
+		IndexedBlock {
+			substrate_block_hash: H256::repeat_byte(seed),
+			ethereum_block_hash: H256::repeat_byte(seed + 0x80),
+			number,
+			schema: EthereumStorageSchema::V3,
+			is_canon,
+			transactions: vec![(H256::from_low_u64_be(seed.into()), logs)],
+		}
+	}
+
+	fn log(address: u64, topic: u64) -> ethereum::Log {
+		ethereum::Log {
+			address: H160::from_low_u64_be(address),
+			topics: vec![H256::from_low_u64_be(topic)],
+			data: Vec::new(),
+		}
+	}
+
+	#[tokio::test]
+	async fn indexes_and_filters_canonical_logs() {
+		let Some((_guard, backend)) = backend().await else { return };
+		backend.insert_block(&block(1, 1, true, vec![log(1, 10), log(2, 20)])).await.unwrap();
+		backend.insert_block(&block(2, 2, true, vec![log(1, 20)])).await.unwrap();
+		// A fork of block 2.
+		backend.insert_block(&block(3, 2, false, vec![log(1, 10)])).await.unwrap();
+
+		let logs =
+			backend.filter_logs(0, 10, vec![H160::from_low_u64_be(1)], vec![]).await.unwrap();
+		let found: Vec<_> = logs.iter().map(|log| (log.block_number, log.log_index)).collect();
+		assert_eq!(found, vec![(1, 0), (2, 0)]);
+
+		let topic = vec![vec![Some(H256::from_low_u64_be(20))]];
+		let logs = backend.filter_logs(0, 10, vec![], topic).await.unwrap();
+		let found: Vec<_> = logs.iter().map(|log| (log.block_number, log.log_index)).collect();
+		assert_eq!(found, vec![(1, 1), (2, 0)]);
+
+		backend.canonicalize(&[H256::repeat_byte(2)], &[H256::repeat_byte(3)]).await.unwrap();
+		let topic = vec![vec![Some(H256::from_low_u64_be(10))]];
+		let logs = backend.filter_logs(2, 2, vec![], topic).await.unwrap();
+		assert_eq!(logs.len(), 1);
+		assert_eq!(logs[0].substrate_block_hash, H256::repeat_byte(3));
+		assert_eq!(backend.latest_block_hash().await.unwrap(), H256::repeat_byte(3));
+
+		let metadata = backend.transaction_metadata(&H256::from_low_u64_be(1)).await.unwrap();
+		assert_eq!(metadata.len(), 1);
+		assert_eq!(metadata[0].ethereum_block_hash, H256::repeat_byte(0x81));
+
+		// Indexing a block again replaces its rows.
+		backend.insert_block(&block(1, 1, true, vec![log(2, 20)])).await.unwrap();
+		let logs = backend.filter_logs(1, 1, vec![], vec![]).await.unwrap();
+		assert_eq!(logs.len(), 1);
+		assert_eq!(backend.block_hash(&H256::repeat_byte(0x81)).await.unwrap().unwrap().len(), 1);
+	}
+
+	#[tokio::test]
+	async fn lists_the_missing_canonical_blocks() {
+		let Some((_guard, backend)) = backend().await else { return };
+		backend.insert_block(&block(1, 0, true, vec![])).await.unwrap();
+		backend.insert_block(&block(2, 2, true, vec![])).await.unwrap();
+		// Only a fork of block 3 is indexed.
+		backend.insert_block(&block(3, 3, false, vec![])).await.unwrap();
+
+		assert_eq!(backend.missing_canon_numbers(4).await.unwrap(), vec![1, 3, 4]);
+	}
+
+	#[tokio::test]
+	async fn too_many_logs_is_an_error() {
+		let Some((_guard, backend)) = backend().await else { return };
+		backend.insert_block(&block(1, 1, true, vec![log(1, 10), log(1, 10)])).await.unwrap();
+		backend.insert_block(&block(2, 2, true, vec![log(1, 10), log(1, 10)])).await.unwrap();
+
+		assert_eq!(backend.filter_logs(1, 1, vec![], vec![]).await.unwrap().len(), 2);
+		let error = backend.filter_logs(1, 2, vec![], vec![]).await.unwrap_err();
+		assert_eq!(error, "query returned more than 3 results");
+	}
+}
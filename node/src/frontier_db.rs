@@ -0,0 +1,253 @@
+//! `frontier-db` on the SQL backend, and `frontier-db-reindex` on every backend.
+//!
+//! [`fc_cli::FrontierDbCmd`] only knows the key-value mapping database. On the SQL backend its
+//! operations map onto the `blocks`, `transactions`, `logs` and `sync_status` tables: reads
+//! query the mapping, writes re-derive the rows of a block from the chain instead of taking them
+//! verbatim, and deletes drop the rows so the mapping-sync worker indexes the block again.
+
+use std::{io::Read, path::PathBuf, str::FromStr, sync::Arc};
+
+use fc_api::Backend as _;
+use fc_cli::{Column, FrontierDbCmd, Operation};
+use fc_rpc::OverrideHandle;
+use fintradex_runtime::opaque::Block;
+use sc_cli::{CliConfiguration, SharedParams};
+use sc_client_api::{backend::Backend as BackendT, StorageProvider};
+use serde_json::json;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+
+use crate::eth::{postgres, FrontierBackend};
+
+/// Re-index a range of blocks in the Frontier mapping database.
+#[derive(Debug, clap::Parser)]
+pub struct FrontierDbReindexCmd {
+	/// First block to index.
+	#[arg(long)]
+	pub from: u32,
+
+	/// Last block to index, the best block by default.
+	#[arg(long)]
+	pub to: Option<u32>,
+
+	#[allow(missing_docs)]
+	#[command(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl CliConfiguration for FrontierDbReindexCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+fn error(message: impl Into<String>) -> sc_cli::Error {
+	sc_cli::Error::Input(message.into())
+}
+
+fn sql_error(e: impl std::fmt::Display) -> sc_cli::Error {
+	sc_cli::Error::Application(format!("SQL backend: {e}").into())
+}
+
+fn parse_hash(key: &str) -> sc_cli::Result<H256> {
+	H256::from_str(key).map_err(|_| error(format!("`{key}` is not a 32-byte hex hash")))
+}
+
+/// The value to write, from `--value` or stdin.
+fn read_value(path: &Option<PathBuf>) -> sc_cli::Result<H256> {
+	let value = match path {
+		Some(path) => std::fs::read_to_string(path)?,
+		None => {
+			let mut value = String::new();
+			std::io::stdin().read_to_string(&mut value)?;
+			value
+		},
+	};
+	serde_json::from_str(value.trim())
+		.map_err(|_| error("the value must be a JSON string of the substrate block hash"))
+}
+
+/// Run `cmd` against the SQL backend.
+pub fn run_sql<C, BE>(
+	cmd: &FrontierDbCmd,
+	client: Arc<C>,
+	backend: &fc_db::sql::Backend<Block>,
+) -> sc_cli::Result<()>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + StorageProvider<Block, BE>,
+	C: Send + Sync + 'static,
+	BE: BackendT<Block> + 'static,
+{
+	futures::executor::block_on(async {
+		match (&cmd.operation, &cmd.column) {
+			(Operation::Read, Column::Meta) => {
+				let latest = backend.latest_block_hash().await.map_err(sql_error)?;
+				let missing = backend.get_first_missing_canonical_block().await;
+				println!(
+					"{}",
+					json!({ "latestIndexedBlock": latest, "firstMissingCanonicalBlock": missing })
+				);
+			},
+			(_, Column::Meta) => {
+				return Err(error(
+					"the SQL backend keeps no meta column, use frontier-db-reindex to resync",
+				))
+			},
+			(Operation::Read, Column::Block) => {
+				let hash = parse_hash(&cmd.key)?;
+				let substrate_hashes = backend.block_hash(&hash).await.map_err(sql_error)?;
+				println!("{}", json!(substrate_hashes));
+			},
+			(Operation::Create | Operation::Update, Column::Block) => {
+				let hash = parse_hash(&cmd.key)?;
+				let substrate_hash = read_value(&cmd.value)?;
+				reindex_sql_block::<C, BE>(client.clone(), backend, substrate_hash).await?;
+				match backend.block_hash(&hash).await.map_err(sql_error)? {
+					Some(hashes) if hashes.contains(&substrate_hash) => {},
+					_ => {
+						return Err(error(format!(
+							"block {substrate_hash:?} is not the ethereum block {hash:?}"
+						)))
+					},
+				}
+			},
+			(Operation::Delete, Column::Block) => {
+				let hash = parse_hash(&cmd.key)?;
+				let substrate_hashes = backend.block_hash(&hash).await.map_err(sql_error)?;
+				for substrate_hash in substrate_hashes.unwrap_or_default() {
+					delete_sql_block(backend, substrate_hash).await?;
+				}
+			},
+			(Operation::Read, Column::Transaction) => {
+				let hash = parse_hash(&cmd.key)?;
+				let metadata = backend.transaction_metadata(&hash).await.map_err(sql_error)?;
+				let metadata: Vec<_> = metadata
+					.into_iter()
+					.map(|metadata| {
+						json!({
+							"substrateBlockHash": metadata.substrate_block_hash,
+							"ethereumBlockHash": metadata.ethereum_block_hash,
+							"ethereumIndex": metadata.ethereum_index,
+						})
+					})
+					.collect();
+				println!("{}", json!(metadata));
+			},
+			(Operation::Create | Operation::Update, Column::Transaction) => {
+				return Err(error(
+					"transactions are indexed with their block, write the block instead",
+				))
+			},
+			(Operation::Delete, Column::Transaction) => {
+				let hash = parse_hash(&cmd.key)?;
+				sqlx::query("DELETE FROM transactions WHERE ethereum_transaction_hash = ?")
+					.bind(hash.as_bytes())
+					.execute(backend.pool())
+					.await
+					.map_err(sql_error)?;
+			},
+		}
+		Ok(())
+	})
+}
+
+/// Drop the mapping rows of `hash`.
+async fn delete_sql_block(backend: &fc_db::sql::Backend<Block>, hash: H256) -> sc_cli::Result<()> {
+	let mut tx = backend.pool().begin().await.map_err(sql_error)?;
+	for table in ["logs", "transactions", "blocks", "sync_status"] {
+		sqlx::query(&format!("DELETE FROM {table} WHERE substrate_block_hash = ?"))
+			.bind(hash.as_bytes())
+			.execute(&mut *tx)
+			.await
+			.map_err(sql_error)?;
+	}
+	tx.commit().await.map_err(sql_error)
+}
+
+/// Replace the mapping rows and logs of `hash` with the ones derived from the chain.
+async fn reindex_sql_block<C, BE>(
+	client: Arc<C>,
+	backend: &fc_db::sql::Backend<Block>,
+	hash: H256,
+) -> sc_cli::Result<()>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + StorageProvider<Block, BE>,
+	C: Send + Sync + 'static,
+	BE: BackendT<Block> + 'static,
+{
+	if client.header(hash)?.is_none() {
+		return Err(error(format!("block {hash:?} not found")));
+	}
+	delete_sql_block(backend, hash).await?;
+	backend.insert_block_metadata::<C, BE>(client.clone(), hash).await.map_err(sql_error)?;
+	backend.index_block_logs::<C, BE>(client, hash).await;
+	Ok(())
+}
+
+/// Re-index the canonical block `number`.
+pub fn reindex_block<C, BE>(
+	client: &Arc<C>,
+	backend: &FrontierBackend,
+	overrides: &Arc<OverrideHandle<Block>>,
+	number: u32,
+) -> sc_cli::Result<()>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + StorageProvider<Block, BE>,
+	C: Send + Sync + 'static,
+	BE: BackendT<Block> + 'static,
+{
+	let hash = client.hash(number)?.ok_or_else(|| error(format!("block #{number} not found")))?;
+	match backend {
+		FrontierBackend::KeyValue(backend) => {
+			let header =
+				client.header(hash)?.ok_or_else(|| error(format!("block #{number} not found")))?;
+			fc_mapping_sync::kv::sync_block(client.as_ref(), overrides.clone(), backend, &header)
+				.map_err(|e| error(format!("block #{number}: {e}")))?;
+		},
+		FrontierBackend::Sql(backend) => {
+			futures::executor::block_on(reindex_sql_block::<C, BE>(
+				client.clone(),
+				backend,
+				hash,
+			))?;
+		},
+		FrontierBackend::Postgres(backend) => {
+			if backend.is_read_only() {
+				return Err(error("cannot re-index with --frontier-postgres-read-only"));
+			}
+			let block = postgres::read_block::<C, BE>(client.as_ref(), overrides, hash, true);
+			if let Some(block) = block {
+				futures::executor::block_on(backend.insert_block(&block)).map_err(sql_error)?;
+			}
+		},
+	}
+	Ok(())
+}
+
+/// Re-index the canonical blocks `cmd.from..=cmd.to`.
+pub fn run_reindex<C, BE>(
+	cmd: &FrontierDbReindexCmd,
+	client: Arc<C>,
+	backend: FrontierBackend,
+	overrides: Arc<OverrideHandle<Block>>,
+) -> sc_cli::Result<()>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + StorageProvider<Block, BE>,
+	C: Send + Sync + 'static,
+	BE: BackendT<Block> + 'static,
+{
+	let to = cmd.to.unwrap_or(client.info().best_number);
+	if cmd.from > to {
+		return Err(error(format!("--from {} is after --to {to}", cmd.from)));
+	}
+
+	for number in cmd.from..=to {
+		reindex_block::<C, BE>(&client, &backend, &overrides, number)?;
+		if number % 1000 == 0 {
+			log::info!("Re-indexed block #{number}");
+		}
+	}
+	log::info!("Re-indexed blocks #{}..=#{to}", cmd.from);
+	Ok(())
+}
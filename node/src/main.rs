@@ -3,12 +3,19 @@
 #![warn(missing_docs)]
 
 mod chain_spec;
+mod chain_spec_cmd;
 #[macro_use]
 mod service;
 mod cli;
 mod command;
+mod dev;
 mod eth;
+mod frontier_db;
+mod metrics;
+mod private_pool;
+mod relay;
 mod rpc;
+mod tx_lanes;
 
 fn main() -> sc_cli::Result<()> {
 	command::run()
@@ -0,0 +1,266 @@
+//! Collator metrics and health endpoints.
+//!
+//! [`Metrics`] adds the block production of the collator to the node's Prometheus registry:
+//! the proposals, measured by [`MeteredProposer`], and the imported blocks, followed by
+//! [`spawn_collator_metrics`]. The liveness and readiness probes of the orchestrator call the
+//! `/health` and `/health/readiness` endpoints of the RPC server.
+
+use std::{
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use codec::{Compact, Decode, Encode};
+use cumulus_client_consensus_proposer::{Error as ProposerError, ProposerInterface};
+use cumulus_primitives_parachain_inherent::ParachainInherentData;
+use fintradex_runtime::opaque::Block;
+use fp_rpc::EthereumRuntimeRPCApi;
+use futures::StreamExt;
+use jsonrpsee::core::async_trait;
+use sc_client_api::{BlockchainEvents, StorageProvider};
+use sc_network_sync::SyncingService;
+use sc_service::TaskManager;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::{BlockOrigin, Proposal, SyncOracle};
+use sp_consensus_aura::{
+	digests::CompatibleDigestItem, sr25519::AuthoritySignature, AuraApi, Slot,
+};
+use sp_core::{crypto::ByteArray, storage::StorageKey, twox_128};
+use sp_inherents::InherentData;
+use sp_keystore::KeystorePtr;
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT},
+	Digest, DigestItem,
+};
+use sp_trie::StorageProof;
+use substrate_prometheus_endpoint::{
+	exponential_buckets, register, Counter, Gauge, Histogram, HistogramOpts, PrometheusError,
+	Registry, U64,
+};
+
+use crate::service::ParachainClient;
+
+/// Block production of the collator.
+#[derive(Clone)]
+pub struct Metrics {
+	blocks_authored: Counter<U64>,
+	slots_missed: Counter<U64>,
+	proposal_duration: Histogram,
+	proposal_budget: Histogram,
+	pov_size: Histogram,
+	unincluded_segment: Gauge<U64>,
+	evm_gas: Histogram,
+}
+
+impl Metrics {
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			blocks_authored: register(
+				Counter::new(
+					"fintradex_collator_blocks_authored_total",
+					"Blocks authored by this collator",
+				)?,
+				registry,
+			)?,
+			slots_missed: register(
+				Counter::new(
+					"fintradex_collator_slots_missed_total",
+					"Slots of this collator without a block authored",
+				)?,
+				registry,
+			)?,
+			proposal_duration: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"fintradex_collator_proposal_duration_seconds",
+						"Time spent building a block",
+					)
+					.buckets(exponential_buckets(0.05, 2.0, 8)?),
+				)?,
+				registry,
+			)?,
+			proposal_budget: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"fintradex_collator_proposal_budget_ratio",
+						"Time spent building a block, over the time it was given",
+					)
+					.buckets(vec![0.25, 0.5, 0.75, 0.9, 1.0, 1.1, 1.25, 1.5]),
+				)?,
+				registry,
+			)?,
+			pov_size: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"fintradex_collator_pov_size_bytes",
+						"Size of the blocks built and of their storage proofs",
+					)
+					.buckets(exponential_buckets(16.0 * 1024.0, 2.0, 9)?),
+				)?,
+				registry,
+			)?,
+			unincluded_segment: register(
+				Gauge::new(
+					"fintradex_collator_unincluded_segment_depth",
+					"Blocks of the best chain not yet included in the relay chain",
+				)?,
+				registry,
+			)?,
+			evm_gas: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"fintradex_collator_evm_gas_per_block",
+						"EVM gas used by the best blocks",
+					)
+					.buckets(exponential_buckets(21_000.0, 4.0, 9)?),
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// A proposer recording how long the blocks take to build and how large they are.
+pub struct MeteredProposer<P> {
+	inner: P,
+	metrics: Option<Metrics>,
+}
+
+impl<P> MeteredProposer<P> {
+	pub fn new(inner: P, metrics: Option<Metrics>) -> Self {
+		Self { inner, metrics }
+	}
+}
+
+#[async_trait]
+impl<B, P> ProposerInterface<B> for MeteredProposer<P>
+where
+	B: BlockT,
+	P: ProposerInterface<B> + Send,
+{
+	async fn propose(
+		&mut self,
+		parent_header: &B::Header,
+		paras_inherent_data: &ParachainInherentData,
+		other_inherent_data: InherentData,
+		inherent_digests: Digest,
+		max_duration: Duration,
+		block_size_limit: Option<usize>,
+	) -> Result<Option<Proposal<B, StorageProof>>, ProposerError> {
+		let started = Instant::now();
+		let proposal = self
+			.inner
+			.propose(
+				parent_header,
+				paras_inherent_data,
+				other_inherent_data,
+				inherent_digests,
+				max_duration,
+				block_size_limit,
+			)
+			.await;
+		if let (Some(metrics), Ok(Some(proposal))) = (&self.metrics, &proposal) {
+			let elapsed = started.elapsed().as_secs_f64();
+			metrics.proposal_duration.observe(elapsed);
+			metrics.proposal_budget.observe(elapsed / max_duration.as_secs_f64());
+			metrics
+				.pov_size
+				.observe((proposal.block.encoded_size() + proposal.proof.encoded_size()) as f64);
+		}
+		proposal
+	}
+}
+
+/// Aura slot of the block `header`.
+fn slot_of(header: &<Block as BlockT>::Header) -> Option<Slot> {
+	header.digest().logs().iter().find_map(|log| {
+		<DigestItem as CompatibleDigestItem<AuthoritySignature>>::as_aura_pre_digest(log)
+	})
+}
+
+/// Number of blocks in the unincluded segment of `ParachainSystem` at `hash`.
+fn unincluded_segment_depth(
+	client: &ParachainClient,
+	hash: <Block as BlockT>::Hash,
+) -> Option<u64> {
+	let key = [twox_128(b"ParachainSystem"), twox_128(b"UnincludedSegment")].concat();
+	let segment = client.storage(hash, &StorageKey(key)).ok()??;
+	// Only the length prefix of the segment is read.
+	Compact::<u32>::decode(&mut &segment.0[..]).ok().map(|depth| depth.0.into())
+}
+
+/// Follow the imported blocks: those authored by this collator, and the unincluded segment and
+/// the EVM gas of the best ones, and, given the `keystore` of a collator, the slots of its Aura
+/// keys without a block once the node is synced.
+pub fn spawn_collator_metrics(
+	task_manager: &TaskManager,
+	client: Arc<ParachainClient>,
+	sync_service: Arc<SyncingService<Block>>,
+	keystore: Option<KeystorePtr>,
+	metrics: Metrics,
+) {
+	let last_authored = Arc::new(AtomicU64::new(0));
+
+	if let Some(keystore) = keystore {
+		let client = client.clone();
+		let metrics = metrics.clone();
+		let last_authored = last_authored.clone();
+		task_manager.spawn_handle().spawn("collator-missed-slots", None, async move {
+			let Ok(slot_duration) = sc_consensus_aura::slot_duration(&*client) else {
+				return;
+			};
+			let slot_millis = slot_duration.as_millis();
+			loop {
+				let now = SystemTime::now()
+					.duration_since(UNIX_EPOCH)
+					.expect("the clock is after the epoch; qed")
+					.as_millis() as u64;
+				// The block of the current slot is built on the best block, whose authorities take
+				// turns.
+				let slot = now / slot_millis;
+				let parent = client.info().best_hash;
+				tokio::time::sleep(Duration::from_millis(slot_millis - now % slot_millis)).await;
+				// The slot just ended, its block is imported by now unless the node is syncing.
+				if sync_service.is_major_syncing() {
+					continue;
+				}
+				let Ok(authorities) = client.runtime_api().authorities(parent) else { continue };
+				if authorities.is_empty() {
+					continue;
+				}
+				let author = &authorities[(slot % authorities.len() as u64) as usize];
+				let ours =
+					keystore.has_keys(&[(author.to_raw_vec(), sp_core::crypto::key_types::AURA)]);
+				if ours && last_authored.load(Ordering::Relaxed) < slot {
+					metrics.slots_missed.inc();
+				}
+			}
+		});
+	}
+
+	task_manager.spawn_handle().spawn("collator-metrics", None, async move {
+		let mut imports = client.import_notification_stream();
+		while let Some(notification) = imports.next().await {
+			if notification.origin == BlockOrigin::Own {
+				metrics.blocks_authored.inc();
+				if let Some(slot) = slot_of(&notification.header) {
+					last_authored.fetch_max(*slot, Ordering::Relaxed);
+				}
+			}
+			if !notification.is_new_best {
+				continue;
+			}
+			let hash = notification.hash;
+			if let Some(depth) = unincluded_segment_depth(&*client, hash) {
+				metrics.unincluded_segment.set(depth);
+			}
+			if let Ok(Some(block)) = client.runtime_api().current_block(hash) {
+				metrics.evm_gas.observe(block.header.gas_used.low_u64() as f64);
+			}
+		}
+	});
+}
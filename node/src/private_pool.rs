@@ -0,0 +1,664 @@
+//! Private order flow.
+//!
+//! Transactions sent through `eth_sendBundle` and `eth_sendPrivateTransaction` are kept in a
+//! [`PrivatePool`] instead of the public transaction pool, so they are never gossiped. Only the
+//! local collator sees them: [`ProposerPool`] is the pool handed to its proposer, and lists the
+//! private bundles ahead of the public ready queue, itself ordered by the [`TxLanes`]. Bundles
+//! are dropped once included, once past their last block, or when one of their transactions is
+//! reported invalid.
+//!
+//! Bundles are included on a best-effort basis. A bundle is only proposed when all its
+//! transactions apply in a dry run on top of the parent block and of the bundles before it, so
+//! the bundles proposed fit the weight and length the block leaves to normal transactions. The
+//! dry run has no inherents and an empty digest, though, and the proposer still stops at its
+//! own size limit and deadline: a bundle may then be cut, its transactions before the cut
+//! staying in the block. The proposer skips the rest of a bundle once one of its transactions
+//! fails.
+
+use std::{
+	collections::{HashMap, HashSet},
+	pin::Pin,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+use codec::Encode;
+use futures::StreamExt;
+use jsonrpsee::core::async_trait;
+use sc_client_api::{BlockBackend, BlockchainEvents};
+use sc_service::TaskManager;
+use sp_api::{ApiExt, Core, ProvideRuntimeApi, TransactionOutcome};
+use sp_block_builder::BlockBuilder as BlockBuilderApi;
+use sp_blockchain::HeaderBackend;
+use sc_transaction_pool_api::{
+	ImportNotificationStream, InPoolTransaction, PoolStatus, ReadyTransactions, TransactionFor,
+	TransactionPool, TransactionSource, TransactionStatusStreamFor, TxHash, TxInvalidityReportMap,
+};
+use sp_core::{H160, H256};
+use sp_runtime::{
+	traits::{Block as BlockT, Hash as HashT, HashingFor, Header as HeaderT, UniqueSaturatedInto},
+	transaction_validity::{TransactionLongevity, TransactionPriority, TransactionTag},
+};
+
+use crate::tx_lanes::TxLanes;
+
+/// Maximum number of bundles dry run for a block, in arrival order.
+const MAX_SIMULATED_BUNDLES: usize = 64;
+
+/// A transaction of a private bundle.
+pub struct PrivateTransaction<B: BlockT> {
+	pub extrinsic: Arc<B::Extrinsic>,
+	/// Hash of the extrinsic, as computed by the transaction pool.
+	pub hash: B::Hash,
+}
+
+impl<B: BlockT> PrivateTransaction<B> {
+	pub fn new(extrinsic: B::Extrinsic) -> Self {
+		let hash = HashingFor::<B>::hash_of(&extrinsic);
+		Self { extrinsic: Arc::new(extrinsic), hash }
+	}
+}
+
+/// Transactions to include one after the other, on a best-effort basis.
+pub struct Bundle<B: BlockT> {
+	pub hash: H256,
+	/// Signer of the first transaction, the only one who may cancel the bundle.
+	pub sender: H160,
+	/// Last block the bundle may be included in.
+	pub max_block: u32,
+	pub transactions: Vec<PrivateTransaction<B>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+	/// The pool holds too many transactions to accept the bundle.
+	Full,
+	/// A bundle with the same hash is already pooled.
+	AlreadyPooled,
+	/// The bundle was sent by another account.
+	NotSender,
+}
+
+/// A pooled bundle, as its hash and its transactions with their hashes.
+type PooledBundle<B> = (H256, Vec<(<B as BlockT>::Hash, Arc<<B as BlockT>::Extrinsic>)>);
+
+/// Bundles waiting for the local collator, in arrival order.
+pub struct PrivatePool<B: BlockT> {
+	bundles: Mutex<Vec<Bundle<B>>>,
+	/// Maximum number of transactions over all bundles.
+	capacity: usize,
+}
+
+impl<B: BlockT> PrivatePool<B> {
+	pub fn new(capacity: usize) -> Self {
+		Self { bundles: Mutex::new(Vec::new()), capacity }
+	}
+
+	fn bundles(&self) -> std::sync::MutexGuard<'_, Vec<Bundle<B>>> {
+		self.bundles.lock().expect("private pool lock poisoned")
+	}
+
+	pub fn submit(&self, bundle: Bundle<B>) -> Result<(), Error> {
+		let mut bundles = self.bundles();
+		if bundles.iter().any(|pooled| pooled.hash == bundle.hash) {
+			return Err(Error::AlreadyPooled);
+		}
+		let pooled: usize = bundles.iter().map(|bundle| bundle.transactions.len()).sum();
+		if pooled + bundle.transactions.len() > self.capacity {
+			return Err(Error::Full);
+		}
+		bundles.push(bundle);
+		Ok(())
+	}
+
+	/// Drop the bundle `hash` of `sender`, returning whether it was pooled.
+	pub fn cancel(&self, hash: H256, sender: H160) -> Result<bool, Error> {
+		let mut bundles = self.bundles();
+		let Some(index) = bundles.iter().position(|bundle| bundle.hash == hash) else {
+			return Ok(false);
+		};
+		if bundles[index].sender != sender {
+			return Err(Error::NotSender);
+		}
+		bundles.remove(index);
+		Ok(true)
+	}
+
+	/// Drop the bundles that cannot be included after block `number`, which includes
+	/// the extrinsics `included`.
+	pub fn prune(&self, number: u32, included: &HashSet<B::Hash>) {
+		self.bundles().retain(|bundle| {
+			bundle.max_block > number &&
+				!bundle.transactions.iter().any(|tx| included.contains(&tx.hash))
+		});
+	}
+
+	/// Drop the bundles containing one of `invalid`.
+	fn remove_invalid(&self, invalid: &HashSet<B::Hash>) {
+		self.bundles()
+			.retain(|bundle| !bundle.transactions.iter().any(|tx| invalid.contains(&tx.hash)));
+	}
+
+	fn pooled(&self) -> Vec<PooledBundle<B>> {
+		self.bundles()
+			.iter()
+			.map(|bundle| {
+				let transactions =
+					bundle.transactions.iter().map(|tx| (tx.hash, tx.extrinsic.clone())).collect();
+				(bundle.hash, transactions)
+			})
+			.collect()
+	}
+
+	/// The first [`MAX_SIMULATED_BUNDLES`] pooled bundles whose transactions all apply on top
+	/// of block `at`, each after the bundles before it.
+	fn ready_at<C>(&self, client: &C, at: B::Hash) -> Vec<PooledBundle<B>>
+	where
+		C: ProvideRuntimeApi<B> + HeaderBackend<B>,
+		C::Api: BlockBuilderApi<B>,
+	{
+		let bundles = self.pooled();
+		if bundles.is_empty() {
+			return bundles;
+		}
+		let parent = match client.header(at) {
+			Ok(Some(parent)) => parent,
+			Ok(None) => return Vec::new(),
+			Err(e) => {
+				log::warn!(target: "private-pool", "Cannot read block {at}: {e}");
+				return Vec::new();
+			},
+		};
+		let header = <B::Header as HeaderT>::new(
+			*parent.number() + 1u32.into(),
+			Default::default(),
+			Default::default(),
+			at,
+			Default::default(),
+		);
+		let api = client.runtime_api();
+		if let Err(e) = api.initialize_block(at, &header) {
+			log::warn!(target: "private-pool", "Cannot build on block {at}: {e}");
+			return Vec::new();
+		}
+
+		bundles
+			.into_iter()
+			.take(MAX_SIMULATED_BUNDLES)
+			.filter(|(_, transactions)| {
+				api.execute_in_transaction(|api| {
+					let applied = transactions.iter().all(|(_, extrinsic)| {
+						matches!(api.apply_extrinsic(at, (**extrinsic).clone()), Ok(Ok(Ok(_))))
+					});
+					if applied {
+						TransactionOutcome::Commit(true)
+					} else {
+						TransactionOutcome::Rollback(false)
+					}
+				})
+			})
+			.collect()
+	}
+}
+
+/// A transaction handed to the proposer by [`ProposerPool`].
+pub enum PoolTransaction<T: InPoolTransaction> {
+	Public(Arc<T>),
+	Private { data: T::Transaction, hash: T::Hash, bundle: H256 },
+}
+
+impl<T: InPoolTransaction> InPoolTransaction for PoolTransaction<T> {
+	type Transaction = T::Transaction;
+	type Hash = T::Hash;
+
+	fn data(&self) -> &Self::Transaction {
+		match self {
+			Self::Public(tx) => tx.data(),
+			Self::Private { data, .. } => data,
+		}
+	}
+
+	fn hash(&self) -> &Self::Hash {
+		match self {
+			Self::Public(tx) => tx.hash(),
+			Self::Private { hash, .. } => hash,
+		}
+	}
+
+	fn priority(&self) -> &TransactionPriority {
+		match self {
+			Self::Public(tx) => tx.priority(),
+			Self::Private { .. } => &TransactionPriority::MAX,
+		}
+	}
+
+	fn longevity(&self) -> &TransactionLongevity {
+		match self {
+			Self::Public(tx) => tx.longevity(),
+			Self::Private { .. } => &TransactionLongevity::MAX,
+		}
+	}
+
+	fn requires(&self) -> &[TransactionTag] {
+		match self {
+			Self::Public(tx) => tx.requires(),
+			Self::Private { .. } => &[],
+		}
+	}
+
+	fn provides(&self) -> &[TransactionTag] {
+		match self {
+			Self::Public(tx) => tx.provides(),
+			Self::Private { .. } => &[],
+		}
+	}
+
+	fn is_propagable(&self) -> bool {
+		match self {
+			Self::Public(tx) => tx.is_propagable(),
+			Self::Private { .. } => false,
+		}
+	}
+}
+
+type PublicReady<T> = Box<dyn ReadyTransactions<Item = Arc<T>> + Send>;
+
+/// The private transactions, then the public ones not among them.
+pub struct ProposerReady<T: InPoolTransaction> {
+	private: std::vec::IntoIter<Arc<PoolTransaction<T>>>,
+	private_hashes: HashSet<T::Hash>,
+	/// Bundle whose remaining transactions are skipped, one of its transactions having failed.
+	failed: Option<H256>,
+	public: PublicReady<T>,
+}
+
+impl<T: InPoolTransaction> Iterator for ProposerReady<T>
+where
+	T::Hash: std::hash::Hash + Eq,
+{
+	type Item = Arc<PoolTransaction<T>>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let failed = self.failed;
+		let private = self.private.find(|tx| match &**tx {
+			PoolTransaction::Private { bundle, .. } => Some(*bundle) != failed,
+			PoolTransaction::Public(_) => true,
+		});
+		if private.is_some() {
+			return private;
+		}
+		let private_hashes = &self.private_hashes;
+		self.public
+			.find(|tx| !private_hashes.contains(tx.hash()))
+			.map(|tx| Arc::new(PoolTransaction::Public(tx)))
+	}
+}
+
+impl<T: InPoolTransaction> ReadyTransactions for ProposerReady<T>
+where
+	T::Hash: std::hash::Hash + Eq,
+{
+	fn report_invalid(&mut self, tx: &Self::Item) {
+		match &**tx {
+			PoolTransaction::Public(tx) => self.public.report_invalid(tx),
+			PoolTransaction::Private { bundle, .. } => self.failed = Some(*bundle),
+		}
+	}
+}
+
+/// The transaction pool of the local proposer: the public pool, with the private bundles ahead
+/// of its ready queue, ordered by lane.
+pub struct ProposerPool<P: TransactionPool, C> {
+	client: Arc<C>,
+	public: Arc<P>,
+	private: Arc<PrivatePool<P::Block>>,
+	lanes: Arc<TxLanes>,
+}
+
+impl<P: TransactionPool, C> ProposerPool<P, C> {
+	pub fn new(
+		client: Arc<C>,
+		public: Arc<P>,
+		private: Arc<PrivatePool<P::Block>>,
+		lanes: Arc<TxLanes>,
+	) -> Self {
+		Self { client, public, private, lanes }
+	}
+}
+
+impl<P, C> ProposerPool<P, C>
+where
+	P: TransactionPool<Hash = <<P as TransactionPool>::Block as BlockT>::Hash>,
+	P::InPoolTransaction: Send + Sync + 'static,
+	<P::InPoolTransaction as InPoolTransaction>::Transaction: Encode,
+	C: ProvideRuntimeApi<P::Block> + HeaderBackend<P::Block>,
+	C::Api: BlockBuilderApi<P::Block>,
+{
+	/// The private bundles that apply on top of block `at`, then `public`.
+	fn with_private(
+		&self,
+		at: <P::Block as BlockT>::Hash,
+		public: PublicReady<P::InPoolTransaction>,
+	) -> ReadyIteratorFor<P> {
+		let public = self.lanes.order(public);
+		let private: Vec<_> = self
+			.private
+			.ready_at(&*self.client, at)
+			.into_iter()
+			.flat_map(|(bundle, transactions)| {
+				transactions.into_iter().map(move |(hash, data)| {
+					Arc::new(PoolTransaction::Private { data, hash, bundle })
+				})
+			})
+			.collect();
+		Box::new(ProposerReady {
+			private_hashes: private.iter().map(|tx| *tx.hash()).collect(),
+			private: private.into_iter(),
+			failed: None,
+			public,
+		})
+	}
+}
+
+type ReadyIteratorFor<P> = Box<
+	dyn ReadyTransactions<Item = Arc<PoolTransaction<<P as TransactionPool>::InPoolTransaction>>>
+		+ Send,
+>;
+
+#[async_trait]
+impl<P, C> TransactionPool for ProposerPool<P, C>
+where
+	P: TransactionPool<Hash = <<P as TransactionPool>::Block as BlockT>::Hash> + 'static,
+	P::InPoolTransaction: Send + Sync + 'static,
+	<P::InPoolTransaction as InPoolTransaction>::Transaction: Encode,
+	C: ProvideRuntimeApi<P::Block> + HeaderBackend<P::Block> + Send + Sync + 'static,
+	C::Api: BlockBuilderApi<P::Block>,
+{
+	type Block = P::Block;
+	type Hash = P::Hash;
+	type InPoolTransaction = PoolTransaction<P::InPoolTransaction>;
+	type Error = P::Error;
+
+	async fn submit_at(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		source: TransactionSource,
+		xts: Vec<TransactionFor<Self>>,
+	) -> Result<Vec<Result<TxHash<Self>, Self::Error>>, Self::Error> {
+		self.public.submit_at(at, source, xts).await
+	}
+
+	async fn submit_one(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> Result<TxHash<Self>, Self::Error> {
+		self.public.submit_one(at, source, xt).await
+	}
+
+	async fn submit_and_watch(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> Result<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error> {
+		self.public.submit_and_watch(at, source, xt).await
+	}
+
+	async fn ready_at(&self, at: <Self::Block as BlockT>::Hash) -> ReadyIteratorFor<P> {
+		self.with_private(at, self.public.ready_at(at).await)
+	}
+
+	async fn ready_at_with_timeout(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		timeout: Duration,
+	) -> ReadyIteratorFor<P> {
+		self.with_private(at, self.public.ready_at_with_timeout(at, timeout).await)
+	}
+
+	fn ready(&self) -> ReadyIteratorFor<P> {
+		self.with_private(self.client.info().best_hash, self.public.ready())
+	}
+
+	async fn report_invalid(
+		&self,
+		at: Option<<Self::Block as BlockT>::Hash>,
+		invalid_tx_errors: TxInvalidityReportMap<TxHash<Self>>,
+	) -> Vec<Arc<Self::InPoolTransaction>> {
+		self.private.remove_invalid(&invalid_tx_errors.keys().copied().collect());
+		self.public
+			.report_invalid(at, invalid_tx_errors)
+			.await
+			.into_iter()
+			.map(|tx| Arc::new(PoolTransaction::Public(tx)))
+			.collect()
+	}
+
+	fn futures(&self) -> Vec<Self::InPoolTransaction> {
+		self.public.futures().into_iter().map(|tx| PoolTransaction::Public(Arc::new(tx))).collect()
+	}
+
+	fn status(&self) -> PoolStatus {
+		self.public.status()
+	}
+
+	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>> {
+		self.public.import_notification_stream()
+	}
+
+	fn on_broadcasted(&self, propagations: HashMap<TxHash<Self>, Vec<String>>) {
+		self.public.on_broadcasted(propagations)
+	}
+
+	fn hash_of(&self, xt: &TransactionFor<Self>) -> TxHash<Self> {
+		self.public.hash_of(xt)
+	}
+
+	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
+		self.public.ready_transaction(hash).map(|tx| Arc::new(PoolTransaction::Public(tx)))
+	}
+}
+
+/// Drop the private bundles that were included or expired as new best blocks are imported.
+pub fn spawn_private_pool_pruner<B, C>(
+	task_manager: &TaskManager,
+	client: Arc<C>,
+	private_pool: Arc<PrivatePool<B>>,
+) where
+	B: BlockT,
+	C: BlockchainEvents<B> + BlockBackend<B> + Send + Sync + 'static,
+{
+	task_manager.spawn_handle().spawn("private-pool-pruner", None, async move {
+		let mut imports = client.import_notification_stream();
+		while let Some(notification) = imports.next().await {
+			if !notification.is_new_best {
+				continue;
+			}
+			let included = match client.block_body(notification.hash) {
+				Ok(Some(body)) => body.iter().map(HashingFor::<B>::hash_of).collect(),
+				Ok(None) => HashSet::new(),
+				Err(e) => {
+					log::warn!(
+						target: "private-pool",
+						"Cannot read block {}: {e}",
+						notification.hash,
+					);
+					HashSet::new()
+				},
+			};
+			let number = (*notification.header.number()).unique_saturated_into();
+			private_pool.prune(number, &included);
+		}
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Decode;
+	use fintradex_runtime::opaque::Block;
+
+	const ALICE: H160 = H160::repeat_byte(0xa1);
+	const BOB: H160 = H160::repeat_byte(0xb0);
+
+	fn transaction(n: u8) -> PrivateTransaction<Block> {
+		let extrinsic = Decode::decode(&mut &vec![n].encode()[..]).unwrap();
+		PrivateTransaction::new(extrinsic)
+	}
+
+	fn bundle(id: u8, max_block: u32, transactions: &[u8]) -> Bundle<Block> {
+		Bundle {
+			hash: H256::repeat_byte(id),
+			sender: ALICE,
+			max_block,
+			transactions: transactions.iter().copied().map(transaction).collect(),
+		}
+	}
+
+	fn pooled(pool: &PrivatePool<Block>) -> Vec<H256> {
+		pool.pooled().into_iter().map(|(hash, _)| hash).collect()
+	}
+
+	#[test]
+	fn bundles_are_listed_in_arrival_order() {
+		let pool = PrivatePool::new(10);
+		pool.submit(bundle(2, 10, &[1, 2])).unwrap();
+		pool.submit(bundle(1, 10, &[3])).unwrap();
+
+		assert_eq!(pooled(&pool), vec![H256::repeat_byte(2), H256::repeat_byte(1)]);
+		let transactions: Vec<_> = pool.pooled()[0].1.iter().map(|(hash, _)| *hash).collect();
+		assert_eq!(transactions, vec![transaction(1).hash, transaction(2).hash]);
+	}
+
+	#[test]
+	fn submissions_are_bounded() {
+		let pool = PrivatePool::new(3);
+		pool.submit(bundle(1, 10, &[1, 2])).unwrap();
+		assert_eq!(pool.submit(bundle(1, 10, &[3])), Err(Error::AlreadyPooled));
+		assert_eq!(pool.submit(bundle(2, 10, &[3, 4])), Err(Error::Full));
+		assert_eq!(pool.submit(bundle(2, 10, &[3])), Ok(()));
+	}
+
+	#[test]
+	fn expired_and_included_bundles_are_pruned() {
+		let pool = PrivatePool::new(10);
+		pool.submit(bundle(1, 5, &[1])).unwrap();
+		pool.submit(bundle(2, 8, &[2, 3])).unwrap();
+		pool.submit(bundle(3, 8, &[4])).unwrap();
+
+		pool.prune(5, &HashSet::from([transaction(3).hash]));
+		assert_eq!(pooled(&pool), vec![H256::repeat_byte(3)]);
+		pool.prune(8, &HashSet::new());
+		assert!(pooled(&pool).is_empty());
+	}
+
+	#[test]
+	fn bundles_with_an_invalid_transaction_are_dropped() {
+		let pool = PrivatePool::new(10);
+		pool.submit(bundle(1, 10, &[1, 2])).unwrap();
+		pool.submit(bundle(2, 10, &[3])).unwrap();
+
+		pool.remove_invalid(&HashSet::from([transaction(2).hash]));
+		assert_eq!(pooled(&pool), vec![H256::repeat_byte(2)]);
+	}
+
+	#[test]
+	fn only_the_sender_cancels() {
+		let pool = PrivatePool::new(10);
+		pool.submit(bundle(1, 10, &[1])).unwrap();
+
+		assert_eq!(pool.cancel(H256::repeat_byte(1), BOB), Err(Error::NotSender));
+		assert_eq!(pool.cancel(H256::repeat_byte(1), ALICE), Ok(true));
+		assert_eq!(pool.cancel(H256::repeat_byte(1), ALICE), Ok(false));
+	}
+
+	/// A public transaction, identified by its data.
+	struct TestTx(u8);
+
+	impl InPoolTransaction for TestTx {
+		type Transaction = u8;
+		type Hash = u8;
+
+		fn data(&self) -> &u8 {
+			&self.0
+		}
+
+		fn hash(&self) -> &u8 {
+			&self.0
+		}
+
+		fn priority(&self) -> &TransactionPriority {
+			&0
+		}
+
+		fn longevity(&self) -> &TransactionLongevity {
+			&TransactionLongevity::MAX
+		}
+
+		fn requires(&self) -> &[TransactionTag] {
+			&[]
+		}
+
+		fn provides(&self) -> &[TransactionTag] {
+			&[]
+		}
+
+		fn is_propagable(&self) -> bool {
+			true
+		}
+	}
+
+	struct TestReady(std::vec::IntoIter<Arc<TestTx>>);
+
+	impl Iterator for TestReady {
+		type Item = Arc<TestTx>;
+
+		fn next(&mut self) -> Option<Self::Item> {
+			self.0.next()
+		}
+	}
+
+	impl ReadyTransactions for TestReady {
+		fn report_invalid(&mut self, _: &Self::Item) {}
+	}
+
+	fn proposer_ready(private: &[(u8, u8)], public: &[u8]) -> ProposerReady<TestTx> {
+		let private: Vec<_> = private
+			.iter()
+			.map(|&(bundle, tx)| {
+				Arc::new(PoolTransaction::Private {
+					data: tx,
+					hash: tx,
+					bundle: H256::repeat_byte(bundle),
+				})
+			})
+			.collect();
+		let public: Vec<_> = public.iter().map(|&tx| Arc::new(TestTx(tx))).collect();
+		ProposerReady {
+			private_hashes: private.iter().map(|tx| *tx.hash()).collect(),
+			private: private.into_iter(),
+			failed: None,
+			public: Box::new(TestReady(public.into_iter())),
+		}
+	}
+
+	#[test]
+	fn private_transactions_come_first() {
+		let ready = proposer_ready(&[(1, 10), (2, 11)], &[1, 11, 2]);
+		let hashes: Vec<_> = ready.map(|tx| *tx.hash()).collect();
+		assert_eq!(hashes, vec![10, 11, 1, 2]);
+	}
+
+	#[test]
+	fn a_failed_transaction_skips_the_rest_of_its_bundle() {
+		let mut ready = proposer_ready(&[(1, 10), (1, 11), (1, 12), (2, 13)], &[1]);
+		let first = ready.next().unwrap();
+		assert_eq!(*first.hash(), 10);
+		ready.report_invalid(&first);
+
+		let hashes: Vec<_> = ready.map(|tx| *tx.hash()).collect();
+		assert_eq!(hashes, vec![13, 1]);
+	}
+}
@@ -0,0 +1,447 @@
+//! Relay chain connection of collators not embedding a relay chain node.
+//!
+//! With `--relay-chain-rpc-url` the collator follows the relay chain through external RPC
+//! nodes, and with `--relay-chain-light-client` through an embedded smoldot light client,
+//! instead of running a full relay chain node. The RPC client moves on to the next URL when its
+//! connection drops; [`healthy_rpc_first`] tries the reachable and synced nodes first, and
+//! [`spawn_relay_chain_failover`] switches in-process to a new RPC interface on another node
+//! when the one followed falls behind, as a relay chain RPC node may stay connected while stuck,
+//! which the RPC client does not notice. A relay chain stalled on every node is only logged: the
+//! collator resumes once it moves again.
+
+use std::{
+	collections::{BTreeMap, VecDeque},
+	pin::Pin,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+use cumulus_client_cli::{CollatorOptions, RelayChainMode};
+use cumulus_primitives_core::relay_chain::{
+	vstaging::CommittedCandidateReceiptV2 as CommittedCandidateReceipt, BlockId, BlockNumber,
+	CoreIndex, CoreState, Hash as PHash, Header as PHeader, InboundHrmpMessage,
+	OccupiedCoreAssumption, SessionIndex, ValidationCodeHash, ValidatorId,
+};
+use cumulus_primitives_core::{InboundDownwardMessage, ParaId, PersistedValidationData};
+use cumulus_relay_chain_interface::{
+	OverseerHandle, RelayChainError, RelayChainInterface, RelayChainResult, StorageValue,
+};
+use cumulus_relay_chain_rpc_interface::{
+	create_client_and_start_worker, RelayChainRpcInterface, Url,
+};
+use futures::{future::Either, Future, FutureExt, Stream, StreamExt};
+use jsonrpsee::{
+	core::{async_trait, client::ClientT},
+	rpc_params,
+	ws_client::WsClientBuilder,
+};
+use sc_service::TaskManager;
+use serde::Deserialize;
+use sp_state_machine::StorageProof;
+use sp_version::RuntimeVersion;
+use tokio::sync::watch;
+
+/// Time given to a relay chain RPC node to answer the health check.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the relay chain RPC nodes are checked again.
+const RANKING_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Relay chain blocks the followed node may be behind the healthy node furthest ahead before the
+/// collator switches to the latter.
+const MAX_LAG: BlockNumber = 2;
+
+/// Answer of `system_health`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Health {
+	is_syncing: bool,
+	peers: u64,
+}
+
+/// Best block number of the relay chain RPC node at `url` if it is synced and has peers.
+async fn healthy_best_number(
+	url: &str,
+) -> Result<Option<BlockNumber>, jsonrpsee::core::ClientError> {
+	let client = WsClientBuilder::default()
+		.connection_timeout(HEALTH_CHECK_TIMEOUT)
+		.request_timeout(HEALTH_CHECK_TIMEOUT)
+		.build(url)
+		.await?;
+	let health: Health = client.request("system_health", rpc_params![]).await?;
+	if health.is_syncing || health.peers == 0 {
+		return Ok(None);
+	}
+	let best: PHeader = client.request("chain_getHeader", rpc_params![]).await?;
+	Ok(Some(best.number))
+}
+
+/// Order the relay chain RPC nodes of `options` healthy first, the others keeping their order,
+/// as the RPC client connects to the first one it can.
+pub async fn healthy_rpc_first(mut options: CollatorOptions) -> CollatorOptions {
+	let RelayChainMode::ExternalRpc(urls) = &mut options.relay_chain_mode else {
+		return options;
+	};
+	let health =
+		futures::future::join_all(urls.iter().map(|url| healthy_best_number(url.as_str()))).await;
+	let mut ranked: Vec<_> = urls.drain(..).zip(health).collect();
+	for (url, health) in &ranked {
+		match health {
+			Ok(Some(_)) =>
+				log::info!(target: "relay-chain", "Relay chain RPC node {url} is healthy"),
+			Ok(None) => log::warn!(
+				target: "relay-chain",
+				"Relay chain RPC node {url} is syncing or has no peers",
+			),
+			Err(e) => log::warn!(target: "relay-chain", "Relay chain RPC node {url} failed: {e}"),
+		}
+	}
+	ranked.sort_by_key(|(_, health)| !matches!(health, Ok(Some(_))));
+	urls.extend(ranked.into_iter().map(|(url, _)| url));
+	options
+}
+
+/// Follow the relay chain through `relay_chain_interface` and, with `--relay-chain-rpc-url`,
+/// switch in-process to a new RPC interface on the healthy relay chain RPC node of `options`
+/// furthest ahead when the node followed is more than [`MAX_LAG`] blocks behind it. This is
+/// checked every [`RANKING_INTERVAL`] and when the relay chain has no new best block for
+/// `max_stall`; a relay chain stalled on every node is only logged, the node keeps running.
+///
+/// The collator reads the relay chain through the returned interface, while the relay chain
+/// networking distributing its collations keeps the RPC client it started with, which moves on
+/// to the next node when its connection drops.
+pub fn spawn_relay_chain_failover(
+	task_manager: &TaskManager,
+	relay_chain_interface: Arc<dyn RelayChainInterface>,
+	options: &CollatorOptions,
+	max_stall: Duration,
+) -> Arc<dyn RelayChainInterface> {
+	let urls = match &options.relay_chain_mode {
+		RelayChainMode::ExternalRpc(urls) => urls.clone(),
+		_ => Vec::new(),
+	};
+	let failover = Arc::new(FailoverRelayChainInterface {
+		current: watch::Sender::new(relay_chain_interface),
+	});
+	task_manager.spawn_handle().spawn(
+		"relay-chain-failover",
+		None,
+		fail_over(failover.clone(), urls, max_stall),
+	);
+	failover
+}
+
+/// Switch `failover` to the relay chain RPC node of `urls` furthest ahead when the one it
+/// follows falls behind.
+async fn fail_over(
+	failover: Arc<FailoverRelayChainInterface>,
+	urls: Vec<Url>,
+	max_stall: Duration,
+) {
+	let mut best = match failover.new_best_notification_stream().await {
+		Ok(best) => best,
+		Err(e) => {
+			log::error!(target: "relay-chain", "Cannot follow the relay chain: {e}");
+			return;
+		},
+	};
+	// The relay chain may take long to sync, e.g. when the light client warp syncs: only time
+	// it out once it follows the tip.
+	if best.next().await.is_none() {
+		log::error!(target: "relay-chain", "Relay chain connection closed");
+		return;
+	}
+	// Tasks of the RPC client of the interface switched to, stopped on the next switch.
+	let mut _rpc_tasks = None;
+	let mut checked = Instant::now();
+	loop {
+		let stalled = match tokio::time::timeout(max_stall, best.next()).await {
+			Ok(Some(_)) if checked.elapsed() < RANKING_INTERVAL => continue,
+			Ok(Some(_)) => false,
+			Ok(None) => {
+				log::error!(target: "relay-chain", "Relay chain connection closed");
+				return;
+			},
+			Err(_) => true,
+		};
+		checked = Instant::now();
+		let followed = followed_number(&*failover.current()).await;
+		let ahead = furthest_ahead(&urls)
+			.await
+			.filter(|(_, number)| followed.is_none_or(|followed| *number > followed + MAX_LAG));
+		let Some((index, number)) = ahead else {
+			if stalled {
+				log::warn!(
+					target: "relay-chain",
+					"No relay chain block for {}s and no relay chain RPC node is ahead",
+					max_stall.as_secs(),
+				);
+			}
+			continue;
+		};
+		match connect(&urls, index, failover.current().overseer_handle()).await {
+			Ok((interface, tasks)) => {
+				log::warn!(
+					target: "relay-chain",
+					"Relay chain RPC node {} is at block {number} while the relay chain followed \
+					 is at {followed:?}, switching to it",
+					urls[index],
+				);
+				failover.current.send_replace(interface);
+				_rpc_tasks = Some(tasks);
+			},
+			Err(e) => log::error!(
+				target: "relay-chain",
+				"Cannot connect to relay chain RPC node {}: {e}",
+				urls[index],
+			),
+		}
+	}
+}
+
+/// Best block number of the relay chain followed through `interface`.
+async fn followed_number(interface: &dyn RelayChainInterface) -> Option<BlockNumber> {
+	let hash = interface.best_block_hash().await.ok()?;
+	Some(interface.header(BlockId::Hash(hash)).await.ok()??.number)
+}
+
+/// Index in `urls` and best block number of the healthy relay chain RPC node furthest ahead.
+async fn furthest_ahead(urls: &[Url]) -> Option<(usize, BlockNumber)> {
+	let numbers =
+		futures::future::join_all(urls.iter().map(|url| healthy_best_number(url.as_str()))).await;
+	numbers
+		.into_iter()
+		.enumerate()
+		.filter_map(|(index, number)| Some((index, number.ok()??)))
+		.max_by_key(|(_, number)| *number)
+}
+
+/// RPC interface on the relay chain RPC node `urls[first]`, moving on to the next ones when its
+/// connection drops, and the tasks of its RPC client.
+async fn connect(
+	urls: &[Url],
+	first: usize,
+	overseer_handle: RelayChainResult<OverseerHandle>,
+) -> RelayChainResult<(Arc<dyn RelayChainInterface>, TaskManager)> {
+	let mut tasks = TaskManager::new(tokio::runtime::Handle::current(), None)
+		.map_err(|e| RelayChainError::GenericError(e.to_string()))?;
+	let urls = urls[first..].iter().chain(&urls[..first]).cloned().collect();
+	// The metrics of the first RPC client are already registered.
+	let client = create_client_and_start_worker(urls, &mut tasks, None).await?;
+	Ok((Arc::new(RelayChainRpcInterface::new(client, overseer_handle?)), tasks))
+}
+
+type HeaderStream = Pin<Box<dyn Stream<Item = PHeader> + Send>>;
+
+/// Relay chain interface delegating to the one [`spawn_relay_chain_failover`] last switched to.
+pub struct FailoverRelayChainInterface {
+	current: watch::Sender<Arc<dyn RelayChainInterface>>,
+}
+
+impl FailoverRelayChainInterface {
+	fn current(&self) -> Arc<dyn RelayChainInterface> {
+		self.current.borrow().clone()
+	}
+
+	/// Headers of the stream `subscribe` opens on the current interface, opened again on the
+	/// interface switched to.
+	async fn follow<F, Fut>(&self, subscribe: F) -> RelayChainResult<HeaderStream>
+	where
+		F: Fn(Arc<dyn RelayChainInterface>) -> Fut + Send + 'static,
+		Fut: Future<Output = RelayChainResult<HeaderStream>> + Send + 'static,
+	{
+		let mut switched = self.current.subscribe();
+		let headers = subscribe(switched.borrow_and_update().clone()).await?;
+		let headers = futures::stream::unfold(
+			(switched, Some(headers), subscribe),
+			|(mut switched, mut headers, subscribe)| async move {
+				loop {
+					let Some(stream) = headers.as_mut() else {
+						let interface = switched.borrow_and_update().clone();
+						match subscribe(interface).await {
+							Ok(stream) => headers = Some(stream),
+							Err(e) => {
+								log::warn!(
+									target: "relay-chain",
+									"Cannot follow the relay chain: {e}",
+								);
+								switched.changed().await.ok()?;
+							},
+						}
+						continue;
+					};
+					let changed = switched.changed().boxed();
+					let next = match futures::future::select(stream.next(), changed).await {
+						Either::Left((header, _)) => Some(header),
+						Either::Right((changed, _)) => {
+							changed.ok()?;
+							None
+						},
+					};
+					match next {
+						Some(Some(header)) => return Some((header, (switched, headers, subscribe))),
+						// The stream of an interface ends when it is dropped, wait for the next.
+						Some(None) => {
+							switched.changed().await.ok()?;
+							headers = None;
+						},
+						None => headers = None,
+					}
+				}
+			},
+		);
+		Ok(Box::pin(headers))
+	}
+}
+
+#[async_trait]
+impl RelayChainInterface for FailoverRelayChainInterface {
+	async fn get_storage_by_key(
+		&self,
+		relay_parent: PHash,
+		key: &[u8],
+	) -> RelayChainResult<Option<StorageValue>> {
+		self.current().get_storage_by_key(relay_parent, key).await
+	}
+
+	async fn validators(&self, block_id: PHash) -> RelayChainResult<Vec<ValidatorId>> {
+		self.current().validators(block_id).await
+	}
+
+	async fn best_block_hash(&self) -> RelayChainResult<PHash> {
+		self.current().best_block_hash().await
+	}
+
+	async fn header(&self, block_id: BlockId) -> RelayChainResult<Option<PHeader>> {
+		self.current().header(block_id).await
+	}
+
+	async fn finalized_block_hash(&self) -> RelayChainResult<PHash> {
+		self.current().finalized_block_hash().await
+	}
+
+	async fn call_runtime_api(
+		&self,
+		method_name: &'static str,
+		hash: PHash,
+		payload: &[u8],
+	) -> RelayChainResult<Vec<u8>> {
+		self.current().call_runtime_api(method_name, hash, payload).await
+	}
+
+	async fn retrieve_dmq_contents(
+		&self,
+		para_id: ParaId,
+		relay_parent: PHash,
+	) -> RelayChainResult<Vec<InboundDownwardMessage>> {
+		self.current().retrieve_dmq_contents(para_id, relay_parent).await
+	}
+
+	async fn retrieve_all_inbound_hrmp_channel_contents(
+		&self,
+		para_id: ParaId,
+		relay_parent: PHash,
+	) -> RelayChainResult<BTreeMap<ParaId, Vec<InboundHrmpMessage>>> {
+		self.current().retrieve_all_inbound_hrmp_channel_contents(para_id, relay_parent).await
+	}
+
+	async fn persisted_validation_data(
+		&self,
+		block_id: PHash,
+		para_id: ParaId,
+		occupied_core_assumption: OccupiedCoreAssumption,
+	) -> RelayChainResult<Option<PersistedValidationData>> {
+		self.current()
+			.persisted_validation_data(block_id, para_id, occupied_core_assumption)
+			.await
+	}
+
+	async fn validation_code_hash(
+		&self,
+		relay_parent: PHash,
+		para_id: ParaId,
+		occupied_core_assumption: OccupiedCoreAssumption,
+	) -> RelayChainResult<Option<ValidationCodeHash>> {
+		self.current()
+			.validation_code_hash(relay_parent, para_id, occupied_core_assumption)
+			.await
+	}
+
+	#[allow(deprecated)]
+	async fn candidate_pending_availability(
+		&self,
+		block_id: PHash,
+		para_id: ParaId,
+	) -> RelayChainResult<Option<CommittedCandidateReceipt>> {
+		self.current().candidate_pending_availability(block_id, para_id).await
+	}
+
+	async fn candidates_pending_availability(
+		&self,
+		block_id: PHash,
+		para_id: ParaId,
+	) -> RelayChainResult<Vec<CommittedCandidateReceipt>> {
+		self.current().candidates_pending_availability(block_id, para_id).await
+	}
+
+	async fn session_index_for_child(&self, block_id: PHash) -> RelayChainResult<SessionIndex> {
+		self.current().session_index_for_child(block_id).await
+	}
+
+	async fn import_notification_stream(&self) -> RelayChainResult<HeaderStream> {
+		self.follow(|interface| async move { interface.import_notification_stream().await })
+			.await
+	}
+
+	async fn new_best_notification_stream(&self) -> RelayChainResult<HeaderStream> {
+		self.follow(|interface| async move { interface.new_best_notification_stream().await })
+			.await
+	}
+
+	async fn finality_notification_stream(&self) -> RelayChainResult<HeaderStream> {
+		self.follow(|interface| async move { interface.finality_notification_stream().await })
+			.await
+	}
+
+	async fn wait_for_block(&self, hash: PHash) -> RelayChainResult<()> {
+		self.current().wait_for_block(hash).await
+	}
+
+	async fn is_major_syncing(&self) -> RelayChainResult<bool> {
+		self.current().is_major_syncing().await
+	}
+
+	fn overseer_handle(&self) -> RelayChainResult<OverseerHandle> {
+		self.current().overseer_handle()
+	}
+
+	async fn prove_read(
+		&self,
+		relay_parent: PHash,
+		relevant_keys: &Vec<Vec<u8>>,
+	) -> RelayChainResult<StorageProof> {
+		self.current().prove_read(relay_parent, relevant_keys).await
+	}
+
+	async fn version(&self, relay_parent: PHash) -> RelayChainResult<RuntimeVersion> {
+		self.current().version(relay_parent).await
+	}
+
+	async fn availability_cores(
+		&self,
+		relay_parent: PHash,
+	) -> RelayChainResult<Vec<CoreState<PHash, BlockNumber>>> {
+		self.current().availability_cores(relay_parent).await
+	}
+
+	async fn claim_queue(
+		&self,
+		relay_parent: PHash,
+	) -> RelayChainResult<BTreeMap<CoreIndex, VecDeque<ParaId>>> {
+		self.current().claim_queue(relay_parent).await
+	}
+
+	async fn scheduling_lookahead(&self, relay_parent: PHash) -> RelayChainResult<u32> {
+		self.current().scheduling_lookahead(relay_parent).await
+	}
+}
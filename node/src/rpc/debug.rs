@@ -0,0 +1,441 @@
+//! `debug_*` tracing RPC, replaying blocks through
+//! [`DebugRuntimeApi`](fintradex_runtime::evm_tracing::DebugRuntimeApi) and answering with
+//! geth-formatted traces.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use fc_rpc::{frontier_backend_client, internal_err};
+use fc_rpc_core::types::{BlockNumberOrHash, Bytes};
+use fintradex_runtime::evm_tracing::{
+	AccountState, CallFrame, CallType, DebugRuntimeApi, RawTrace, StructLog, Trace, TraceError,
+	Tracer,
+};
+use jsonrpsee::{
+	core::{async_trait, RpcResult},
+	proc_macros::rpc,
+	types::ErrorObjectOwned,
+};
+use sc_client_api::BlockBackend;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use sp_api::{ApiError, Core, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{hexdisplay::HexDisplay, H256, U256};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use tokio::sync::Semaphore;
+
+/// Options of a tracing request, as accepted by geth.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceParams {
+	/// `callTracer`, `prestateTracer`, or none for the struct logger.
+	pub tracer: Option<String>,
+	#[serde(default)]
+	pub disable_stack: bool,
+	#[serde(default)]
+	pub disable_memory: bool,
+	#[serde(default)]
+	pub disable_storage: bool,
+}
+
+impl TraceParams {
+	fn tracer(&self) -> RpcResult<Tracer> {
+		match self.tracer.as_deref() {
+			None => Ok(Tracer::Raw {
+				disable_stack: self.disable_stack,
+				disable_memory: self.disable_memory,
+				disable_storage: self.disable_storage,
+			}),
+			Some("callTracer") => Ok(Tracer::Call),
+			Some("prestateTracer") => Ok(Tracer::Prestate),
+			Some(other) => Err(internal_err(format!("unsupported tracer: {other}"))),
+		}
+	}
+}
+
+#[rpc(server)]
+pub trait DebugApi {
+	#[method(name = "debug_traceTransaction")]
+	async fn trace_transaction(
+		&self,
+		transaction_hash: H256,
+		params: Option<TraceParams>,
+	) -> RpcResult<Value>;
+
+	#[method(name = "debug_traceBlockByNumber")]
+	async fn trace_block_by_number(
+		&self,
+		number: BlockNumberOrHash,
+		params: Option<TraceParams>,
+	) -> RpcResult<Vec<Value>>;
+
+	#[method(name = "debug_traceBlockByHash")]
+	async fn trace_block_by_hash(
+		&self,
+		hash: H256,
+		params: Option<TraceParams>,
+	) -> RpcResult<Vec<Value>>;
+}
+
+pub struct Debug<B: BlockT, C> {
+	client: Arc<C>,
+	backend: Arc<dyn fc_api::Backend<B>>,
+	/// Bounds the replays running at once, so tracing cannot starve block import.
+	permits: Arc<Semaphore>,
+	_marker: PhantomData<B>,
+}
+
+impl<B: BlockT, C> Debug<B, C> {
+	pub fn new(
+		client: Arc<C>,
+		backend: Arc<dyn fc_api::Backend<B>>,
+		permits: Arc<Semaphore>,
+	) -> Self {
+		Self { client, backend, permits, _marker: PhantomData }
+	}
+}
+
+impl<B, C> Debug<B, C>
+where
+	B: BlockT<Hash = H256>,
+	C: ProvideRuntimeApi<B> + BlockBackend<B> + HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: DebugRuntimeApi<B>,
+{
+	/// Run `trace` with block `hash` initialized on top of its parent, on the blocking pool
+	/// once a permit is free.
+	async fn replay<R, F>(&self, hash: B::Hash, trace: F) -> RpcResult<R>
+	where
+		R: Send + 'static,
+		F: FnOnce(&C::Api, B::Hash, Vec<B::Extrinsic>) -> Result<Result<R, TraceError>, ApiError>
+			+ Send
+			+ 'static,
+	{
+		let _permit = self
+			.permits
+			.clone()
+			.acquire_owned()
+			.await
+			.map_err(|_| internal_err("tracing is shutting down"))?;
+		let client = self.client.clone();
+
+		tokio::task::spawn_blocking(move || {
+			replay_block(client.as_ref(), hash, trace).map_err(internal_err)?.map_err(trace_err)
+		})
+		.await
+		.map_err(|e| internal_err(e.to_string()))?
+	}
+
+	async fn trace_block(
+		&self,
+		hash: B::Hash,
+		params: Option<TraceParams>,
+	) -> RpcResult<Vec<Value>> {
+		let tracer = params.unwrap_or_default().tracer()?;
+		let traces = self
+			.replay(hash, move |api, parent, extrinsics| api.trace_block(parent, extrinsics, tracer))
+			.await?;
+
+		Ok(traces
+			.into_iter()
+			.map(|(hash, trace)| json!({ "txHash": hash, "result": trace_json(trace) }))
+			.collect())
+	}
+}
+
+#[async_trait]
+impl<B, C> DebugApiServer for Debug<B, C>
+where
+	B: BlockT<Hash = H256>,
+	C: ProvideRuntimeApi<B> + BlockBackend<B> + HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: DebugRuntimeApi<B>,
+{
+	async fn trace_transaction(
+		&self,
+		transaction_hash: H256,
+		params: Option<TraceParams>,
+	) -> RpcResult<Value> {
+		let tracer = params.unwrap_or_default().tracer()?;
+		let (ethereum_block, _) = frontier_backend_client::load_transactions::<B, C>(
+			self.client.as_ref(),
+			self.backend.as_ref(),
+			transaction_hash,
+			true,
+		)
+		.await?
+		.ok_or_else(|| internal_err("transaction not found"))?;
+		let hash = frontier_backend_client::load_hash::<B, C>(
+			self.client.as_ref(),
+			self.backend.as_ref(),
+			ethereum_block,
+		)
+		.await?
+		.ok_or_else(|| internal_err("block not found"))?;
+
+		let trace = self
+			.replay(hash, move |api, parent, extrinsics| {
+				api.trace_transaction(parent, extrinsics, transaction_hash, tracer)
+			})
+			.await?;
+		Ok(trace_json(trace))
+	}
+
+	async fn trace_block_by_number(
+		&self,
+		number: BlockNumberOrHash,
+		params: Option<TraceParams>,
+	) -> RpcResult<Vec<Value>> {
+		let id = frontier_backend_client::native_block_id::<B, C>(
+			self.client.as_ref(),
+			self.backend.as_ref(),
+			Some(number),
+		)
+		.await?
+		.ok_or_else(|| internal_err("block not found"))?;
+		let hash =
+			self.client.expect_block_hash_from_id(&id).map_err(|e| internal_err(e.to_string()))?;
+
+		self.trace_block(hash, params).await
+	}
+
+	async fn trace_block_by_hash(
+		&self,
+		hash: H256,
+		params: Option<TraceParams>,
+	) -> RpcResult<Vec<Value>> {
+		let hash = frontier_backend_client::load_hash::<B, C>(
+			self.client.as_ref(),
+			self.backend.as_ref(),
+			hash,
+		)
+		.await?
+		.ok_or_else(|| internal_err("block not found"))?;
+
+		self.trace_block(hash, params).await
+	}
+}
+
+/// Run `trace` with block `hash` initialized on top of its parent.
+pub fn replay_block<B, C, R, F>(
+	client: &C,
+	hash: B::Hash,
+	trace: F,
+) -> Result<Result<R, TraceError>, String>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + BlockBackend<B> + HeaderBackend<B>,
+	C::Api: DebugRuntimeApi<B>,
+	F: FnOnce(&C::Api, B::Hash, Vec<B::Extrinsic>) -> Result<Result<R, TraceError>, ApiError>,
+{
+	let header = client
+		.header(hash)
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| "block not found".to_string())?;
+	let extrinsics = client
+		.block_body(hash)
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| "block body not found".to_string())?;
+	let parent = *header.parent_hash();
+
+	let api = client.runtime_api();
+	api.initialize_block(parent, &header).map_err(|e| e.to_string())?;
+	trace(&*api, parent, extrinsics).map_err(|e| e.to_string())
+}
+
+fn trace_err(error: TraceError) -> ErrorObjectOwned {
+	match error {
+		TraceError::Unsupported => internal_err(
+			"runtime built without evm-tracing, pass a tracing runtime to --wasm-runtime-overrides",
+		),
+		TraceError::TransactionNotFound => internal_err("transaction not found in its block"),
+	}
+}
+
+fn trace_json(trace: Trace) -> Value {
+	match trace {
+		Trace::Call(frame) => call_json(frame),
+		Trace::Prestate(accounts) => prestate_json(accounts),
+		Trace::Raw(raw) => raw_json(raw),
+	}
+}
+
+fn call_json(frame: CallFrame) -> Value {
+	let call_type = match frame.call_type {
+		CallType::Call => "CALL",
+		CallType::CallCode => "CALLCODE",
+		CallType::DelegateCall => "DELEGATECALL",
+		CallType::StaticCall => "STATICCALL",
+		CallType::Create => "CREATE",
+		CallType::Create2 => "CREATE2",
+		CallType::SelfDestruct => "SELFDESTRUCT",
+	};
+	let mut json = json!({
+		"type": call_type,
+		"from": frame.from,
+		"to": frame.to,
+		"value": frame.value,
+		"gas": U256::from(frame.gas),
+		"gasUsed": U256::from(frame.gas_used),
+		"input": Bytes(frame.input),
+		"output": Bytes(frame.output),
+	});
+	if let Some(error) = frame.error {
+		json["error"] = error.into();
+	}
+	if !frame.calls.is_empty() {
+		json["calls"] = frame.calls.into_iter().map(call_json).collect();
+	}
+	json
+}
+
+fn prestate_json(accounts: Vec<AccountState>) -> Value {
+	let accounts = accounts
+		.into_iter()
+		.map(|account| {
+			let mut json = json!({
+				"balance": account.balance,
+				"nonce": account.nonce.low_u64(),
+			});
+			if !account.code.is_empty() {
+				json["code"] = json!(Bytes(account.code));
+			}
+			if !account.storage.is_empty() {
+				json["storage"] = account
+					.storage
+					.into_iter()
+					.map(|(index, value)| (format!("{index:?}"), json!(value)))
+					.collect::<Map<_, _>>()
+					.into();
+			}
+			(format!("{:?}", account.address), json)
+		})
+		.collect::<Map<_, _>>();
+	Value::Object(accounts)
+}
+
+fn raw_json(raw: RawTrace) -> Value {
+	json!({
+		"gas": raw.gas,
+		"failed": raw.failed,
+		"returnValue": HexDisplay::from(&raw.return_value).to_string(),
+		"structLogs": raw.struct_logs.into_iter().map(struct_log_json).collect::<Vec<_>>(),
+	})
+}
+
+fn struct_log_json(log: StructLog) -> Value {
+	let mut json = json!({
+		"pc": log.pc,
+		"op": opcode_name(log.op),
+		"gas": log.gas,
+		"gasCost": log.gas_cost,
+		"depth": log.depth,
+	});
+	if let Some(stack) = log.stack {
+		json["stack"] =
+			stack.into_iter().map(|word| json!(U256::from_big_endian(word.as_bytes()))).collect();
+	}
+	if let Some(memory) = log.memory {
+		json["memory"] =
+			memory.chunks(32).map(|word| HexDisplay::from(&word).to_string()).collect();
+	}
+	if let Some(storage) = log.storage {
+		json["storage"] = storage
+			.into_iter()
+			.map(|(index, value)| {
+				(HexDisplay::from(&index.0).to_string(), json!(HexDisplay::from(&value.0).to_string()))
+			})
+			.collect::<Map<_, _>>()
+			.into();
+	}
+	json
+}
+
+fn opcode_name(op: u8) -> String {
+	let name = match op {
+		0x00 => "STOP",
+		0x01 => "ADD",
+		0x02 => "MUL",
+		0x03 => "SUB",
+		0x04 => "DIV",
+		0x05 => "SDIV",
+		0x06 => "MOD",
+		0x07 => "SMOD",
+		0x08 => "ADDMOD",
+		0x09 => "MULMOD",
+		0x0a => "EXP",
+		0x0b => "SIGNEXTEND",
+		0x10 => "LT",
+		0x11 => "GT",
+		0x12 => "SLT",
+		0x13 => "SGT",
+		0x14 => "EQ",
+		0x15 => "ISZERO",
+		0x16 => "AND",
+		0x17 => "OR",
+		0x18 => "XOR",
+		0x19 => "NOT",
+		0x1a => "BYTE",
+		0x1b => "SHL",
+		0x1c => "SHR",
+		0x1d => "SAR",
+		0x20 => "KECCAK256",
+		0x30 => "ADDRESS",
+		0x31 => "BALANCE",
+		0x32 => "ORIGIN",
+		0x33 => "CALLER",
+		0x34 => "CALLVALUE",
+		0x35 => "CALLDATALOAD",
+		0x36 => "CALLDATASIZE",
+		0x37 => "CALLDATACOPY",
+		0x38 => "CODESIZE",
+		0x39 => "CODECOPY",
+		0x3a => "GASPRICE",
+		0x3b => "EXTCODESIZE",
+		0x3c => "EXTCODECOPY",
+		0x3d => "RETURNDATASIZE",
+		0x3e => "RETURNDATACOPY",
+		0x3f => "EXTCODEHASH",
+		0x40 => "BLOCKHASH",
+		0x41 => "COINBASE",
+		0x42 => "TIMESTAMP",
+		0x43 => "NUMBER",
+		0x44 => "PREVRANDAO",
+		0x45 => "GASLIMIT",
+		0x46 => "CHAINID",
+		0x47 => "SELFBALANCE",
+		0x48 => "BASEFEE",
+		0x49 => "BLOBHASH",
+		0x4a => "BLOBBASEFEE",
+		0x50 => "POP",
+		0x51 => "MLOAD",
+		0x52 => "MSTORE",
+		0x53 => "MSTORE8",
+		0x54 => "SLOAD",
+		0x55 => "SSTORE",
+		0x56 => "JUMP",
+		0x57 => "JUMPI",
+		0x58 => "PC",
+		0x59 => "MSIZE",
+		0x5a => "GAS",
+		0x5b => "JUMPDEST",
+		0x5c => "TLOAD",
+		0x5d => "TSTORE",
+		0x5e => "MCOPY",
+		0x5f => "PUSH0",
+		0x60..=0x7f => return format!("PUSH{}", op - 0x5f),
+		0x80..=0x8f => return format!("DUP{}", op - 0x7f),
+		0x90..=0x9f => return format!("SWAP{}", op - 0x8f),
+		0xa0..=0xa4 => return format!("LOG{}", op - 0xa0),
+		0xf0 => "CREATE",
+		0xf1 => "CALL",
+		0xf2 => "CALLCODE",
+		0xf3 => "RETURN",
+		0xf4 => "DELEGATECALL",
+		0xf5 => "CREATE2",
+		0xfa => "STATICCALL",
+		0xfd => "REVERT",
+		0xfe => "INVALID",
+		0xff => "SELFDESTRUCT",
+		_ => return format!("opcode {op:#04x} not defined"),
+	};
+	name.into()
+}
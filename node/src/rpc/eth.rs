@@ -2,24 +2,25 @@ use std::{collections::BTreeMap, sync::Arc};
 
 use fc_rpc::pending::AuraConsensusDataProvider;
 use fc_rpc_core::EthApiServer;
-use jsonrpsee::RpcModule;
+use jsonrpsee::{Methods, RpcModule};
 // Substrate
 use sc_client_api::{
 	backend::{Backend, StorageProvider},
 	client::BlockchainEvents,
-	AuxStore, UsageProvider,
+	AuxStore, BlockBackend, UsageProvider,
 };
 use sc_network::NetworkService;
 use sc_network_sync::SyncingService;
 use sc_rpc::SubscriptionTaskExecutor;
-use sc_transaction_pool::{ChainApi, Pool};
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::{CallApiAt, ProvideRuntimeApi};
 use sp_block_builder::BlockBuilder as BlockBuilderApi;
+use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
 use sp_consensus_aura::{sr25519::AuthorityId as AuraId, AuraApi};
-use sp_core::H256;
+use sp_core::{Get, H256};
 use sp_runtime::traits::Block as BlockT;
+use tokio::sync::Semaphore;
 // Frontier
 pub use fc_rpc::{EthBlockDataCacheTask, EthConfig, OverrideHandle, StorageOverride};
 #[cfg(feature = "txpool")]
@@ -27,15 +28,16 @@ use fc_rpc::{TxPool, TxPoolApiServer};
 pub use fc_rpc_core::types::{FeeHistoryCache, FeeHistoryCacheLimit, FilterPool};
 pub use fc_storage::overrides_handle;
 use fp_rpc::{ConvertTransaction, ConvertTransactionRuntimeApi, EthereumRuntimeRPCApi};
+// Local
+use crate::{eth::EthApi, private_pool::PrivatePool};
+use fintradex_runtime::{evm_tracing::DebugRuntimeApi, simulate::SimulateRuntimeApi};
 
 /// Extra dependencies for Ethereum compatibility.
-pub struct EthDeps<C, P, A: ChainApi, CT, B: BlockT, CIDP> {
+pub struct EthDeps<C, P, CT, B: BlockT, CIDP> {
 	/// The client instance to use.
 	pub client: Arc<C>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
-	/// Graph pool instance.
-	pub graph: Arc<Pool<A>>,
 	/// Ethereum transaction converter.
 	pub converter: Option<CT>,
 	/// The Node authority flag
@@ -67,14 +69,27 @@ pub struct EthDeps<C, P, A: ChainApi, CT, B: BlockT, CIDP> {
 	pub forced_parent_hashes: Option<BTreeMap<H256, H256>>,
 	/// Something that can create the inherent data providers for pending state
 	pub pending_create_inherent_data_providers: CIDP,
+	/// Optional Ethereum RPC modules to enable.
+	pub ethapi: Vec<EthApi>,
+	/// Permits of the tracing requests, shared by all connections.
+	pub tracing_permits: Arc<Semaphore>,
+	/// Limits of the `trace_*` queries.
+	pub trace_limits: super::TraceLimits,
+	/// Transactions held for the local collator.
+	pub private_pool: Arc<PrivatePool<B>>,
+	/// Maximum number of blocks a private transaction stays pooled.
+	pub private_tx_lifetime: u32,
+	/// Whether the EVM state history of pruned blocks is indexed.
+	pub evm_state_history: bool,
+	/// Mapping sync lag over which `fintradex_syncStatus` reports it lagging.
+	pub sync_lag_alarm: u32,
 }
 
-impl<C, P, A: ChainApi, CT: Clone, B: BlockT, CIDP: Clone> Clone for EthDeps<C, P, A, CT, B, CIDP> {
+impl<C, P, CT: Clone, B: BlockT, CIDP: Clone> Clone for EthDeps<C, P, CT, B, CIDP> {
 	fn clone(&self) -> Self {
 		Self {
 			client: self.client.clone(),
 			pool: self.pool.clone(),
-			graph: self.graph.clone(),
 			converter: self.converter.clone(),
 			is_authority: self.is_authority,
 			enable_dev_signer: self.enable_dev_signer,
@@ -92,14 +107,21 @@ impl<C, P, A: ChainApi, CT: Clone, B: BlockT, CIDP: Clone> Clone for EthDeps<C,
 			pending_create_inherent_data_providers: self
 				.pending_create_inherent_data_providers
 				.clone(),
+			ethapi: self.ethapi.clone(),
+			tracing_permits: self.tracing_permits.clone(),
+			trace_limits: self.trace_limits,
+			private_pool: self.private_pool.clone(),
+			private_tx_lifetime: self.private_tx_lifetime,
+			evm_state_history: self.evm_state_history,
+			sync_lag_alarm: self.sync_lag_alarm,
 		}
 	}
 }
 
 /// Instantiate Ethereum-compatible RPC extensions.
-pub fn create_eth<B, C, P, CT, BE, A, CIDP, EC: EthConfig<B, C>>(
+pub fn create_eth<B, C, P, CT, BE, CIDP, EC: EthConfig<B, C>>(
 	mut io: RpcModule<()>,
-	deps: EthDeps<C, P, A, CT, B, CIDP>,
+	deps: EthDeps<C, P, CT, B, CIDP>,
 	subscription_task_executor: SubscriptionTaskExecutor,
 	pubsub_notification_sinks: Arc<
 		fc_mapping_sync::EthereumBlockNotificationSinks<
@@ -114,15 +136,26 @@ where
 	C::Api: BlockBuilderApi<B>
 		+ EthereumRuntimeRPCApi<B>
 		+ ConvertTransactionRuntimeApi<B>
+		+ DebugRuntimeApi<B>
+		+ SimulateRuntimeApi<B>
+		+ TaggedTransactionQueue<B>
 		+ AuraApi<B, AuraId>,
+	C: BlockBackend<B>,
 	C: HeaderBackend<B> + HeaderMetadata<B, Error = BlockChainError>,
 	C: BlockchainEvents<B> + AuxStore + UsageProvider<B> + StorageProvider<B, BE> + 'static,
 	BE: Backend<B> + 'static,
-	P: TransactionPool<Block = B> + 'static,
-	A: ChainApi<Block = B> + 'static,
+	P: TransactionPool<Block = B, Hash = B::Hash> + 'static,
 	CT: ConvertTransaction<<B as BlockT>::Extrinsic> + Send + Sync + 'static,
 	CIDP: sp_inherents::CreateInherentDataProviders<B, ()> + Send + 'static,
 {
+	use super::{
+		debug::{Debug, DebugApiServer},
+		history::{History, HistoryApiServer, HISTORY_METHODS},
+		private::{Private, PrivateApiServer},
+		simulate::{Simulate, SimulateApiServer},
+		sync_status::{SyncStatus, SyncStatusApiServer},
+		trace::{Trace, TraceApiServer},
+	};
 	use fc_rpc::{
 		Eth, EthDevSigner, EthFilter, EthFilterApiServer, EthPubSub, EthPubSubApiServer, EthSigner,
 		Net, NetApiServer, Web3, Web3ApiServer,
@@ -131,7 +164,6 @@ where
 	let EthDeps {
 		client,
 		pool,
-		graph,
 		converter,
 		is_authority,
 		enable_dev_signer,
@@ -147,6 +179,13 @@ where
 		execute_gas_limit_multiplier,
 		forced_parent_hashes,
 		pending_create_inherent_data_providers,
+		ethapi,
+		tracing_permits,
+		trace_limits,
+		private_pool,
+		private_tx_lifetime,
+		evm_state_history,
+		sync_lag_alarm,
 	} = deps;
 
 	let mut signers = Vec::new();
@@ -154,35 +193,81 @@ where
 		signers.push(Box::new(EthDevSigner::new()) as Box<dyn EthSigner>);
 	}
 
+	let mut eth = Eth::<B, C, P, CT, BE, CIDP, EC>::new(
+		client.clone(),
+		pool.clone(),
+		converter,
+		sync.clone(),
+		signers,
+		overrides.clone(),
+		frontier_backend.clone(),
+		is_authority,
+		block_data_cache.clone(),
+		fee_history_cache,
+		fee_history_cache_limit,
+		execute_gas_limit_multiplier,
+		forced_parent_hashes,
+		pending_create_inherent_data_providers,
+		Some(Box::new(AuraConsensusDataProvider::new(client.clone()))),
+	)
+	.replace_config::<EC>()
+	.into_rpc();
+	// Frontier answers the state queries while the state of the block is available.
+	let frontier = Methods::from(eth.clone());
+	for method in HISTORY_METHODS {
+		eth.remove_method(method);
+	}
+	io.merge(eth)?;
 	io.merge(
-		Eth::<B, C, P, CT, BE, A, CIDP, EC>::new(
+		History::<B, C, BE>::new(
 			client.clone(),
-			pool.clone(),
-			graph.clone(),
-			converter,
-			sync.clone(),
-			signers,
-			overrides.clone(),
 			frontier_backend.clone(),
-			is_authority,
-			block_data_cache.clone(),
-			fee_history_cache,
-			fee_history_cache_limit,
-			execute_gas_limit_multiplier,
-			forced_parent_hashes,
-			pending_create_inherent_data_providers,
-			Some(Box::new(AuraConsensusDataProvider::new(client.clone()))),
+			frontier,
+			evm_state_history,
 		)
-		.replace_config::<EC>()
 		.into_rpc(),
 	)?;
 
+	io.merge(
+		SyncStatus::new(client.clone(), frontier_backend.clone(), sync_lag_alarm).into_rpc(),
+	)?;
+
+	if ethapi.contains(&EthApi::Debug) {
+		io.merge(
+			Debug::new(client.clone(), frontier_backend.clone(), tracing_permits.clone())
+				.into_rpc(),
+		)?;
+	}
+
+	if ethapi.contains(&EthApi::Simulate) {
+		let block_gas_limit =
+			<fintradex_runtime::Runtime as pallet_evm::Config>::BlockGasLimit::get().low_u64();
+		let gas_cap = block_gas_limit.saturating_mul(execute_gas_limit_multiplier);
+		io.merge(
+			Simulate::new(
+				client.clone(),
+				frontier_backend.clone(),
+				tracing_permits.clone(),
+				gas_cap,
+			)
+			.into_rpc(),
+		)?;
+	}
+
+	if ethapi.contains(&EthApi::Trace) {
+		io.merge(Trace::new(client.clone(), frontier_backend.clone(), trace_limits).into_rpc())?;
+	}
+
+	if ethapi.contains(&EthApi::Private) {
+		io.merge(Private::new(client.clone(), private_pool, private_tx_lifetime).into_rpc())?;
+	}
+
 	if let Some(filter_pool) = filter_pool {
 		io.merge(
 			EthFilter::new(
 				client.clone(),
 				frontier_backend,
-				graph.clone(),
+				pool.clone(),
 				filter_pool,
 				500_usize, // max stored filters
 				max_past_logs,
@@ -194,7 +279,7 @@ where
 
 	io.merge(
 		EthPubSub::new(
-			pool,
+			pool.clone(),
 			client.clone(),
 			sync,
 			subscription_task_executor,
@@ -217,7 +302,7 @@ where
 	io.merge(Web3::new(client.clone()).into_rpc())?;
 
 	#[cfg(feature = "txpool")]
-	io.merge(TxPool::new(client, graph).into_rpc())?;
+	io.merge(TxPool::new(client, pool).into_rpc())?;
 
 	Ok(io)
-}
\ No newline at end of file
+}
@@ -0,0 +1,672 @@
+//! EVM state of blocks whose state was pruned.
+//!
+//! With `--evm-state-history`, the indexer spawned with
+//! [`spawn_evm_history_indexer`](crate::eth::spawn_evm_history_indexer) records, for every
+//! finalized block, the previous value of each `System::Account`, `EVM::AccountCodes`,
+//! `EVM::AccountStorages` and `AccountMapping::Accounts` entry the block changed. The value of an
+//! entry at block `N` is the previous value recorded by its first change after `N`, or its value
+//! at the last indexed block if it did not change since.
+//!
+//! The changes of a block are kept with its parent values until the block is finalized, so they
+//! survive a restart. Finalized blocks whose changes were not seen, e.g. imported during a major
+//! sync, are recovered by comparing their state with the state of their parent while both are
+//! available; otherwise the history starts over.
+//!
+//! `eth_getBalance`, `eth_getTransactionCount`, `eth_getCode` and `eth_getStorageAt` are answered
+//! by Frontier while the state of the block is available, from the index once it was pruned, and
+//! fail with "state pruned at block N" past the retention.
+
+use std::{collections::BTreeMap, marker::PhantomData, sync::Arc};
+
+use codec::{Decode, Encode};
+use fc_rpc::{frontier_backend_client, internal_err};
+use fc_rpc_core::types::{BlockNumberOrHash, Bytes};
+use fintradex_runtime::{AccountId, Balance, Nonce, EXISTENTIAL_DEPOSIT};
+use jsonrpsee::{
+	core::{async_trait, params::ArrayParams, server::MethodsError, RpcResult},
+	proc_macros::rpc,
+	rpc_params,
+	types::{ErrorObject, ErrorObjectOwned},
+	Methods,
+};
+use sc_client_api::backend::{AuxStore, Backend, StorageProvider};
+use serde::de::DeserializeOwned;
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	hashing::{blake2_128, twox_128, twox_64},
+	storage::{well_known_keys, StorageKey},
+	H160, H256, U256,
+};
+use sp_runtime::traits::{Block as BlockT, UniqueSaturatedInto};
+
+const HISTORY_KEY_PREFIX: &[u8] = b"fintradex:evm-state-history:";
+
+/// Number of blocks whose changes of an entry are stored together.
+const CHUNK_LENGTH: u32 = 4096;
+
+/// Error code of queries past the retention, the one of geth for missing state.
+const STATE_PRUNED_CODE: i32 = -32000;
+
+/// Storage maps whose history is indexed, as (pallet, item).
+const INDEXED_MAPS: [(&[u8], &[u8]); 4] = [
+	(b"System", b"Account"),
+	(b"EVM", b"AccountCodes"),
+	(b"EVM", b"AccountStorages"),
+	(b"AccountMapping", b"Accounts"),
+];
+
+/// Methods of Frontier's `EthApi` answered by [`History`] instead.
+pub const HISTORY_METHODS: [&str; 4] =
+	["eth_getBalance", "eth_getTransactionCount", "eth_getCode", "eth_getStorageAt"];
+
+type AccountInfo = frame_system::AccountInfo<Nonce, pallet_balances::AccountData<Balance>>;
+
+/// Blocks covered by the index.
+#[derive(Clone, Copy, Debug, Encode, Decode)]
+pub struct HistoryMeta {
+	/// Oldest block whose state can be recovered.
+	pub first: u32,
+	/// Last indexed block.
+	pub last: u32,
+	/// Hash of the last indexed block.
+	pub last_hash: H256,
+}
+
+fn meta_key() -> Vec<u8> {
+	(HISTORY_KEY_PREFIX, b'm').encode()
+}
+
+/// Chunks holding changes of `key`.
+fn chunks_key(key: &[u8]) -> Vec<u8> {
+	(HISTORY_KEY_PREFIX, b'c', key).encode()
+}
+
+/// Changes of `key` in `chunk`, as (block, value before the block).
+fn chunk_key(key: &[u8], chunk: u32) -> Vec<u8> {
+	(HISTORY_KEY_PREFIX, b'k', key, chunk).encode()
+}
+
+/// Entries changed by block `number`.
+fn block_key(number: u32) -> Vec<u8> {
+	(HISTORY_KEY_PREFIX, b'b', number).encode()
+}
+
+/// Changes of the imported block `hash`, until it is finalized.
+fn pending_key(hash: H256) -> Vec<u8> {
+	(HISTORY_KEY_PREFIX, b'p', hash).encode()
+}
+
+/// The imported blocks with pending changes, as (number, hash).
+fn pending_index_key() -> Vec<u8> {
+	(HISTORY_KEY_PREFIX, b'q').encode()
+}
+
+fn storage_prefix(pallet: &[u8], item: &[u8]) -> Vec<u8> {
+	[twox_128(pallet), twox_128(item)].concat()
+}
+
+fn blake2_128_concat(data: &[u8]) -> Vec<u8> {
+	[&blake2_128(data)[..], data].concat()
+}
+
+/// Storage prefixes of the indexed maps.
+pub fn indexed_prefixes() -> impl Iterator<Item = Vec<u8>> {
+	INDEXED_MAPS.iter().map(|(pallet, item)| storage_prefix(pallet, item))
+}
+
+/// Whether the history of storage `key` is indexed.
+pub fn is_indexed(key: &[u8]) -> bool {
+	indexed_prefixes().any(|prefix| key.starts_with(&prefix))
+}
+
+fn account_key(account: &AccountId) -> Vec<u8> {
+	[storage_prefix(b"System", b"Account"), blake2_128_concat(&account.encode())].concat()
+}
+
+fn code_key(address: H160) -> Vec<u8> {
+	[storage_prefix(b"EVM", b"AccountCodes"), blake2_128_concat(address.as_bytes())].concat()
+}
+
+fn storage_key(address: H160, index: H256) -> Vec<u8> {
+	[
+		storage_prefix(b"EVM", b"AccountStorages"),
+		blake2_128_concat(address.as_bytes()),
+		blake2_128_concat(index.as_bytes()),
+	]
+	.concat()
+}
+
+fn mapping_key(address: H160) -> Vec<u8> {
+	[
+		storage_prefix(b"AccountMapping", b"Accounts"),
+		twox_64(address.as_bytes()).to_vec(),
+		address.as_bytes().to_vec(),
+	]
+	.concat()
+}
+
+/// The account of `address` when it is not bound with `AccountMapping`.
+fn default_account_id(address: H160) -> AccountId {
+	AccountId::from(sp_core::hashing::blake2_256(&[&b"evm:"[..], address.as_bytes()].concat()))
+}
+
+fn decode<T: Decode>(encoded: &[u8]) -> sp_blockchain::Result<T> {
+	T::decode(&mut &encoded[..]).map_err(|e| sp_blockchain::Error::Backend(e.to_string()))
+}
+
+fn get<C: AuxStore, T: Decode>(client: &C, key: &[u8]) -> sp_blockchain::Result<Option<T>> {
+	client.get_aux(key)?.map(|encoded| decode(&encoded)).transpose()
+}
+
+/// The blocks covered by the index, `None` if nothing was indexed.
+pub fn load_meta<C: AuxStore>(client: &C) -> sp_blockchain::Result<Option<HistoryMeta>> {
+	get(client, &meta_key())
+}
+
+/// Aux storage writes of a block, read back before they are committed.
+struct Batch<'a, C> {
+	client: &'a C,
+	overlay: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<'a, C: AuxStore> Batch<'a, C> {
+	fn get<T: Decode>(&self, key: &[u8]) -> sp_blockchain::Result<Option<T>> {
+		match self.overlay.get(key) {
+			Some(value) => value.as_deref().map(decode).transpose(),
+			None => get(self.client, key),
+		}
+	}
+
+	fn put(&mut self, key: Vec<u8>, value: &impl Encode) {
+		self.overlay.insert(key, Some(value.encode()));
+	}
+
+	fn delete(&mut self, key: Vec<u8>) {
+		self.overlay.insert(key, None);
+	}
+
+	/// Drop all the changes recorded in `meta`.
+	fn clear(&mut self, meta: Option<HistoryMeta>) -> sp_blockchain::Result<()> {
+		if let Some(meta) = meta {
+			for number in meta.first + 1..=meta.last {
+				self.prune(number)?;
+			}
+		}
+		self.delete(meta_key());
+		Ok(())
+	}
+
+	/// Drop the changes recorded for block `number`, no longer needed by any query.
+	fn prune(&mut self, number: u32) -> sp_blockchain::Result<()> {
+		let keys: Vec<Vec<u8>> = self.get(&block_key(number))?.unwrap_or_default();
+		let chunk = number / CHUNK_LENGTH;
+		for key in keys {
+			let mut changes: Vec<(u32, Option<Vec<u8>>)> =
+				self.get(&chunk_key(&key, chunk))?.unwrap_or_default();
+			changes.retain(|(block, _)| *block > number);
+			if !changes.is_empty() {
+				self.put(chunk_key(&key, chunk), &changes);
+				continue;
+			}
+			self.delete(chunk_key(&key, chunk));
+			let mut chunks: Vec<u32> = self.get(&chunks_key(&key))?.unwrap_or_default();
+			chunks.retain(|c| *c != chunk);
+			match chunks.is_empty() {
+				true => self.delete(chunks_key(&key)),
+				false => self.put(chunks_key(&key), &chunks),
+			}
+		}
+		self.delete(block_key(number));
+		Ok(())
+	}
+
+	fn commit(self) -> sp_blockchain::Result<()> {
+		let (inserts, deletes): (Vec<_>, Vec<_>) =
+			self.overlay.iter().partition(|(_, value)| value.is_some());
+		let inserts: Vec<_> =
+			inserts.iter().filter_map(|(key, value)| Some((&key[..], value.as_deref()?))).collect();
+		let deletes: Vec<_> = deletes.iter().map(|(key, _)| &key[..]).collect();
+		self.client.insert_aux(&inserts, &deletes)
+	}
+}
+
+/// Indexed entries changed by a block, each with its value before the block.
+pub type Changes = Vec<(Vec<u8>, Option<Vec<u8>>)>;
+
+/// Keep the changes of the imported block `hash` until it is finalized.
+pub fn store_pending<C: AuxStore>(
+	client: &C,
+	number: u32,
+	hash: H256,
+	changes: &Changes,
+) -> sp_blockchain::Result<()> {
+	let mut index: Vec<(u32, H256)> = get(client, &pending_index_key())?.unwrap_or_default();
+	if !index.contains(&(number, hash)) {
+		index.push((number, hash));
+	}
+	client.insert_aux(
+		&[(&pending_key(hash)[..], &changes.encode()[..]), (&pending_index_key(), &index.encode())],
+		&[],
+	)
+}
+
+/// The pending changes of block `hash`, `None` if they were not seen.
+pub fn load_pending<C: AuxStore>(client: &C, hash: H256) -> sp_blockchain::Result<Option<Changes>> {
+	get(client, &pending_key(hash))
+}
+
+/// Drop the pending changes of the blocks up to the finalized block `number`, stored or
+/// abandoned.
+pub fn prune_pending<C: AuxStore>(client: &C, number: u32) -> sp_blockchain::Result<()> {
+	let index: Vec<(u32, H256)> = get(client, &pending_index_key())?.unwrap_or_default();
+	let (pruned, kept): (Vec<_>, Vec<_>) = index.into_iter().partition(|(n, _)| *n <= number);
+	if pruned.is_empty() {
+		return Ok(());
+	}
+	let deleted: Vec<_> = pruned.into_iter().map(|(_, hash)| pending_key(hash)).collect();
+	client.insert_aux(
+		&[(&pending_index_key()[..], &kept.encode()[..])],
+		&deleted.iter().map(|key| &key[..]).collect::<Vec<_>>(),
+	)
+}
+
+/// Record the indexed entries changed by the finalized block `number`, each with its value
+/// before the block, and prune the changes older than `depth` blocks.
+///
+/// Blocks must be stored in order. After a gap the state before it can no longer be recovered:
+/// the history starts over at the block and the changes recorded before are deleted.
+pub fn store_changes<C: AuxStore>(
+	client: &C,
+	number: u32,
+	hash: H256,
+	changes: Changes,
+	depth: Option<u32>,
+) -> sp_blockchain::Result<()> {
+	let mut batch = Batch { client, overlay: BTreeMap::new() };
+	let mut previous = load_meta(client)?;
+	if previous.is_some_and(|meta| meta.last + 1 != number) {
+		batch.clear(previous.take())?;
+	}
+	let first = previous.map_or(number.saturating_sub(1), |meta| meta.first);
+	let mut meta = HistoryMeta { first, last: number, last_hash: hash };
+
+	let chunk = number / CHUNK_LENGTH;
+	let mut keys = Vec::with_capacity(changes.len());
+	for (key, value) in changes {
+		let mut chunks: Vec<u32> = batch.get(&chunks_key(&key))?.unwrap_or_default();
+		if chunks.last() != Some(&chunk) {
+			chunks.push(chunk);
+			batch.put(chunks_key(&key), &chunks);
+		}
+		let mut records: Vec<(u32, Option<Vec<u8>>)> =
+			batch.get(&chunk_key(&key, chunk))?.unwrap_or_default();
+		records.push((number, value));
+		batch.put(chunk_key(&key, chunk), &records);
+		keys.push(key);
+	}
+	if !keys.is_empty() {
+		batch.put(block_key(number), &keys);
+	}
+
+	if let Some(depth) = depth {
+		let oldest = number.saturating_sub(depth);
+		// Blocks up to the previous oldest one were pruned already, the ones after the last
+		// indexed block have no changes.
+		let pruned = previous.map_or(0, |meta| meta.last.saturating_sub(depth));
+		let end = previous.map_or(0, |meta| meta.last).min(oldest);
+		for block in (pruned + 1)..=end {
+			batch.prune(block)?;
+		}
+		meta.first = meta.first.max(oldest);
+	}
+
+	batch.put(meta_key(), &meta);
+	batch.commit()
+}
+
+/// Start the history over at the finalized block `number`, whose changes are unknown.
+pub fn reset_history<C: AuxStore>(
+	client: &C,
+	number: u32,
+	hash: H256,
+) -> sp_blockchain::Result<()> {
+	let mut batch = Batch { client, overlay: BTreeMap::new() };
+	batch.clear(load_meta(client)?)?;
+	batch.put(meta_key(), &HistoryMeta { first: number, last: number, last_hash: hash });
+	batch.commit()
+}
+
+/// The value of storage `key` at block `number` as recorded by its first change after the block,
+/// `None` if it did not change since.
+fn recorded_value<C: AuxStore>(
+	client: &C,
+	key: &[u8],
+	number: u32,
+) -> sp_blockchain::Result<Option<Option<Vec<u8>>>> {
+	let chunks: Vec<u32> = get(client, &chunks_key(key))?.unwrap_or_default();
+	for chunk in chunks.into_iter().filter(|chunk| *chunk >= number / CHUNK_LENGTH) {
+		let changes: Vec<(u32, Option<Vec<u8>>)> =
+			get(client, &chunk_key(key, chunk))?.unwrap_or_default();
+		if let Some((_, value)) = changes.into_iter().find(|(block, _)| *block > number) {
+			return Ok(Some(value));
+		}
+	}
+	Ok(None)
+}
+
+fn pruned_err(number: u32, first: Option<u32>) -> ErrorObjectOwned {
+	let message = match first {
+		Some(first) => {
+			format!("state pruned at block {number}, EVM state history starts at block {first}")
+		},
+		None => format!("state pruned at block {number}"),
+	};
+	ErrorObject::owned(STATE_PRUNED_CODE, message, None::<()>)
+}
+
+#[rpc(server)]
+pub trait HistoryApi {
+	#[method(name = "eth_getBalance")]
+	async fn balance(&self, address: H160, number: Option<BlockNumberOrHash>) -> RpcResult<U256>;
+
+	#[method(name = "eth_getStorageAt")]
+	async fn storage_at(
+		&self,
+		address: H160,
+		index: U256,
+		number: Option<BlockNumberOrHash>,
+	) -> RpcResult<H256>;
+
+	#[method(name = "eth_getTransactionCount")]
+	async fn transaction_count(
+		&self,
+		address: H160,
+		number: Option<BlockNumberOrHash>,
+	) -> RpcResult<U256>;
+
+	#[method(name = "eth_getCode")]
+	async fn code_at(&self, address: H160, number: Option<BlockNumberOrHash>) -> RpcResult<Bytes>;
+}
+
+pub struct History<B: BlockT, C, BE> {
+	client: Arc<C>,
+	backend: Arc<dyn fc_api::Backend<B>>,
+	/// Frontier's methods, answering while the state of the block is available.
+	frontier: Methods,
+	/// Whether the index is maintained.
+	enabled: bool,
+	_marker: PhantomData<BE>,
+}
+
+impl<B: BlockT, C, BE> History<B, C, BE> {
+	pub fn new(
+		client: Arc<C>,
+		backend: Arc<dyn fc_api::Backend<B>>,
+		frontier: Methods,
+		enabled: bool,
+	) -> Self {
+		Self { client, backend, frontier, enabled, _marker: PhantomData }
+	}
+}
+
+impl<B, C, BE> History<B, C, BE>
+where
+	B: BlockT<Hash = H256>,
+	C: HeaderBackend<B> + StorageProvider<B, BE> + AuxStore + Send + Sync + 'static,
+	BE: Backend<B> + 'static,
+{
+	async fn frontier<T: DeserializeOwned>(
+		&self,
+		method: &str,
+		params: ArrayParams,
+	) -> RpcResult<T> {
+		self.frontier.call(method, params).await.map_err(|e| match e {
+			MethodsError::JsonRpc(e) => e,
+			e => internal_err(e.to_string()),
+		})
+	}
+
+	/// The number of `block` if its state was pruned.
+	async fn pruned(&self, block: Option<BlockNumberOrHash>) -> RpcResult<Option<u32>> {
+		// Frontier reports unknown blocks.
+		let Some(id) = frontier_backend_client::native_block_id::<B, C>(
+			self.client.as_ref(),
+			self.backend.as_ref(),
+			block,
+		)
+		.await?
+		else {
+			return Ok(None);
+		};
+		let Ok(hash) = self.client.expect_block_hash_from_id(&id) else {
+			return Ok(None);
+		};
+		let code = StorageKey(well_known_keys::CODE.to_vec());
+		if self.client.storage_hash(hash, &code).is_ok() {
+			return Ok(None);
+		}
+		let number = self
+			.client
+			.expect_block_number_from_id(&id)
+			.map_err(|e| internal_err(e.to_string()))?;
+		Ok(Some(number.unique_saturated_into()))
+	}
+
+	/// The blocks covered by the index, if it covers `number`.
+	fn meta(&self, number: u32) -> RpcResult<HistoryMeta> {
+		if !self.enabled {
+			return Err(pruned_err(number, None));
+		}
+		match load_meta(self.client.as_ref()).map_err(|e| internal_err(e.to_string()))? {
+			Some(meta) if meta.first <= number => Ok(meta),
+			meta => Err(pruned_err(number, meta.map(|meta| meta.first))),
+		}
+	}
+
+	/// The value of storage `key` at block `number`.
+	fn value_at(&self, meta: &HistoryMeta, key: &[u8], number: u32) -> RpcResult<Option<Vec<u8>>> {
+		let client = self.client.as_ref();
+		let recorded =
+			recorded_value(client, key, number).map_err(|e| internal_err(e.to_string()))?;
+		if let Some(value) = recorded {
+			return Ok(value);
+		}
+		// Unchanged since `number`.
+		client
+			.storage(meta.last_hash, &StorageKey(key.to_vec()))
+			.map(|value| value.map(|value| value.0))
+			.map_err(|e| internal_err(e.to_string()))
+	}
+
+	fn decoded_at<T: Decode>(
+		&self,
+		meta: &HistoryMeta,
+		key: &[u8],
+		number: u32,
+	) -> RpcResult<Option<T>> {
+		self.value_at(meta, key, number)?
+			.map(|value| decode(&value).map_err(|e| internal_err(e.to_string())))
+			.transpose()
+	}
+
+	/// The system account of `address` at block `number`.
+	fn account(&self, address: H160, number: u32) -> RpcResult<AccountInfo> {
+		let meta = self.meta(number)?;
+		let account = self
+			.decoded_at(&meta, &mapping_key(address), number)?
+			.unwrap_or_else(|| default_account_id(address));
+		Ok(self.decoded_at(&meta, &account_key(&account), number)?.unwrap_or_default())
+	}
+}
+
+#[async_trait]
+impl<B, C, BE> HistoryApiServer for History<B, C, BE>
+where
+	B: BlockT<Hash = H256>,
+	C: HeaderBackend<B> + StorageProvider<B, BE> + AuxStore + Send + Sync + 'static,
+	BE: Backend<B> + 'static,
+{
+	async fn balance(&self, address: H160, number: Option<BlockNumberOrHash>) -> RpcResult<U256> {
+		let Some(pruned) = self.pruned(number.clone()).await? else {
+			return self.frontier("eth_getBalance", rpc_params![address, number]).await;
+		};
+		// The reducible balance `pallet_evm` reports, keeping the existential deposit.
+		let data = self.account(address, pruned)?.data;
+		let mut untouchable = data.frozen.saturating_sub(data.reserved);
+		if data.free != 0 {
+			untouchable = untouchable.max(EXISTENTIAL_DEPOSIT);
+		}
+		Ok(U256::from(data.free.saturating_sub(untouchable)))
+	}
+
+	async fn storage_at(
+		&self,
+		address: H160,
+		index: U256,
+		number: Option<BlockNumberOrHash>,
+	) -> RpcResult<H256> {
+		let Some(pruned) = self.pruned(number.clone()).await? else {
+			return self.frontier("eth_getStorageAt", rpc_params![address, index, number]).await;
+		};
+		let meta = self.meta(pruned)?;
+		let key = storage_key(address, H256::from(index.to_big_endian()));
+		Ok(self.decoded_at(&meta, &key, pruned)?.unwrap_or_default())
+	}
+
+	async fn transaction_count(
+		&self,
+		address: H160,
+		number: Option<BlockNumberOrHash>,
+	) -> RpcResult<U256> {
+		let Some(pruned) = self.pruned(number.clone()).await? else {
+			return self.frontier("eth_getTransactionCount", rpc_params![address, number]).await;
+		};
+		Ok(U256::from(self.account(address, pruned)?.nonce))
+	}
+
+	async fn code_at(&self, address: H160, number: Option<BlockNumberOrHash>) -> RpcResult<Bytes> {
+		let Some(pruned) = self.pruned(number.clone()).await? else {
+			return self.frontier("eth_getCode", rpc_params![address, number]).await;
+		};
+		let meta = self.meta(pruned)?;
+		let code: Option<Vec<u8>> = self.decoded_at(&meta, &code_key(address), pruned)?;
+		Ok(Bytes(code.unwrap_or_default()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::{collections::HashMap, sync::Mutex};
+
+	#[derive(Default)]
+	struct MemoryAux(Mutex<HashMap<Vec<u8>, Vec<u8>>>);
+
+	impl AuxStore for MemoryAux {
+		fn insert_aux<
+			'a,
+			'b: 'a,
+			'c: 'a,
+			I: IntoIterator<Item = &'a (&'c [u8], &'c [u8])>,
+			D: IntoIterator<Item = &'a &'b [u8]>,
+		>(
+			&self,
+			insert: I,
+			delete: D,
+		) -> sp_blockchain::Result<()> {
+			let mut store = self.0.lock().unwrap();
+			for (key, value) in insert {
+				store.insert(key.to_vec(), value.to_vec());
+			}
+			for key in delete {
+				store.remove(*key);
+			}
+			Ok(())
+		}
+
+		fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+			Ok(self.0.lock().unwrap().get(key).cloned())
+		}
+	}
+
+	const KEY: &[u8] = b"key";
+
+	/// Store block `number`, changing `KEY` from `before`.
+	fn store(client: &MemoryAux, number: u32, before: Option<u8>, depth: Option<u32>) {
+		let changes = vec![(KEY.to_vec(), before.map(|value| vec![value]))];
+		store_changes(client, number, H256::repeat_byte(number as u8), changes, depth).unwrap();
+	}
+
+	fn value(client: &MemoryAux, number: u32) -> Option<Option<Vec<u8>>> {
+		recorded_value(client, KEY, number).unwrap()
+	}
+
+	#[test]
+	fn values_are_looked_up_from_their_next_change() {
+		let client = MemoryAux::default();
+		store(&client, 1, None, None);
+		store_changes(&client, 2, H256::repeat_byte(2), Vec::new(), None).unwrap();
+		store(&client, 3, Some(1), None);
+		store(&client, 4, Some(3), None);
+
+		let meta = load_meta(&client).unwrap().unwrap();
+		assert_eq!((meta.first, meta.last), (0, 4));
+		assert_eq!(value(&client, 0), Some(None));
+		assert_eq!(value(&client, 1), Some(Some(vec![1])));
+		assert_eq!(value(&client, 2), Some(Some(vec![1])));
+		assert_eq!(value(&client, 3), Some(Some(vec![3])));
+		// Unchanged since the last indexed block.
+		assert_eq!(value(&client, 4), None);
+	}
+
+	#[test]
+	fn changes_past_the_depth_are_pruned() {
+		let client = MemoryAux::default();
+		for number in 1..=5 {
+			store(&client, number, Some(number as u8 - 1), Some(2));
+		}
+
+		let meta = load_meta(&client).unwrap().unwrap();
+		assert_eq!((meta.first, meta.last), (3, 5));
+		assert_eq!(value(&client, 3), Some(Some(vec![3])));
+		assert_eq!(client.get_aux(&block_key(3)).unwrap(), None);
+		let changes: Vec<(u32, Option<Vec<u8>>)> =
+			get(&client, &chunk_key(KEY, 0)).unwrap().unwrap();
+		assert_eq!(changes.iter().map(|(block, _)| *block).collect::<Vec<_>>(), vec![4, 5]);
+	}
+
+	#[test]
+	fn a_gap_starts_the_history_over() {
+		let client = MemoryAux::default();
+		store(&client, 1, None, None);
+		store(&client, 2, Some(1), None);
+		store(&client, 5, Some(4), None);
+
+		let meta = load_meta(&client).unwrap().unwrap();
+		assert_eq!((meta.first, meta.last), (4, 5));
+		// The changes before the gap are deleted.
+		assert_eq!(client.get_aux(&block_key(1)).unwrap(), None);
+		let changes: Vec<(u32, Option<Vec<u8>>)> =
+			get(&client, &chunk_key(KEY, 0)).unwrap().unwrap();
+		assert_eq!(changes, vec![(5, Some(vec![4]))]);
+
+		reset_history(&client, 6, H256::repeat_byte(6)).unwrap();
+		let meta = load_meta(&client).unwrap().unwrap();
+		assert_eq!((meta.first, meta.last), (6, 6));
+		assert_eq!(client.get_aux(&chunks_key(KEY)).unwrap(), None);
+	}
+
+	#[test]
+	fn pending_changes_are_kept_until_finality() {
+		let client = MemoryAux::default();
+		let changes = vec![(KEY.to_vec(), None)];
+		store_pending(&client, 1, H256::repeat_byte(1), &changes).unwrap();
+		store_pending(&client, 1, H256::repeat_byte(0xf1), &changes).unwrap();
+		store_pending(&client, 2, H256::repeat_byte(2), &changes).unwrap();
+
+		prune_pending(&client, 1).unwrap();
+		assert_eq!(load_pending(&client, H256::repeat_byte(1)).unwrap(), None);
+		assert_eq!(load_pending(&client, H256::repeat_byte(0xf1)).unwrap(), None);
+		assert_eq!(load_pending(&client, H256::repeat_byte(2)).unwrap(), Some(changes));
+	}
+}
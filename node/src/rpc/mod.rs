@@ -7,15 +7,19 @@
 
 use std::sync::Arc;
 
-use fintradex_runtime::{opaque::Block, AccountId, Balance, Nonce};
+use fintradex_runtime::{
+	opaque::{Block, Hash},
+	AccountId, Balance, Nonce,
+};
+use futures::channel::mpsc;
 
 use sc_client_api::{
 	backend::{AuxStore, Backend, StorageProvider},
 	client::BlockchainEvents,
 	UsageProvider,
 };
+use sc_consensus_manual_seal::rpc::{EngineCommand, ManualSeal, ManualSealApiServer};
 pub use sc_rpc::{DenyUnsafe, SubscriptionTaskExecutor};
-use sc_transaction_pool::ChainApi;
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::{CallApiAt, ProvideRuntimeApi};
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
@@ -23,22 +27,39 @@ use sp_consensus_aura::{sr25519::AuthorityId as AuraId, AuraApi};
 use sp_runtime::traits::Block as BlockT;
 use substrate_frame_rpc_system::SystemApiServer;
 
+mod debug;
 mod eth;
-pub use self::eth::{create_eth, overrides_handle, EthDeps};
+mod history;
+mod private;
+mod simulate;
+mod sync_status;
+mod trace;
+pub use self::{
+	debug::replay_block,
+	eth::{create_eth, overrides_handle, EthDeps},
+	history::{
+		indexed_prefixes, is_indexed, load_meta, load_pending, prune_pending, reset_history,
+		store_changes, store_pending, Changes,
+	},
+	sync_status::{mapping_sync_status, MappingSyncStatus},
+	trace::{advance_traces, load_traces, load_traces_meta, store_traces, TraceLimits},
+};
 
 /// A type representing all RPC extensions.
 pub type RpcExtension = jsonrpsee::RpcModule<()>;
 
 /// Full client dependencies
-pub struct FullDeps<C, P, A: ChainApi, CT, CIDP> {
+pub struct FullDeps<C, P, CT, CIDP> {
 	/// The client instance to use.
 	pub client: Arc<C>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// Manual seal command sink, on development nodes started with `--sealing`.
+	pub command_sink: Option<mpsc::Sender<EngineCommand<Hash>>>,
 	/// Ethereum-compatibility specific dependencies.
-	pub eth: EthDeps<C, P, A, CT, Block, CIDP>,
+	pub eth: EthDeps<C, P, CT, Block, CIDP>,
 }
 pub struct DefaultEthConfig<C, BE>(std::marker::PhantomData<(C, BE)>);
 
@@ -53,8 +74,8 @@ where
 }
 
 /// Instantiate all Full RPC extensions.
-pub fn create_full<C, P, BE, A, CT, CIDP>(
-	deps: FullDeps<C, P, A, CT, CIDP>,
+pub fn create_full<C, P, BE, CT, CIDP>(
+	deps: FullDeps<C, P, CT, CIDP>,
 	subscription_task_executor: SubscriptionTaskExecutor,
 	pubsub_notification_sinks: Arc<
 		fc_mapping_sync::EthereumBlockNotificationSinks<
@@ -72,16 +93,18 @@ where
 		+ UsageProvider<Block>
 		+ HeaderMetadata<Block, Error = BlockChainError>
 		+ 'static,
-	C: CallApiAt<Block>,
+	C: CallApiAt<Block> + sc_client_api::BlockBackend<Block>,
+	C::Api: fintradex_runtime::evm_tracing::DebugRuntimeApi<Block>,
+	C::Api: fintradex_runtime::simulate::SimulateRuntimeApi<Block>,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: sp_block_builder::BlockBuilder<Block>,
+	C::Api: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>,
 	C::Api: fp_rpc::ConvertTransactionRuntimeApi<Block>,
 	C::Api: fp_rpc::EthereumRuntimeRPCApi<Block>,
 	C::Api: AuraApi<Block, AuraId>,
 	BE: Backend<Block> + 'static,
-	P: TransactionPool<Block = Block> + 'static,
-	A: ChainApi<Block = Block> + 'static,
+	P: TransactionPool<Block = Block, Hash = <Block as BlockT>::Hash> + 'static,
 	CIDP: sp_inherents::CreateInherentDataProviders<Block, ()> + Send + 'static,
 	CT: fp_rpc::ConvertTransaction<<Block as BlockT>::Extrinsic> + Send + Sync + 'static,
 {
@@ -89,13 +112,17 @@ where
 	use substrate_frame_rpc_system::System;
 
 	let mut io = RpcExtension::new(());
-	let FullDeps { client, pool, deny_unsafe, eth } = deps;
+	let FullDeps { client, pool, deny_unsafe, command_sink, eth } = deps;
 
 	io.merge(System::new(client.clone(), pool, deny_unsafe).into_rpc())?;
 	io.merge(TransactionPayment::new(client).into_rpc())?;
 
+	if let Some(command_sink) = command_sink {
+		io.merge(ManualSeal::new(command_sink).into_rpc())?;
+	}
+
 	// Ethereum compatibility RPCs
-	let io = create_eth::<Block, C, P, CT, BE, A, CIDP, DefaultEthConfig<C, BE>>(
+	let io = create_eth::<Block, C, P, CT, BE, CIDP, DefaultEthConfig<C, BE>>(
 		io,
 		eth,
 		subscription_task_executor,
@@ -0,0 +1,198 @@
+//! `eth_sendBundle`, `eth_sendPrivateTransaction` and `eth_cancelPrivateTransaction`, feeding
+//! the [`PrivatePool`] of the local collator.
+
+use std::sync::Arc;
+
+use fc_rpc::internal_err;
+use fc_rpc_core::types::Bytes;
+use fp_rpc::ConvertTransactionRuntimeApi;
+use jsonrpsee::{
+	core::{async_trait, RpcResult},
+	proc_macros::rpc,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{keccak_256, H160, H256, U256};
+use sp_runtime::{
+	traits::{Block as BlockT, UniqueSaturatedInto},
+	transaction_validity::TransactionSource,
+};
+use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
+
+use crate::private_pool::{self, Bundle, PrivatePool, PrivateTransaction};
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleRequest {
+	/// Signed raw transactions, included in this order.
+	pub txs: Vec<Bytes>,
+	/// Last block the bundle may be included in.
+	pub block_number: U256,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivateTransactionRequest {
+	/// Signed raw transaction.
+	pub tx: Bytes,
+	/// Last block the transaction may be included in, `--private-tx-lifetime` blocks from now
+	/// by default.
+	pub max_block_number: Option<U256>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelPrivateTransactionRequest {
+	pub tx_hash: H256,
+	/// `personal_sign` signature of the 32 bytes of `tx_hash` by the sender of the transaction,
+	/// or of the first transaction of the bundle.
+	pub signature: Bytes,
+}
+
+/// The signer of the EIP-191 message `message`, given its 65-byte `signature`.
+fn personal_signer(message: &[u8], signature: &[u8]) -> Option<H160> {
+	let mut signature: [u8; 65] = signature.try_into().ok()?;
+	if signature[64] >= 27 {
+		signature[64] -= 27;
+	}
+	let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
+	let digest = keccak_256(&[prefix.as_bytes(), message].concat());
+	let public = sp_io::crypto::secp256k1_ecdsa_recover(&signature, &digest).ok()?;
+	Some(H160::from_slice(&keccak_256(&public)[12..]))
+}
+
+#[rpc(server)]
+pub trait PrivateApi {
+	#[method(name = "eth_sendBundle")]
+	async fn send_bundle(&self, bundle: BundleRequest) -> RpcResult<Value>;
+
+	#[method(name = "eth_sendPrivateTransaction")]
+	async fn send_private_transaction(&self, request: PrivateTransactionRequest)
+		-> RpcResult<H256>;
+
+	#[method(name = "eth_cancelPrivateTransaction")]
+	async fn cancel_private_transaction(
+		&self,
+		request: CancelPrivateTransactionRequest,
+	) -> RpcResult<bool>;
+}
+
+pub struct Private<B: BlockT, C> {
+	client: Arc<C>,
+	pool: Arc<PrivatePool<B>>,
+	/// Maximum number of blocks a transaction stays pooled.
+	lifetime: u32,
+}
+
+impl<B: BlockT, C> Private<B, C> {
+	pub fn new(client: Arc<C>, pool: Arc<PrivatePool<B>>, lifetime: u32) -> Self {
+		Self { client, pool, lifetime }
+	}
+}
+
+impl<B, C> Private<B, C>
+where
+	B: BlockT<Hash = H256>,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: ConvertTransactionRuntimeApi<B> + TaggedTransactionQueue<B>,
+{
+	/// The last block a transaction may be pooled for, given the one requested.
+	fn max_block(&self, requested: Option<U256>) -> RpcResult<u32> {
+		let best: u32 = self.client.info().best_number.unique_saturated_into();
+		let latest = best.saturating_add(self.lifetime);
+		let requested = match requested {
+			Some(number) if number > U256::from(latest) => {
+				return Err(internal_err(format!(
+					"block number is too far ahead (maximum: {latest})"
+				)))
+			},
+			Some(number) => number.low_u32(),
+			None => latest,
+		};
+		if requested <= best {
+			return Err(internal_err("block number is in the past"));
+		}
+		Ok(requested)
+	}
+
+	/// Decode `bytes` as a signed Ethereum transaction and check it against the best block,
+	/// returning its hash and its sender.
+	fn transaction(&self, bytes: &Bytes) -> RpcResult<(H256, H160, PrivateTransaction<B>)> {
+		let transaction: ethereum::TransactionV2 =
+			ethereum::EnvelopedDecodable::decode(&bytes.0)
+				.map_err(|_| internal_err("decode transaction failed"))?;
+		let hash = transaction.hash();
+		let public = fc_rpc::public_key(&transaction)
+			.map_err(|_| internal_err(format!("invalid signature of transaction {hash:?}")))?;
+		let sender = H160::from_slice(&keccak_256(&public)[12..]);
+
+		let best = self.client.info().best_hash;
+		let api = self.client.runtime_api();
+		let extrinsic =
+			api.convert_transaction(best, transaction).map_err(|e| internal_err(e.to_string()))?;
+		api.validate_transaction(best, TransactionSource::External, extrinsic.clone(), best)
+			.map_err(|e| internal_err(e.to_string()))?
+			.map_err(|e| internal_err(format!("invalid transaction {hash:?}: {e:?}")))?;
+
+		Ok((hash, sender, PrivateTransaction::new(extrinsic)))
+	}
+
+	fn submit(&self, bundle: Bundle<B>) -> RpcResult<()> {
+		self.pool.submit(bundle).map_err(|e| match e {
+			private_pool::Error::Full => internal_err("private pool is full"),
+			private_pool::Error::AlreadyPooled => internal_err("already pooled"),
+			private_pool::Error::NotSender => internal_err("not the sender"),
+		})
+	}
+}
+
+#[async_trait]
+impl<B, C> PrivateApiServer for Private<B, C>
+where
+	B: BlockT<Hash = H256>,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: ConvertTransactionRuntimeApi<B> + TaggedTransactionQueue<B>,
+{
+	async fn send_bundle(&self, bundle: BundleRequest) -> RpcResult<Value> {
+		if bundle.txs.is_empty() {
+			return Err(internal_err("empty bundle"));
+		}
+		let max_block = self.max_block(Some(bundle.block_number))?;
+		let transactions =
+			bundle.txs.iter().map(|tx| self.transaction(tx)).collect::<RpcResult<Vec<_>>>()?;
+		let sender = transactions[0].1;
+		let (hashes, transactions): (Vec<_>, Vec<_>) =
+			transactions.into_iter().map(|(hash, _, transaction)| (hash, transaction)).unzip();
+
+		// The bundle hash of Flashbots: the hash of the transaction hashes.
+		let hash = H256(keccak_256(
+			&hashes.iter().flat_map(|hash| hash.as_bytes()).copied().collect::<Vec<_>>(),
+		));
+		self.submit(Bundle { hash, sender, max_block, transactions })?;
+		Ok(json!({ "bundleHash": hash }))
+	}
+
+	async fn send_private_transaction(
+		&self,
+		request: PrivateTransactionRequest,
+	) -> RpcResult<H256> {
+		let max_block = self.max_block(request.max_block_number)?;
+		let (hash, sender, transaction) = self.transaction(&request.tx)?;
+		self.submit(Bundle { hash, sender, max_block, transactions: vec![transaction] })?;
+		Ok(hash)
+	}
+
+	async fn cancel_private_transaction(
+		&self,
+		request: CancelPrivateTransactionRequest,
+	) -> RpcResult<bool> {
+		let sender = personal_signer(request.tx_hash.as_bytes(), &request.signature)
+			.ok_or_else(|| internal_err("invalid signature"))?;
+		self.pool.cancel(request.tx_hash, sender).map_err(|e| match e {
+			private_pool::Error::NotSender => internal_err("not signed by the sender"),
+			e => internal_err(format!("{e:?}")),
+		})
+	}
+}
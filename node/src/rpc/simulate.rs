@@ -0,0 +1,334 @@
+//! `eth_simulateV1`, running blocks of calls with state and block overrides through
+//! [`SimulateRuntimeApi`](fintradex_runtime::simulate::SimulateRuntimeApi).
+//!
+//! Simulations run on the blocking pool and share the permits of the tracing requests.
+
+use std::{collections::BTreeMap, marker::PhantomData, sync::Arc};
+
+use fc_rpc::{frontier_backend_client, internal_err};
+use fc_rpc_core::types::{BlockNumberOrHash, Bytes};
+use fintradex_runtime::simulate::{
+	AccountOverride, BlockOverrides, BlockResult, SimulateError, SimulateRuntimeApi,
+	SimulatedBlock, SimulatedCall, MAX_OVERRIDE_SLOTS,
+};
+use jsonrpsee::{
+	core::{async_trait, RpcResult},
+	proc_macros::rpc,
+	types::{ErrorObject, ErrorObjectOwned},
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{H160, H256, U256};
+use sp_runtime::traits::{Block as BlockT, UniqueSaturatedInto};
+use tokio::sync::Semaphore;
+
+/// Maximum number of blocks a single request may simulate.
+const MAX_BLOCKS: usize = 256;
+
+/// Maximum number of calls a single request may simulate, over all its blocks.
+const MAX_CALLS: usize = 1000;
+
+/// Error code of `eth_simulateV1` for a block number not above the one of the previous block.
+const BLOCK_NUMBER_INVALID_CODE: i32 = -38020;
+
+/// Error code of `eth_simulateV1` for a request beyond a limit of the node.
+const LIMIT_EXCEEDED_CODE: i32 = -38026;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatePayload {
+	pub block_state_calls: Vec<BlockStateCalls>,
+	/// Check nonces, balances and fees like for transactions.
+	#[serde(default)]
+	pub validation: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockStateCalls {
+	pub block_overrides: Option<BlockOverridesRequest>,
+	pub state_overrides: Option<BTreeMap<H160, AccountOverrideRequest>>,
+	#[serde(default)]
+	pub calls: Vec<CallRequest>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockOverridesRequest {
+	pub number: Option<U256>,
+	pub time: Option<U256>,
+	pub base_fee_per_gas: Option<U256>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverrideRequest {
+	pub balance: Option<U256>,
+	pub nonce: Option<U256>,
+	pub code: Option<Bytes>,
+	pub state: Option<BTreeMap<H256, H256>>,
+	pub state_diff: Option<BTreeMap<H256, H256>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListItem {
+	pub address: H160,
+	pub storage_keys: Vec<H256>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallRequest {
+	#[serde(default)]
+	pub from: H160,
+	pub to: Option<H160>,
+	/// Also accepted as `data`.
+	#[serde(alias = "data")]
+	pub input: Option<Bytes>,
+	#[serde(default)]
+	pub value: U256,
+	pub gas: Option<U256>,
+	pub max_fee_per_gas: Option<U256>,
+	pub max_priority_fee_per_gas: Option<U256>,
+	/// Legacy calls pay `gasPrice` as both fee caps.
+	pub gas_price: Option<U256>,
+	pub nonce: Option<U256>,
+	pub access_list: Option<Vec<AccessListItem>>,
+}
+
+fn limit_err(message: impl Into<String>) -> ErrorObjectOwned {
+	ErrorObject::owned(LIMIT_EXCEEDED_CODE, message.into(), None::<()>)
+}
+
+/// `slots` of the account `address`, at most [`MAX_OVERRIDE_SLOTS`] of them.
+fn override_slots(address: H160, slots: BTreeMap<H256, H256>) -> RpcResult<Vec<(H256, H256)>> {
+	if slots.len() > MAX_OVERRIDE_SLOTS as usize {
+		return Err(limit_err(format!(
+			"too many slots overridden for {address:?} (maximum: {MAX_OVERRIDE_SLOTS})"
+		)));
+	}
+	Ok(slots.into_iter().collect())
+}
+
+/// `value` as a `T`, failing when it does not fit.
+fn narrow<T: TryFrom<U256>>(value: U256, field: &str) -> RpcResult<T> {
+	T::try_from(value).map_err(|_| internal_err(format!("{field} {value} is out of range")))
+}
+
+impl TryFrom<BlockStateCalls> for SimulatedBlock {
+	type Error = ErrorObjectOwned;
+
+	fn try_from(block: BlockStateCalls) -> RpcResult<Self> {
+		let overrides = block.block_overrides.unwrap_or_default();
+		Ok(SimulatedBlock {
+			block_overrides: BlockOverrides {
+				number: overrides.number.map(|number| narrow(number, "number")).transpose()?,
+				time: overrides.time.map(|time| narrow(time, "time")).transpose()?,
+				base_fee_per_gas: overrides.base_fee_per_gas,
+			},
+			state_overrides: block
+				.state_overrides
+				.unwrap_or_default()
+				.into_iter()
+				.map(|(address, account)| {
+					Ok(AccountOverride {
+						address,
+						balance: account
+							.balance
+							.map(|balance| narrow(balance, "balance"))
+							.transpose()?,
+						nonce: account.nonce.map(|nonce| narrow(nonce, "nonce")).transpose()?,
+						code: account.code.map(|code| code.into_vec()),
+						state: account
+							.state
+							.map(|state| override_slots(address, state))
+							.transpose()?,
+						state_diff: account
+							.state_diff
+							.map(|diff| override_slots(address, diff))
+							.transpose()?,
+					})
+				})
+				.collect::<RpcResult<_>>()?,
+			calls: block.calls.into_iter().map(TryInto::try_into).collect::<RpcResult<_>>()?,
+		})
+	}
+}
+
+impl TryFrom<CallRequest> for SimulatedCall {
+	type Error = ErrorObjectOwned;
+
+	fn try_from(call: CallRequest) -> RpcResult<Self> {
+		Ok(SimulatedCall {
+			from: call.from,
+			to: call.to,
+			data: call.input.map(|input| input.into_vec()).unwrap_or_default(),
+			value: call.value,
+			gas_limit: call.gas.map(|gas| narrow(gas, "gas")).transpose()?,
+			max_fee_per_gas: call.max_fee_per_gas.or(call.gas_price),
+			max_priority_fee_per_gas: call.max_priority_fee_per_gas.or(call.gas_price),
+			nonce: call.nonce,
+			access_list: call
+				.access_list
+				.unwrap_or_default()
+				.into_iter()
+				.map(|item| (item.address, item.storage_keys))
+				.collect(),
+		})
+	}
+}
+
+#[rpc(server)]
+pub trait SimulateApi {
+	#[method(name = "eth_simulateV1")]
+	async fn simulate(
+		&self,
+		payload: SimulatePayload,
+		block: Option<BlockNumberOrHash>,
+	) -> RpcResult<Vec<Value>>;
+}
+
+pub struct Simulate<B: BlockT, C> {
+	client: Arc<C>,
+	backend: Arc<dyn fc_api::Backend<B>>,
+	/// Bounds the simulations running at once, shared with the tracing requests.
+	permits: Arc<Semaphore>,
+	/// Gas the calls of a request may use altogether.
+	gas_cap: u64,
+	_marker: PhantomData<B>,
+}
+
+impl<B: BlockT, C> Simulate<B, C> {
+	pub fn new(
+		client: Arc<C>,
+		backend: Arc<dyn fc_api::Backend<B>>,
+		permits: Arc<Semaphore>,
+		gas_cap: u64,
+	) -> Self {
+		Self { client, backend, permits, gas_cap, _marker: PhantomData }
+	}
+}
+
+#[async_trait]
+impl<B, C> SimulateApiServer for Simulate<B, C>
+where
+	B: BlockT<Hash = H256>,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + 'static,
+	C::Api: SimulateRuntimeApi<B>,
+{
+	async fn simulate(
+		&self,
+		payload: SimulatePayload,
+		block: Option<BlockNumberOrHash>,
+	) -> RpcResult<Vec<Value>> {
+		if payload.block_state_calls.len() > MAX_BLOCKS {
+			return Err(limit_err(format!("too many blocks (maximum: {MAX_BLOCKS})")));
+		}
+		let calls: usize = payload.block_state_calls.iter().map(|block| block.calls.len()).sum();
+		if calls > MAX_CALLS {
+			return Err(limit_err(format!("too many calls (maximum: {MAX_CALLS})")));
+		}
+		let blocks: Vec<SimulatedBlock> = payload
+			.block_state_calls
+			.into_iter()
+			.map(TryInto::try_into)
+			.collect::<RpcResult<_>>()?;
+
+		let id = frontier_backend_client::native_block_id::<B, C>(
+			self.client.as_ref(),
+			self.backend.as_ref(),
+			block,
+		)
+		.await?
+		.ok_or_else(|| internal_err("block not found"))?;
+		let hash =
+			self.client.expect_block_hash_from_id(&id).map_err(|e| internal_err(e.to_string()))?;
+
+		let mut number: u32 = self
+			.client
+			.number(hash)
+			.map_err(|e| internal_err(e.to_string()))?
+			.ok_or_else(|| internal_err("block not found"))?
+			.unique_saturated_into();
+		for block in &blocks {
+			match block.block_overrides.number {
+				Some(next) if next <= number =>
+					return Err(ErrorObject::owned(
+						BLOCK_NUMBER_INVALID_CODE,
+						format!("block number {next} is not above the previous block #{number}"),
+						None::<()>,
+					)),
+				Some(next) => number = next,
+				None => number = number.saturating_add(1),
+			}
+		}
+
+		let _permit = self
+			.permits
+			.clone()
+			.acquire_owned()
+			.await
+			.map_err(|_| internal_err("simulation is shutting down"))?;
+		let client = self.client.clone();
+		let (validation, gas_cap) = (payload.validation, self.gas_cap);
+		let results = tokio::task::spawn_blocking(move || {
+			client.runtime_api().simulate(hash, blocks, validation, gas_cap)
+		})
+		.await
+		.map_err(|e| internal_err(e.to_string()))?
+		.map_err(|e| internal_err(e.to_string()))?
+		.map_err(|SimulateError::TooManySlots(address)| {
+			limit_err(format!(
+				"the state override of {address:?} clears more than {MAX_OVERRIDE_SLOTS} slots"
+			))
+		})?;
+
+		Ok(results.into_iter().map(block_json).collect())
+	}
+}
+
+fn block_json(block: BlockResult) -> Value {
+	let mut log_index = 0u32;
+	let calls: Vec<_> = block
+		.calls
+		.into_iter()
+		.map(|call| {
+			let logs: Vec<_> = call
+				.logs
+				.into_iter()
+				.map(|log| {
+					let json = json!({
+						"address": log.address,
+						"topics": log.topics,
+						"data": Bytes(log.data),
+						"blockNumber": U256::from(block.number),
+						"logIndex": U256::from(log_index),
+					});
+					log_index += 1;
+					json
+				})
+				.collect();
+			let mut json = json!({
+				"status": if call.success { "0x1" } else { "0x0" },
+				"returnData": Bytes(call.return_data),
+				"gasUsed": call.gas_used,
+				"logs": logs,
+			});
+			if let Some(error) = call.error {
+				json["error"] = json!({ "message": error });
+			}
+			json
+		})
+		.collect();
+
+	json!({
+		"number": U256::from(block.number),
+		"timestamp": U256::from(block.timestamp),
+		"gasUsed": block.gas_used,
+		"baseFeePerGas": block.base_fee_per_gas,
+		"calls": calls,
+	})
+}
@@ -0,0 +1,98 @@
+//! `fintradex_syncStatus`: how far the Frontier mapping sync, which the Ethereum RPC reads, is
+//! behind the best block.
+
+use std::sync::Arc;
+
+use fc_rpc::internal_err;
+use jsonrpsee::{
+	core::{async_trait, RpcResult},
+	proc_macros::rpc,
+};
+use serde::Serialize;
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use sp_runtime::traits::{Block as BlockT, UniqueSaturatedInto};
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Head {
+	pub number: u32,
+	pub hash: H256,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingSyncStatus {
+	/// Best block of the node.
+	pub substrate_head: Head,
+	/// Last block indexed by the mapping sync.
+	pub indexed_head: Head,
+	/// Number of best blocks not indexed yet.
+	pub lag: u32,
+	/// Whether the lag is over `--frontier-sync-lag-alarm`, the Ethereum RPC answering with
+	/// stale data.
+	pub lagging: bool,
+}
+
+/// The best block against the last block indexed in `frontier_backend`.
+pub async fn mapping_sync_status<B, C>(
+	client: &C,
+	frontier_backend: &dyn fc_api::Backend<B>,
+	lag_alarm: u32,
+) -> Result<MappingSyncStatus, String>
+where
+	B: BlockT<Hash = H256>,
+	C: HeaderBackend<B>,
+{
+	let info = client.info();
+	let indexed = frontier_backend.latest_block_hash().await?;
+	let indexed_number = client
+		.number(indexed)
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| format!("indexed block {indexed:?} not found"))?
+		.unique_saturated_into();
+	let best_number: u32 = info.best_number.unique_saturated_into();
+	let lag = best_number.saturating_sub(indexed_number);
+	Ok(MappingSyncStatus {
+		substrate_head: Head { number: best_number, hash: info.best_hash },
+		indexed_head: Head { number: indexed_number, hash: indexed },
+		lag,
+		lagging: lag > lag_alarm,
+	})
+}
+
+#[rpc(server)]
+pub trait SyncStatusApi {
+	#[method(name = "fintradex_syncStatus")]
+	async fn sync_status(&self) -> RpcResult<MappingSyncStatus>;
+}
+
+pub struct SyncStatus<B: BlockT, C> {
+	client: Arc<C>,
+	frontier_backend: Arc<dyn fc_api::Backend<B>>,
+	/// Lag over which the mapping sync is reported lagging.
+	lag_alarm: u32,
+}
+
+impl<B: BlockT, C> SyncStatus<B, C> {
+	pub fn new(
+		client: Arc<C>,
+		frontier_backend: Arc<dyn fc_api::Backend<B>>,
+		lag_alarm: u32,
+	) -> Self {
+		Self { client, frontier_backend, lag_alarm }
+	}
+}
+
+#[async_trait]
+impl<B, C> SyncStatusApiServer for SyncStatus<B, C>
+where
+	B: BlockT<Hash = H256>,
+	C: HeaderBackend<B> + Send + Sync + 'static,
+{
+	async fn sync_status(&self) -> RpcResult<MappingSyncStatus> {
+		mapping_sync_status(&*self.client, &*self.frontier_backend, self.lag_alarm)
+			.await
+			.map_err(internal_err)
+	}
+}
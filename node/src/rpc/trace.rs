@@ -0,0 +1,360 @@
+//! Parity-style `trace_filter` and `trace_block` RPC.
+//!
+//! Call traces are recorded by the indexer task spawned with
+//! [`spawn_trace_indexer`](crate::eth::spawn_trace_indexer) and kept in the client's auxiliary
+//! storage, one entry per block, for the last `--ethapi-trace-retention` blocks. Queries walk the
+//! canonical chain and flatten the call trees of the requested blocks.
+
+use std::{
+	marker::PhantomData,
+	num::NonZeroU32,
+	sync::{Arc, Mutex},
+};
+
+use codec::{Decode, Encode};
+use fc_rpc::{frontier_backend_client, internal_err};
+use fc_rpc_core::types::{BlockNumberOrHash, Bytes};
+use fintradex_runtime::evm_tracing::{CallFrame, CallType};
+use jsonrpsee::{
+	core::{async_trait, RpcResult},
+	proc_macros::rpc,
+};
+use sc_client_api::backend::AuxStore;
+use schnellru::{ByLength, LruMap};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sp_blockchain::HeaderBackend;
+use sp_core::{H160, H256, U256};
+use sp_runtime::traits::{Block as BlockT, UniqueSaturatedInto};
+
+const TRACES_KEY_PREFIX: &[u8] = b"fintradex:evm-call-traces:";
+
+/// Call traces of the Ethereum transactions of a block, in block order.
+pub type BlockTraces = Vec<(H256, CallFrame)>;
+
+/// Blocks covered by the index.
+#[derive(Clone, Copy, Debug, Encode, Decode)]
+pub struct TracesMeta {
+	/// Oldest block whose traces may be stored.
+	pub first: u32,
+	/// Last canonical block the indexer went through.
+	pub last: u32,
+}
+
+fn traces_key(block: H256) -> Vec<u8> {
+	(TRACES_KEY_PREFIX, block).encode()
+}
+
+fn meta_key() -> Vec<u8> {
+	(TRACES_KEY_PREFIX, b'm').encode()
+}
+
+/// Blocks at height `number` whose traces are stored, canonical or not.
+fn number_key(number: u32) -> Vec<u8> {
+	(TRACES_KEY_PREFIX, b'n', number).encode()
+}
+
+fn get<C: AuxStore, T: Decode>(client: &C, key: &[u8]) -> sp_blockchain::Result<Option<T>> {
+	client
+		.get_aux(key)?
+		.map(|encoded| {
+			T::decode(&mut &encoded[..]).map_err(|e| sp_blockchain::Error::Backend(e.to_string()))
+		})
+		.transpose()
+}
+
+/// Persist the traces of `block`, at height `number`.
+pub fn store_traces<C: AuxStore>(
+	client: &C,
+	number: u32,
+	block: H256,
+	traces: &BlockTraces,
+) -> sp_blockchain::Result<()> {
+	let mut blocks: Vec<H256> = get(client, &number_key(number))?.unwrap_or_default();
+	if !blocks.contains(&block) {
+		blocks.push(block);
+	}
+	client.insert_aux(
+		&[(&traces_key(block)[..], &traces.encode()[..]), (&number_key(number), &blocks.encode())],
+		&[],
+	)
+}
+
+/// The traces of `block`, `None` if it was not indexed.
+pub fn load_traces<C: AuxStore>(
+	client: &C,
+	block: H256,
+) -> sp_blockchain::Result<Option<BlockTraces>> {
+	get(client, &traces_key(block))
+}
+
+/// The blocks covered by the index, `None` before the indexer first ran.
+pub fn load_traces_meta<C: AuxStore>(client: &C) -> sp_blockchain::Result<Option<TracesMeta>> {
+	get(client, &meta_key())
+}
+
+/// Record that the indexer went through the canonical blocks up to `last`, deleting the traces
+/// of the blocks below `last + 1 - retention`.
+pub fn advance_traces<C: AuxStore>(
+	client: &C,
+	last: u32,
+	retention: Option<NonZeroU32>,
+) -> sp_blockchain::Result<()> {
+	let meta = load_traces_meta(client)?;
+	let first = meta.map_or(last, |meta| meta.first);
+	let kept = retention.map_or(first, |retention| (last + 1).saturating_sub(retention.get()));
+
+	let mut deleted = Vec::new();
+	for number in first..kept {
+		let blocks: Vec<H256> = get(client, &number_key(number))?.unwrap_or_default();
+		deleted.extend(blocks.into_iter().map(traces_key));
+		deleted.push(number_key(number));
+	}
+	let meta = TracesMeta { first: first.max(kept), last };
+	client.insert_aux(
+		&[(&meta_key()[..], &meta.encode()[..])],
+		&deleted.iter().map(|key| &key[..]).collect::<Vec<_>>(),
+	)
+}
+
+/// Filter of `trace_filter`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceFilter {
+	pub from_block: Option<BlockNumberOrHash>,
+	pub to_block: Option<BlockNumberOrHash>,
+	pub from_address: Option<Vec<H160>>,
+	pub to_address: Option<Vec<H160>>,
+	/// Number of matching traces to skip.
+	pub after: Option<u32>,
+	/// Maximum number of traces to return.
+	pub count: Option<u32>,
+}
+
+#[rpc(server)]
+pub trait TraceApi {
+	#[method(name = "trace_filter")]
+	async fn filter(&self, filter: TraceFilter) -> RpcResult<Vec<Value>>;
+
+	#[method(name = "trace_block")]
+	async fn block(&self, number: BlockNumberOrHash) -> RpcResult<Vec<Value>>;
+}
+
+/// Limits of the trace RPC.
+#[derive(Clone, Copy, Debug)]
+pub struct TraceLimits {
+	/// Maximum number of traces a query may return.
+	pub max_count: u32,
+	/// Maximum number of blocks a query may scan.
+	pub max_block_range: u32,
+	/// Number of blocks whose traces are kept decoded in memory.
+	pub cache_size: NonZeroU32,
+}
+
+pub struct Trace<B: BlockT, C> {
+	client: Arc<C>,
+	backend: Arc<dyn fc_api::Backend<B>>,
+	limits: TraceLimits,
+	cache: Mutex<LruMap<H256, Arc<BlockTraces>>>,
+	_marker: PhantomData<B>,
+}
+
+impl<B: BlockT, C> Trace<B, C> {
+	pub fn new(client: Arc<C>, backend: Arc<dyn fc_api::Backend<B>>, limits: TraceLimits) -> Self {
+		Self {
+			client,
+			backend,
+			cache: Mutex::new(LruMap::new(ByLength::new(limits.cache_size.get()))),
+			limits,
+			_marker: PhantomData,
+		}
+	}
+}
+
+impl<B, C> Trace<B, C>
+where
+	B: BlockT<Hash = H256>,
+	C: HeaderBackend<B> + AuxStore + Send + Sync + 'static,
+{
+	async fn block_number(&self, block: Option<BlockNumberOrHash>) -> RpcResult<u32> {
+		let id = frontier_backend_client::native_block_id::<B, C>(
+			self.client.as_ref(),
+			self.backend.as_ref(),
+			block,
+		)
+		.await?
+		.ok_or_else(|| internal_err("block not found"))?;
+		let number = self
+			.client
+			.block_number_from_id(&id)
+			.map_err(|e| internal_err(e.to_string()))?
+			.ok_or_else(|| internal_err("block not found"))?;
+		Ok(number.unique_saturated_into())
+	}
+
+	/// The canonical block at `number` and its traces.
+	fn block_traces(&self, number: u32) -> RpcResult<(H256, Arc<BlockTraces>)> {
+		let hash = self
+			.client
+			.hash(number.into())
+			.map_err(|e| internal_err(e.to_string()))?
+			.ok_or_else(|| internal_err(format!("block #{number} not found")))?;
+		if let Some(traces) = self.cache.lock().expect("trace cache lock poisoned").get(&hash) {
+			return Ok((hash, traces.clone()));
+		}
+
+		let traces = load_traces(self.client.as_ref(), hash)
+			.map_err(|e| internal_err(e.to_string()))?
+			.ok_or_else(|| internal_err(format!("block #{number} is not indexed")))?;
+		let traces = Arc::new(traces);
+		self.cache.lock().expect("trace cache lock poisoned").insert(hash, traces.clone());
+		Ok((hash, traces))
+	}
+}
+
+#[async_trait]
+impl<B, C> TraceApiServer for Trace<B, C>
+where
+	B: BlockT<Hash = H256>,
+	C: HeaderBackend<B> + AuxStore + Send + Sync + 'static,
+{
+	async fn filter(&self, filter: TraceFilter) -> RpcResult<Vec<Value>> {
+		let from = self.block_number(filter.from_block).await?;
+		let to = self.block_number(filter.to_block).await?;
+		if from > to {
+			return Err(internal_err("fromBlock is after toBlock"));
+		}
+		if to - from >= self.limits.max_block_range {
+			return Err(internal_err(format!(
+				"block range is too wide (maximum: {})",
+				self.limits.max_block_range
+			)));
+		}
+		let count = filter.count.unwrap_or(self.limits.max_count);
+		if count > self.limits.max_count {
+			return Err(internal_err(format!(
+				"count is too large (maximum: {})",
+				self.limits.max_count
+			)));
+		}
+
+		let matches = |trace: &FlatTrace| {
+			let (from, to) = trace.addresses();
+			filter.from_address.as_ref().map_or(true, |addresses| addresses.contains(&from)) &&
+				filter.to_address.as_ref().map_or(true, |addresses| addresses.contains(&to))
+		};
+		let mut skip = filter.after.unwrap_or(0);
+		let mut traces = Vec::new();
+		for number in from..=to {
+			let (hash, block_traces) = self.block_traces(number)?;
+			for trace in flatten(&block_traces).filter(matches) {
+				if skip > 0 {
+					skip -= 1;
+					continue;
+				}
+				if traces.len() == count as usize {
+					return Ok(traces);
+				}
+				traces.push(trace.to_json(hash, number));
+			}
+		}
+		Ok(traces)
+	}
+
+	async fn block(&self, number: BlockNumberOrHash) -> RpcResult<Vec<Value>> {
+		let number = self.block_number(Some(number)).await?;
+		let (hash, block_traces) = self.block_traces(number)?;
+		Ok(flatten(&block_traces).map(|trace| trace.to_json(hash, number)).collect())
+	}
+}
+
+/// A call of a call tree, with its position in it.
+struct FlatTrace<'a> {
+	frame: &'a CallFrame,
+	trace_address: Vec<u32>,
+	transaction_hash: H256,
+	transaction_position: u32,
+}
+
+/// The calls of `traces` in depth-first order, as listed by Parity.
+fn flatten(traces: &BlockTraces) -> impl Iterator<Item = FlatTrace<'_>> {
+	traces.iter().enumerate().flat_map(|(position, (hash, root))| {
+		let mut flat = Vec::new();
+		let mut pending = vec![(root, Vec::new())];
+		while let Some((frame, trace_address)) = pending.pop() {
+			for (index, call) in frame.calls.iter().enumerate().rev() {
+				let mut address = trace_address.clone();
+				address.push(index as u32);
+				pending.push((call, address));
+			}
+			flat.push(FlatTrace {
+				frame,
+				trace_address,
+				transaction_hash: *hash,
+				transaction_position: position as u32,
+			});
+		}
+		flat
+	})
+}
+
+impl FlatTrace<'_> {
+	/// The sender and recipient the address filters match against.
+	fn addresses(&self) -> (H160, H160) {
+		(self.frame.from, self.frame.to)
+	}
+
+	fn to_json(&self, block_hash: H256, block_number: u32) -> Value {
+		let frame = self.frame;
+		let gas = U256::from(frame.gas);
+		let gas_used = U256::from(frame.gas_used);
+		let (kind, action, result) = match frame.call_type {
+			CallType::Create | CallType::Create2 => (
+				"create",
+				json!({ "from": frame.from, "gas": gas, "init": Bytes(frame.input.clone()), "value": frame.value }),
+				json!({ "address": frame.to, "code": Bytes(frame.output.clone()), "gasUsed": gas_used }),
+			),
+			CallType::SelfDestruct => (
+				"suicide",
+				json!({ "address": frame.from, "refundAddress": frame.to, "balance": frame.value }),
+				Value::Null,
+			),
+			call_type => {
+				let call_type = match call_type {
+					CallType::CallCode => "callcode",
+					CallType::DelegateCall => "delegatecall",
+					CallType::StaticCall => "staticcall",
+					_ => "call",
+				};
+				(
+					"call",
+					json!({
+						"callType": call_type,
+						"from": frame.from,
+						"to": frame.to,
+						"gas": gas,
+						"input": Bytes(frame.input.clone()),
+						"value": frame.value,
+					}),
+					json!({ "gasUsed": gas_used, "output": Bytes(frame.output.clone()) }),
+				)
+			},
+		};
+
+		let mut json = json!({
+			"type": kind,
+			"action": action,
+			"result": if frame.error.is_some() { Value::Null } else { result },
+			"blockHash": block_hash,
+			"blockNumber": block_number,
+			"subtraces": frame.calls.len(),
+			"traceAddress": self.trace_address,
+			"transactionHash": self.transaction_hash,
+			"transactionPosition": self.transaction_position,
+		});
+		if let Some(error) = &frame.error {
+			json["error"] = error.clone().into();
+		}
+		json
+	}
+}
@@ -3,7 +3,7 @@
 // std
 use std::{path::Path, sync::Arc, time::Duration};
 
-use cumulus_client_cli::CollatorOptions;
+use cumulus_client_cli::{CollatorOptions, RelayChainMode};
 // Local Runtime Types
 use fintradex_runtime::{
 	opaque::{Block, Hash},
@@ -18,8 +18,11 @@ use cumulus_client_service::{
 	build_network, build_relay_chain_interface, prepare_node_config, start_relay_chain_tasks,
 	BuildNetworkParams, CollatorSybilResistance, DARecoveryProfile, StartRelayChainTasksParams,
 };
-use cumulus_primitives_core::{relay_chain::CollatorPair, ParaId};
-use cumulus_relay_chain_interface::{OverseerHandle, RelayChainInterface};
+use cumulus_primitives_core::{
+	relay_chain::{CollatorPair, ValidationCode},
+	ParaId,
+};
+use cumulus_relay_chain_interface::RelayChainInterface;
 
 // Substrate Imports
 use frame_benchmarking_cli::SUBSTRATE_REFERENCE_HARDWARE;
@@ -27,10 +30,10 @@ use futures::FutureExt;
 use sc_client_api::Backend;
 use sc_consensus::ImportQueue;
 use sc_executor::{
-	HeapAllocStrategy, NativeElseWasmExecutor, WasmExecutor, DEFAULT_HEAP_ALLOC_STRATEGY,
+	sp_wasm_interface::ExtendedHostFunctions, HeapAllocStrategy, WasmExecutor,
+	DEFAULT_HEAP_ALLOC_STRATEGY,
 };
 use sc_network::NetworkBlock;
-use sc_network_sync::SyncingService;
 use sc_service::{Configuration, PartialComponents, TFullBackend, TFullClient, TaskManager};
 use sc_telemetry::{Telemetry, TelemetryHandle, TelemetryWorker, TelemetryWorkerHandle};
 use sc_transaction_pool_api::OffchainTransactionPoolFactory;
@@ -40,60 +43,87 @@ use substrate_prometheus_endpoint::Registry;
 
 // Frontier
 use crate::eth::{
-	db_config_dir, new_frontier_partial, spawn_frontier_tasks, BackendType, EthConfiguration,
-	FrontierBackend, FrontierBlockImport as TFrontierBlockImport, FrontierPartialComponents,
+	db_config_dir, new_frontier_partial, spawn_evm_history_indexer, spawn_frontier_tasks,
+	spawn_trace_indexer, BackendType, EthApi, EthConfiguration, FrontierBackend,
+	FrontierBlockImport as TFrontierBlockImport, FrontierPartialComponents,
 };
+use crate::private_pool::{spawn_private_pool_pruner, PrivatePool, ProposerPool};
+use crate::metrics::{spawn_collator_metrics, MeteredProposer, Metrics};
+use crate::tx_lanes::{LanePool, TxLanes};
 
-/// Native executor type.
-pub struct ParachainNativeExecutor;
+/// Time the collator may spend building a block: the share of a slot of each core, as the
+/// runtime authors one block per core in each slot.
+const AUTHORING_DURATION: Duration =
+	Duration::from_millis(fintradex_runtime::MILLI_SECS_PER_BLOCK);
 
-impl sc_executor::NativeExecutionDispatch for ParachainNativeExecutor {
-	type ExtendHostFunctions = frame_benchmarking::benchmarking::HostFunctions;
+/// How long before the start of its slot the collator begins building a block.
+const SLOT_DRIFT: Duration = Duration::from_secs(1);
 
-	fn dispatch(method: &str, data: &[u8]) -> Option<Vec<u8>> {
-		fintradex_runtime::apis::dispatch(method, data)
-	}
+/// Host functions of the runtime, including the benchmarking ones.
+pub(crate) type HostFunctions = ExtendedHostFunctions<
+	sp_io::SubstrateHostFunctions,
+	frame_benchmarking::benchmarking::HostFunctions,
+>;
 
-	fn native_version() -> sc_executor::NativeVersion {
-		fintradex_runtime::native_version()
-	}
-}
+/// Runtime calls always run the wasm runtime, the on-chain one or the one of
+/// `--wasm-runtime-overrides` for its version.
+pub(crate) type ParachainExecutor = WasmExecutor<HostFunctions>;
 
-type ParachainExecutor = NativeElseWasmExecutor<ParachainNativeExecutor>;
+pub(crate) type ParachainClient = TFullClient<Block, RuntimeApi, ParachainExecutor>;
 
-type ParachainClient = TFullClient<Block, RuntimeApi, ParachainExecutor>;
+pub(crate) type ParachainBackend = TFullBackend<Block>;
 
-type ParachainBackend = TFullBackend<Block>;
+/// The fork-aware transaction pool of the parachain node.
+pub(crate) type ParachainTransactionPool =
+	sc_transaction_pool::TransactionPoolHandle<Block, ParachainClient>;
 
-type ParachainBlockImport = TParachainBlockImport<Block, FrontierBlockImport, ParachainBackend>;
+pub(crate) type ParachainBlockImport =
+	TParachainBlockImport<Block, FrontierBlockImport, ParachainBackend>;
 
 type FrontierBlockImport = TFrontierBlockImport<Block, Arc<ParachainClient>, ParachainClient>;
 
+type Service = PartialComponents<
+	ParachainClient,
+	ParachainBackend,
+	(),
+	sc_consensus::DefaultImportQueue<Block>,
+	ParachainTransactionPool,
+	(
+		ParachainBlockImport,
+		Option<Telemetry>,
+		Option<TelemetryWorkerHandle>,
+		FrontierBackend,
+		Arc<fc_rpc::OverrideHandle<Block>>,
+	),
+>;
+
 /// Starts a `ServiceBuilder` for a full service.
 ///
 /// Use this macro if you don't actually need the full service, but just the builder in order to
 /// be able to perform chain operations.
-#[allow(clippy::type_complexity)]
 pub fn new_partial(
 	config: &Configuration,
 	eth_config: &EthConfiguration,
-) -> Result<
-	PartialComponents<
-		ParachainClient,
-		ParachainBackend,
-		(),
-		sc_consensus::DefaultImportQueue<Block>,
-		sc_transaction_pool::FullPool<Block, ParachainClient>,
-		(
-			ParachainBlockImport,
-			Option<Telemetry>,
-			Option<TelemetryWorkerHandle>,
-			FrontierBackend,
-			Arc<fc_rpc::OverrideHandle<Block>>,
-		),
-	>,
-	sc_service::Error,
-> {
+) -> Result<Service, sc_service::Error> {
+	new_partial_with(config, eth_config, build_import_queue)
+}
+
+/// [`new_partial`] with the import queue built by `build_import_queue`.
+pub(crate) fn new_partial_with<BIQ>(
+	config: &Configuration,
+	eth_config: &EthConfiguration,
+	build_import_queue: BIQ,
+) -> Result<Service, sc_service::Error>
+where
+	BIQ: FnOnce(
+		Arc<ParachainClient>,
+		ParachainBlockImport,
+		&Configuration,
+		&EthConfiguration,
+		Option<TelemetryHandle>,
+		&TaskManager,
+	) -> Result<sc_consensus::DefaultImportQueue<Block>, sc_service::Error>,
+{
 	let telemetry = config
 		.telemetry_endpoints
 		.clone()
@@ -109,7 +139,7 @@ pub fn new_partial(
 		.default_heap_pages
 		.map_or(DEFAULT_HEAP_ALLOC_STRATEGY, |h| HeapAllocStrategy::Static { extra_pages: h as _ });
 
-	let wasm = WasmExecutor::builder()
+	let executor = ParachainExecutor::builder()
 		.with_execution_method(config.wasm_method)
 		.with_onchain_heap_alloc_strategy(heap_pages)
 		.with_offchain_heap_alloc_strategy(heap_pages)
@@ -117,8 +147,6 @@ pub fn new_partial(
 		.with_runtime_cache_size(config.runtime_cache_size)
 		.build();
 
-	let executor = ParachainExecutor::new_with_wasm_executor(wasm);
-
 	let (client, backend, keystore_container, task_manager) =
 		sc_service::new_full_parts::<Block, RuntimeApi, _>(
 			config,
@@ -134,12 +162,18 @@ pub fn new_partial(
 		telemetry
 	});
 
-	let transaction_pool = sc_transaction_pool::BasicPool::new_full(
-		config.transaction_pool.clone(),
-		config.role.is_authority().into(),
-		config.prometheus_registry(),
-		task_manager.spawn_essential_handle(),
-		client.clone(),
+	// Under async backing, blocks are built on top of unincluded parents that may be forked
+	// away: the fork-aware pool keeps a view per fork, so transactions survive reorgs (see
+	// `--pool-type`, set by `command::run`).
+	let transaction_pool = Arc::from(
+		sc_transaction_pool::Builder::new(
+			task_manager.spawn_essential_handle(),
+			client.clone(),
+			config.role.is_authority().into(),
+		)
+		.with_options(config.transaction_pool.clone())
+		.with_prometheus(config.prometheus_registry())
+		.build(),
 	);
 
 	let overrides = crate::rpc::overrides_handle(client.clone());
@@ -170,6 +204,20 @@ pub fn new_partial(
 			.unwrap_or_else(|err| panic!("failed creating sql backend: {:?}", err));
 			FrontierBackend::Sql(backend)
 		},
+		BackendType::Postgres => {
+			let url = eth_config
+				.frontier_postgres_url
+				.as_deref()
+				.ok_or("--frontier-postgres-url is required with the postgres backend")?;
+			let backend = futures::executor::block_on(crate::eth::postgres::Backend::connect(
+				url,
+				eth_config.frontier_sql_backend_pool_size,
+				eth_config.frontier_postgres_read_only,
+				eth_config.max_past_logs,
+			))
+			.map_err(|err| format!("failed connecting to the postgres backend: {err}"))?;
+			FrontierBackend::Postgres(backend)
+		},
 	};
 
 	let frontier_block_import = FrontierBlockImport::new(client.clone(), client.clone());
@@ -214,6 +262,7 @@ async fn start_node_impl(
 	collator_options: CollatorOptions,
 	para_id: ParaId,
 	hwbench: Option<sc_sysinfo::HwBench>,
+	relay_chain_stall_timeout: Duration,
 ) -> sc_service::error::Result<(TaskManager, Arc<ParachainClient>)> {
 	let mut parachain_config = prepare_node_config(parachain_config);
 
@@ -231,6 +280,7 @@ async fn start_node_impl(
 	let FrontierPartialComponents { filter_pool, fee_history_cache, fee_history_cache_limit } =
 		new_frontier_partial(&eth_config)?;
 
+	let collator_options = crate::relay::healthy_rpc_first(collator_options).await;
 	let (relay_chain_interface, collator_key) = build_relay_chain_interface(
 		polkadot_config,
 		&parachain_config,
@@ -242,6 +292,20 @@ async fn start_node_impl(
 	.await
 	.map_err(|e| sc_service::Error::Application(Box::new(e) as Box<_>))?;
 
+	// The embedded relay chain node is part of the collator, RPC nodes and peers of the light
+	// client may stall.
+	let relay_chain_interface =
+		if matches!(collator_options.relay_chain_mode, RelayChainMode::Embedded) {
+			relay_chain_interface
+		} else {
+			crate::relay::spawn_relay_chain_failover(
+				&task_manager,
+				relay_chain_interface,
+				&collator_options,
+				relay_chain_stall_timeout,
+			)
+		};
+
 	let validator = parachain_config.role.is_authority();
 	let prometheus_registry = parachain_config.prometheus_registry().cloned();
 	let import_queue_service = import_queue.service();
@@ -292,23 +356,27 @@ async fn start_node_impl(
 	let pubsub_notification_sinks = Arc::new(pubsub_notification_sinks);
 	let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
 	let target_gas_price = eth_config.target_gas_price;
+	let private_pool = Arc::new(PrivatePool::new(eth_config.private_pool_capacity));
+	let tx_lanes = Arc::new(TxLanes::new(
+		eth_config.tx_lane.clone(),
+		eth_config.tx_lane_sender_limit,
+		eth_config.tx_lane_pool_limit,
+	));
+	let rpc_pool =
+		Arc::new(LanePool::new(transaction_pool.clone(), client.clone(), tx_lanes.clone()));
 
 	// for ethereum-compatibility rpc.
 	parachain_config.rpc_id_provider = Some(Box::new(fc_rpc::EthereumSubIdProvider));
 
 	let eth_rpc_params = crate::rpc::EthDeps {
 		client: client.clone(),
-		pool: transaction_pool.clone(),
-		graph: transaction_pool.pool().clone(),
+		pool: rpc_pool.clone(),
 		converter: Some(TransactionConverter),
 		is_authority: parachain_config.role.is_authority(),
 		enable_dev_signer: eth_config.enable_dev_signer,
 		network: network.clone(),
 		sync: sync_service.clone(),
-		frontier_backend: match frontier_backend.clone() {
-			fc_db::Backend::KeyValue(b) => Arc::new(b),
-			fc_db::Backend::Sql(b) => Arc::new(b),
-		},
+		frontier_backend: frontier_backend.api(),
 		overrides: overrides.clone(),
 		block_data_cache: Arc::new(fc_rpc::EthBlockDataCacheTask::new(
 			task_manager.spawn_handle(),
@@ -334,18 +402,31 @@ async fn start_node_impl(
 			let dynamic_fee = fp_dynamic_fee::InherentDataProvider(U256::from(target_gas_price));
 			Ok((slot, timestamp, dynamic_fee))
 		},
+		ethapi: eth_config.ethapi.clone(),
+		tracing_permits: Arc::new(tokio::sync::Semaphore::new(
+			eth_config.ethapi_max_permits as usize,
+		)),
+		trace_limits: crate::rpc::TraceLimits {
+			max_count: eth_config.ethapi_trace_max_count,
+			max_block_range: eth_config.ethapi_trace_max_block_range,
+			cache_size: eth_config.ethapi_trace_cache_size,
+		},
+		private_pool: private_pool.clone(),
+		private_tx_lifetime: eth_config.private_tx_lifetime,
+		evm_state_history: eth_config.evm_state_history.is_some(),
+		sync_lag_alarm: eth_config.frontier_sync_lag_alarm,
 	};
 
 	let rpc_builder = {
 		let client = client.clone();
-		let transaction_pool = transaction_pool.clone();
 		let pubsub_notification_sinks = pubsub_notification_sinks.clone();
 
 		Box::new(move |deny_unsafe, subscription_task_executor| {
 			let deps = crate::rpc::FullDeps {
 				client: client.clone(),
-				pool: transaction_pool.clone(),
+				pool: rpc_pool.clone(),
 				deny_unsafe,
+				command_sink: None,
 				eth: eth_rpc_params.clone(),
 			};
 
@@ -373,10 +454,27 @@ async fn start_node_impl(
 		telemetry: telemetry.as_mut(),
 	})?;
 
+	let metrics = prometheus_registry
+		.as_ref()
+		.map(Metrics::register)
+		.transpose()
+		.map_err(|e| sc_service::Error::Application(Box::new(e)))?;
+	if let Some(metrics) = metrics.clone() {
+		spawn_collator_metrics(
+			&task_manager,
+			client.clone(),
+			sync_service.clone(),
+			validator.then(|| keystore_container.keystore()),
+			metrics,
+		);
+	}
+
 	spawn_frontier_tasks(
 		&task_manager,
+		&eth_config,
+		prometheus_registry.as_ref(),
 		client.clone(),
-		backend,
+		backend.clone(),
 		frontier_backend,
 		filter_pool,
 		overrides,
@@ -387,6 +485,24 @@ async fn start_node_impl(
 	)
 	.await;
 
+	if eth_config.ethapi.contains(&EthApi::Trace) {
+		spawn_trace_indexer(&task_manager, client.clone(), eth_config.ethapi_trace_retention);
+	}
+
+	if let Some(history) = eth_config.evm_state_history {
+		spawn_evm_history_indexer(&task_manager, client.clone(), history);
+	}
+
+	if eth_config.ethapi.contains(&EthApi::Private) {
+		if !validator {
+			log::warn!(
+				"⚠️  Private transactions are only included by collators, this node will hold \
+				 them until they expire."
+			);
+		}
+		spawn_private_pool_pruner(&task_manager, client.clone(), private_pool.clone());
+	}
+
 	if let Some(hwbench) = hwbench {
 		sc_sysinfo::print_hwbench(&hwbench);
 		// Here you can check whether the hardware meets your chains' requirements. Putting a link
@@ -432,25 +548,27 @@ async fn start_node_impl(
 		},
 		import_queue: import_queue_service,
 		relay_chain_slot_duration,
-		recovery_handle: Box::new(overseer_handle.clone()),
+		recovery_handle: Box::new(overseer_handle),
 		sync_service: sync_service.clone(),
 	})?;
 
 	if validator {
 		start_consensus(
 			client.clone(),
+			backend,
 			block_import,
 			prometheus_registry.as_ref(),
 			telemetry.as_ref().map(|t| t.handle()),
 			&task_manager,
 			relay_chain_interface.clone(),
 			transaction_pool,
-			sync_service.clone(),
+			private_pool,
+			tx_lanes,
+			metrics,
 			keystore_container.keystore(),
 			relay_chain_slot_duration,
 			para_id,
 			collator_key.expect("Command line arguments do not allow this. qed"),
-			overseer_handle,
 			announce_block,
 		)?;
 	}
@@ -500,38 +618,40 @@ fn build_import_queue(
 
 fn start_consensus(
 	client: Arc<ParachainClient>,
+	backend: Arc<ParachainBackend>,
 	block_import: ParachainBlockImport,
 	prometheus_registry: Option<&Registry>,
 	telemetry: Option<TelemetryHandle>,
 	task_manager: &TaskManager,
 	relay_chain_interface: Arc<dyn RelayChainInterface>,
-	transaction_pool: Arc<sc_transaction_pool::FullPool<Block, ParachainClient>>,
-	sync_oracle: Arc<SyncingService<Block>>,
+	transaction_pool: Arc<ParachainTransactionPool>,
+	private_pool: Arc<PrivatePool<Block>>,
+	tx_lanes: Arc<TxLanes>,
+	metrics: Option<Metrics>,
 	keystore: KeystorePtr,
 	relay_chain_slot_duration: Duration,
 	para_id: ParaId,
 	collator_key: CollatorPair,
-	overseer_handle: OverseerHandle,
 	announce_block: Arc<dyn Fn(Hash, Option<Vec<u8>>) + Send + Sync>,
 ) -> Result<(), sc_service::Error> {
-	use cumulus_client_consensus_aura::collators::basic::{
-		self as basic_aura, Params as BasicAuraParams,
+	use cumulus_client_consensus_aura::collators::slot_based::{
+		self as aura, Params as AuraParams, SlotBasedBlockImport,
 	};
 
 	// NOTE: because we use Aura here explicitly, we can use `CollatorSybilResistance::Resistant`
 	// when starting the network.
 
-	let slot_duration = cumulus_client_consensus_aura::slot_duration(&*client)?;
-
 	let proposer_factory = sc_basic_authorship::ProposerFactory::with_proof_recording(
 		task_manager.spawn_handle(),
 		client.clone(),
-		transaction_pool,
+		// Private bundles are proposed ahead of the public pool, and lane transactions ahead of
+		// the other public ones.
+		Arc::new(ProposerPool::new(client.clone(), transaction_pool, private_pool, tx_lanes)),
 		prometheus_registry,
 		telemetry.clone(),
 	);
 
-	let proposer = Proposer::new(proposer_factory);
+	let proposer = MeteredProposer::new(Proposer::new(proposer_factory), metrics);
 
 	let collator_service = CollatorService::new(
 		client.clone(),
@@ -540,29 +660,41 @@ fn start_consensus(
 		client.clone(),
 	);
 
-	let params = BasicAuraParams {
+	// Blocks built by the collator go through this import, which keeps their proofs for the
+	// collation task.
+	let (block_import, block_import_handle) = SlotBasedBlockImport::new(block_import, client.clone());
+
+	// The collator reads the rest from the runtime: it builds on the relay parent
+	// `RelayParentOffsetApi::relay_parent_offset` blocks behind the relay chain tip, and sends
+	// each block to the core `GetCoreSelectorApi::core_selector` picks, one per assigned core.
+
+	let params = AuraParams {
 		create_inherent_data_providers: move |_, ()| async move { Ok(()) },
 		block_import,
-		para_client: client,
+		para_client: client.clone(),
+		para_backend: backend,
 		relay_client: relay_chain_interface,
-		sync_oracle,
+		code_hash_provider: move |block_hash| {
+			client.code_at(block_hash).ok().map(|c| ValidationCode::from(c).hash())
+		},
 		keystore,
 		collator_key,
 		para_id,
-		overseer_handle,
-		slot_duration,
 		relay_chain_slot_duration,
 		proposer,
 		collator_service,
-		// Very limited proposal time.
-		authoring_duration: Duration::from_millis(500),
+		// Each block gets its core's share of the slot and is validated on a core of its own, so
+		// that several blocks are backed per relay chain block (elastic scaling).
+		authoring_duration: AUTHORING_DURATION,
+		reinitialize: false,
+		slot_drift: SLOT_DRIFT,
+		block_import_handle,
+		spawner: task_manager.spawn_handle(),
+		export_pov: None,
 	};
 
-	let fut =
-		basic_aura::run::<Block, sp_consensus_aura::sr25519::AuthorityPair, _, _, _, _, _, _, _>(
-			params,
-		);
-	task_manager.spawn_essential_handle().spawn("aura", None, fut);
+	// Spawns the block builder and the collation tasks.
+	aura::run::<Block, sp_consensus_aura::sr25519::AuthorityPair, _, _, _, _, _, _, _, _, _>(params);
 
 	Ok(())
 }
@@ -575,6 +707,7 @@ pub async fn start_parachain_node(
 	collator_options: CollatorOptions,
 	para_id: ParaId,
 	hwbench: Option<sc_sysinfo::HwBench>,
+	relay_chain_stall_timeout: Duration,
 ) -> sc_service::error::Result<(TaskManager, Arc<ParachainClient>)> {
 	start_node_impl(
 		parachain_config,
@@ -583,6 +716,42 @@ pub async fn start_parachain_node(
 		collator_options,
 		para_id,
 		hwbench,
+		relay_chain_stall_timeout,
 	)
 	.await
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::chain_spec::development_config;
+	use codec::Decode;
+	use sp_api::RuntimeVersion;
+	use sp_core::{
+		storage::well_known_keys,
+		traits::{CallContext, CodeExecutor, RuntimeCode, WrappedRuntimeCode},
+	};
+	use sp_runtime::BuildStorage;
+	use sp_state_machine::BasicExternalities;
+
+	/// A runtime of the same version as the native one still runs in wasm, which is what lets
+	/// `--wasm-runtime-overrides` replace it, e.g. with a tracing build.
+	#[test]
+	fn runtime_calls_run_the_given_wasm() {
+		let genesis = development_config().build_storage().unwrap();
+		let code = genesis.top[well_known_keys::CODE].clone();
+		let runtime_code = RuntimeCode {
+			code_fetcher: &WrappedRuntimeCode(code.as_slice().into()),
+			heap_pages: None,
+			hash: sp_core::blake2_256(&code).to_vec(),
+		};
+		let executor = ParachainExecutor::builder().build();
+
+		let mut ext = BasicExternalities::new(genesis.clone());
+		let (version, used_native) =
+			executor.call(&mut ext, &runtime_code, "Core_version", &[], CallContext::Offchain);
+		assert!(!used_native, "the runtime was called natively");
+		let version = RuntimeVersion::decode(&mut &version.expect("Core_version failed")[..])
+			.expect("Core_version returns a version");
+		assert_eq!(version, fintradex_runtime::VERSION);
+	}
+}
@@ -0,0 +1,579 @@
+//! Trading-priority lanes.
+//!
+//! Some contract calls must not wait behind the rest of the pool during volatility: order
+//! cancellations, oracle updates. Each [`Lane`] lists such calls, by contract and optionally by
+//! function selector, and [`TxLanes`] proposes the ready transactions of the lanes ahead of the
+//! others, highest lane first. A sender only gets a few transactions ahead in each block, so
+//! that spamming a lane does not starve it: its other transactions keep their place in the pool
+//! order. Nor does the [`LanePool`] the RPC submits to take more than a few lane transactions of
+//! a sender in.
+
+use std::{
+	collections::{HashMap, HashSet},
+	pin::Pin,
+	str::FromStr,
+	sync::Arc,
+	time::Duration,
+};
+
+use codec::{Decode, Encode};
+use fintradex_runtime::{RuntimeCall, UncheckedExtrinsic};
+use fp_rpc::EthereumRuntimeRPCApi;
+use fp_self_contained::SelfContainedCall;
+use jsonrpsee::core::async_trait;
+use sc_transaction_pool_api::{
+	error::Error as PoolError, ImportNotificationStream, InPoolTransaction, PoolStatus,
+	ReadyTransactions, TransactionFor, TransactionPool, TransactionSource,
+	TransactionStatusStreamFor, TxHash, TxInvalidityReportMap,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_core::{H160, U256};
+use sp_runtime::{traits::Block as BlockT, transaction_validity::TransactionTag};
+
+/// A contract call placed in a lane: any call to `to`, or only those to its function `selector`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+	pub to: H160,
+	pub selector: Option<[u8; 4]>,
+}
+
+impl Rule {
+	fn matches(&self, to: &H160, input: &[u8]) -> bool {
+		&self.to == to && self.selector.map_or(true, |selector| input.get(..4) == Some(&selector))
+	}
+}
+
+impl FromStr for Rule {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (to, selector) = match s.split_once(':') {
+			Some((to, selector)) => (to, Some(selector)),
+			None => (s, None),
+		};
+		let to = H160::from_str(to).map_err(|_| format!("invalid contract address: {to}"))?;
+		let selector = selector
+			.map(|selector| {
+				let hex = selector.strip_prefix("0x").unwrap_or(selector);
+				match u32::from_str_radix(hex, 16) {
+					Ok(selector) if hex.len() == 8 => Ok(selector.to_be_bytes()),
+					_ => Err(format!("invalid function selector: {selector}")),
+				}
+			})
+			.transpose()?;
+		Ok(Self { to, selector })
+	}
+}
+
+/// The contract calls of a lane, given as comma-separated `ADDRESS[:SELECTOR]` rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lane(pub Vec<Rule>);
+
+impl FromStr for Lane {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		s.split(',').map(Rule::from_str).collect::<Result<_, _>>().map(Self)
+	}
+}
+
+type Ready<T> = Box<dyn ReadyTransactions<Item = Arc<T>> + Send>;
+
+/// An Ethereum transaction of a lane.
+struct LaneTransaction {
+	lane: usize,
+	sender: H160,
+	nonce: U256,
+}
+
+/// The lanes of the local proposer, highest first.
+pub struct TxLanes {
+	lanes: Vec<Lane>,
+	/// Maximum number of transactions of a sender proposed ahead in a block.
+	sender_limit: u32,
+	/// Maximum number of lane transactions of a sender in the pool, counted in nonces from its
+	/// account's.
+	pool_limit: u32,
+}
+
+impl TxLanes {
+	pub fn new(lanes: Vec<Lane>, sender_limit: u32, pool_limit: u32) -> Self {
+		Self { lanes, sender_limit, pool_limit }
+	}
+
+	/// The lane of the Ethereum transaction `extrinsic`, with its sender and nonce.
+	fn lane_of(&self, extrinsic: &impl Encode) -> Option<LaneTransaction> {
+		if self.lanes.is_empty() {
+			return None;
+		}
+		extrinsic.using_encoded(|encoded| {
+			// Only decode the transactions naming the contract of a rule.
+			let named = |to: &H160| encoded.windows(H160::len_bytes()).any(|w| w == to.as_bytes());
+			if !self.lanes.iter().flat_map(|lane| &lane.0).any(|rule| named(&rule.to)) {
+				return None;
+			}
+			let extrinsic = UncheckedExtrinsic::decode(&mut &encoded[..]).ok()?;
+			let RuntimeCall::Ethereum(pallet_ethereum::Call::transact { transaction }) =
+				&extrinsic.0.function
+			else {
+				return None;
+			};
+			let (nonce, action, input) = match transaction {
+				ethereum::TransactionV2::Legacy(t) => (t.nonce, &t.action, &t.input),
+				ethereum::TransactionV2::EIP2930(t) => (t.nonce, &t.action, &t.input),
+				ethereum::TransactionV2::EIP1559(t) => (t.nonce, &t.action, &t.input),
+			};
+			let ethereum::TransactionAction::Call(to) = action else {
+				return None;
+			};
+			let lane = self
+				.lanes
+				.iter()
+				.position(|lane| lane.0.iter().any(|rule| rule.matches(to, input)))?;
+			// Only recover the signer of the few transactions in a lane.
+			let sender = extrinsic.0.function.check_self_contained()?.ok()?;
+			Some(LaneTransaction { lane, sender, nonce })
+		})
+	}
+
+	/// Whether the pool takes `extrinsic` in: a lane transaction is turned away when it is
+	/// `pool_limit` or more nonces ahead of its sender's account, as returned by `account_nonce`.
+	fn admits<E>(
+		&self,
+		extrinsic: &impl Encode,
+		account_nonce: impl FnOnce(H160) -> Result<U256, E>,
+	) -> Result<bool, E> {
+		let Some(tx) = self.lane_of(extrinsic) else {
+			return Ok(true);
+		};
+		Ok(tx.nonce < account_nonce(tx.sender)?.saturating_add(self.pool_limit.into()))
+	}
+
+	/// Reorder `ready`: the transactions of the lanes, highest lane first, then the others, each
+	/// in pool order.
+	///
+	/// A transaction only moves ahead when the transactions it depends on, e.g. the previous
+	/// nonces of its sender, are proposed in the same lane or a higher one.
+	pub fn order<T>(&self, mut ready: Ready<T>) -> Ready<T>
+	where
+		T: InPoolTransaction + Send + Sync + 'static,
+		T::Transaction: Encode,
+	{
+		if self.lanes.is_empty() {
+			return ready;
+		}
+
+		let pending: Vec<Arc<T>> = ready.by_ref().collect();
+		let normal = self.lanes.len();
+		let mut lanes = vec![Vec::new(); normal + 1];
+		// Lane of the transaction providing each tag.
+		let mut providers = HashMap::<&TransactionTag, usize>::new();
+		let mut ahead = HashMap::<H160, u32>::new();
+		for tx in &pending {
+			let lane = self
+				.lane_of(tx.data())
+				.filter(|lane_tx| {
+					let providers_ahead = tx.requires().iter().all(|tag| {
+						providers.get(tag).map_or(true, |&provider| provider <= lane_tx.lane)
+					});
+					providers_ahead &&
+						ahead.get(&lane_tx.sender).map_or(true, |&count| count < self.sender_limit)
+				})
+				.map_or(normal, |lane_tx| {
+					*ahead.entry(lane_tx.sender).or_default() += 1;
+					lane_tx.lane
+				});
+			providers.extend(tx.provides().iter().map(|tag| (tag, lane)));
+			lanes[lane].push(tx.clone());
+		}
+
+		let ordered = lanes.into_iter().flatten().collect::<Vec<_>>().into_iter();
+		Box::new(LaneReady { ordered, invalid: HashSet::new(), inner: ready })
+	}
+}
+
+/// The ready transactions, as ordered by [`TxLanes::order`].
+struct LaneReady<T: InPoolTransaction> {
+	ordered: std::vec::IntoIter<Arc<T>>,
+	/// Tags provided by the transactions reported invalid and by their dependents.
+	invalid: HashSet<TransactionTag>,
+	/// The pool's iterator, drained.
+	inner: Ready<T>,
+}
+
+impl<T: InPoolTransaction> Iterator for LaneReady<T> {
+	type Item = Arc<T>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let invalid = &mut self.invalid;
+		self.ordered.find(|tx| {
+			if tx.requires().iter().any(|tag| invalid.contains(tag)) {
+				invalid.extend(tx.provides().iter().cloned());
+				return false;
+			}
+			true
+		})
+	}
+}
+
+impl<T: InPoolTransaction> ReadyTransactions for LaneReady<T> {
+	fn report_invalid(&mut self, tx: &Self::Item) {
+		// The pool's iterator is drained, so skip the dependents of `tx` here.
+		self.invalid.extend(tx.provides().iter().cloned());
+		self.inner.report_invalid(tx);
+	}
+}
+
+/// The transaction pool the RPC submits to: it turns away the lane transactions of a sender
+/// past `--tx-lane-pool-limit`, see [`TxLanes::admits`], so that a sender cannot fill the pool
+/// with transactions moving ahead of the others.
+pub struct LanePool<P, C> {
+	pool: Arc<P>,
+	client: Arc<C>,
+	lanes: Arc<TxLanes>,
+}
+
+impl<P, C> LanePool<P, C> {
+	pub fn new(pool: Arc<P>, client: Arc<C>, lanes: Arc<TxLanes>) -> Self {
+		Self { pool, client, lanes }
+	}
+}
+
+impl<P, C> LanePool<P, C>
+where
+	P: TransactionPool,
+	C: ProvideRuntimeApi<P::Block>,
+	C::Api: EthereumRuntimeRPCApi<P::Block>,
+{
+	/// Check that the pool takes `xt` in on top of block `at`.
+	fn admit(
+		&self,
+		at: <P::Block as BlockT>::Hash,
+		xt: &TransactionFor<P>,
+	) -> Result<(), P::Error> {
+		let admitted = self.lanes.admits(xt, |sender| {
+			self.client
+				.runtime_api()
+				.account_basic(at, sender)
+				.map(|account| account.nonce)
+				.map_err(|e| PoolError::RuntimeApi(e.to_string()))
+		})?;
+		if !admitted {
+			return Err(PoolError::ImmediatelyDropped.into());
+		}
+		Ok(())
+	}
+}
+
+#[async_trait]
+impl<P, C> TransactionPool for LanePool<P, C>
+where
+	P: TransactionPool,
+	C: ProvideRuntimeApi<P::Block> + Send + Sync,
+	C::Api: EthereumRuntimeRPCApi<P::Block>,
+{
+	type Block = P::Block;
+	type Hash = P::Hash;
+	type InPoolTransaction = P::InPoolTransaction;
+	type Error = P::Error;
+
+	async fn submit_at(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		source: TransactionSource,
+		xts: Vec<TransactionFor<Self>>,
+	) -> Result<Vec<Result<TxHash<Self>, Self::Error>>, Self::Error> {
+		let mut admitted = Vec::with_capacity(xts.len());
+		let mut submitted = Vec::with_capacity(xts.len());
+		for xt in xts {
+			let admit = self.admit(at, &xt);
+			if admit.is_ok() {
+				submitted.push(xt);
+			}
+			admitted.push(admit);
+		}
+		let mut submitted = self.pool.submit_at(at, source, submitted).await?.into_iter();
+		Ok(admitted
+			.into_iter()
+			.map(|admit| {
+				admit.and_then(|()| {
+					submitted.next().expect("one result per submitted transaction; qed")
+				})
+			})
+			.collect())
+	}
+
+	async fn submit_one(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> Result<TxHash<Self>, Self::Error> {
+		self.admit(at, &xt)?;
+		self.pool.submit_one(at, source, xt).await
+	}
+
+	async fn submit_and_watch(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> Result<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error> {
+		self.admit(at, &xt)?;
+		self.pool.submit_and_watch(at, source, xt).await
+	}
+
+	async fn ready_at(&self, at: <Self::Block as BlockT>::Hash) -> Ready<Self::InPoolTransaction> {
+		self.pool.ready_at(at).await
+	}
+
+	async fn ready_at_with_timeout(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		timeout: Duration,
+	) -> Ready<Self::InPoolTransaction> {
+		self.pool.ready_at_with_timeout(at, timeout).await
+	}
+
+	fn ready(&self) -> Ready<Self::InPoolTransaction> {
+		self.pool.ready()
+	}
+
+	async fn report_invalid(
+		&self,
+		at: Option<<Self::Block as BlockT>::Hash>,
+		invalid_tx_errors: TxInvalidityReportMap<TxHash<Self>>,
+	) -> Vec<Arc<Self::InPoolTransaction>> {
+		self.pool.report_invalid(at, invalid_tx_errors).await
+	}
+
+	fn futures(&self) -> Vec<Self::InPoolTransaction> {
+		self.pool.futures()
+	}
+
+	fn status(&self) -> PoolStatus {
+		self.pool.status()
+	}
+
+	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>> {
+		self.pool.import_notification_stream()
+	}
+
+	fn on_broadcasted(&self, propagations: HashMap<TxHash<Self>, Vec<String>>) {
+		self.pool.on_broadcasted(propagations)
+	}
+
+	fn hash_of(&self, xt: &TransactionFor<Self>) -> TxHash<Self> {
+		self.pool.hash_of(xt)
+	}
+
+	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
+		self.pool.ready_transaction(hash)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use fintradex_runtime::Runtime;
+	use sp_core::{ecdsa, Pair, H256};
+	use sp_runtime::transaction_validity::{TransactionLongevity, TransactionPriority};
+	use std::sync::Mutex;
+
+	const ORACLE: H160 = H160::repeat_byte(0x0a);
+	const EXCHANGE: H160 = H160::repeat_byte(0x0e);
+	const TOKEN: H160 = H160::repeat_byte(0x70);
+
+	/// A transaction of sender `key` with nonce `nonce` calling `to`.
+	fn transaction(key: u8, nonce: u64, to: H160) -> UncheckedExtrinsic {
+		let message = ethereum::EIP1559TransactionMessage {
+			chain_id: 42,
+			nonce: nonce.into(),
+			max_priority_fee_per_gas: U256::zero(),
+			max_fee_per_gas: U256::zero(),
+			gas_limit: 100_000.into(),
+			action: ethereum::TransactionAction::Call(to),
+			value: U256::zero(),
+			input: vec![0xde, 0xad, 0xbe, 0xef],
+			access_list: vec![],
+		};
+		let signature = ecdsa::Pair::from_seed(&[key; 32]).sign_prehashed(&message.hash().0).0;
+		let transaction = ethereum::TransactionV2::EIP1559(ethereum::EIP1559Transaction {
+			chain_id: message.chain_id,
+			nonce: message.nonce,
+			max_priority_fee_per_gas: message.max_priority_fee_per_gas,
+			max_fee_per_gas: message.max_fee_per_gas,
+			gas_limit: message.gas_limit,
+			action: message.action,
+			value: message.value,
+			input: message.input,
+			access_list: message.access_list,
+			odd_y_parity: signature[64] != 0,
+			r: H256::from_slice(&signature[..32]),
+			s: H256::from_slice(&signature[32..64]),
+		});
+		UncheckedExtrinsic::new_bare(
+			pallet_ethereum::Call::<Runtime>::transact { transaction }.into(),
+		)
+	}
+
+	/// A pooled transaction, its dependencies given by the sender's previous nonce.
+	struct TestTx {
+		id: u8,
+		data: UncheckedExtrinsic,
+		requires: Vec<TransactionTag>,
+		provides: Vec<TransactionTag>,
+	}
+
+	impl InPoolTransaction for TestTx {
+		type Transaction = UncheckedExtrinsic;
+		type Hash = u8;
+
+		fn data(&self) -> &UncheckedExtrinsic {
+			&self.data
+		}
+
+		fn hash(&self) -> &u8 {
+			&self.id
+		}
+
+		fn priority(&self) -> &TransactionPriority {
+			&0
+		}
+
+		fn longevity(&self) -> &TransactionLongevity {
+			&TransactionLongevity::MAX
+		}
+
+		fn requires(&self) -> &[TransactionTag] {
+			&self.requires
+		}
+
+		fn provides(&self) -> &[TransactionTag] {
+			&self.provides
+		}
+
+		fn is_propagable(&self) -> bool {
+			true
+		}
+	}
+
+	/// Transaction `id` of sender `key` with nonce `nonce` calling `to`.
+	fn pooled(id: u8, key: u8, nonce: u8, to: H160) -> Arc<TestTx> {
+		Arc::new(TestTx {
+			id,
+			data: transaction(key, nonce.into(), to),
+			requires: if nonce == 0 { vec![] } else { vec![vec![key, nonce - 1]] },
+			provides: vec![vec![key, nonce]],
+		})
+	}
+
+	/// The pool's ready queue, recording the transactions reported invalid.
+	struct TestReady {
+		transactions: std::vec::IntoIter<Arc<TestTx>>,
+		reported: Arc<Mutex<Vec<u8>>>,
+	}
+
+	impl Iterator for TestReady {
+		type Item = Arc<TestTx>;
+
+		fn next(&mut self) -> Option<Self::Item> {
+			self.transactions.next()
+		}
+	}
+
+	impl ReadyTransactions for TestReady {
+		fn report_invalid(&mut self, tx: &Self::Item) {
+			self.reported.lock().unwrap().push(tx.id);
+		}
+	}
+
+	fn ready(transactions: Vec<Arc<TestTx>>) -> (Ready<TestTx>, Arc<Mutex<Vec<u8>>>) {
+		let reported = Arc::new(Mutex::new(Vec::new()));
+		let ready =
+			TestReady { transactions: transactions.into_iter(), reported: reported.clone() };
+		(Box::new(ready), reported)
+	}
+
+	fn ordered(lanes: &TxLanes, transactions: Vec<Arc<TestTx>>) -> Vec<u8> {
+		lanes.order(ready(transactions).0).map(|tx| tx.id).collect()
+	}
+
+	fn lanes(sender_limit: u32) -> TxLanes {
+		let rule = |to| Rule { to, selector: None };
+		TxLanes::new(vec![Lane(vec![rule(ORACLE)]), Lane(vec![rule(EXCHANGE)])], sender_limit, 8)
+	}
+
+	#[test]
+	fn lane_transactions_are_promoted_highest_lane_first() {
+		let transactions = vec![
+			pooled(1, 1, 0, TOKEN),
+			pooled(2, 2, 0, EXCHANGE),
+			pooled(3, 3, 0, ORACLE),
+			pooled(4, 4, 0, TOKEN),
+		];
+		assert_eq!(ordered(&lanes(4), transactions), vec![3, 2, 1, 4]);
+	}
+
+	#[test]
+	fn transactions_stay_behind_their_lower_lane_providers() {
+		let transactions = vec![
+			// Its previous nonce is not in a lane.
+			pooled(1, 1, 0, TOKEN),
+			pooled(2, 1, 1, ORACLE),
+			// Its previous nonce is in a lower lane.
+			pooled(3, 2, 0, EXCHANGE),
+			pooled(4, 2, 1, ORACLE),
+			// Its previous nonce is in a higher lane.
+			pooled(5, 3, 0, ORACLE),
+			pooled(6, 3, 1, EXCHANGE),
+		];
+		assert_eq!(ordered(&lanes(4), transactions), vec![5, 3, 6, 1, 2, 4]);
+	}
+
+	#[test]
+	fn a_sender_only_gets_a_few_transactions_ahead() {
+		let transactions = vec![
+			pooled(1, 1, 0, TOKEN),
+			pooled(2, 2, 0, ORACLE),
+			pooled(3, 2, 1, ORACLE),
+			pooled(4, 2, 2, ORACLE),
+			pooled(5, 3, 0, ORACLE),
+		];
+		assert_eq!(ordered(&lanes(2), transactions), vec![2, 3, 5, 1, 4]);
+	}
+
+	#[test]
+	fn invalid_transactions_skip_their_dependents() {
+		let (pool, reported) = ready(vec![
+			pooled(1, 1, 0, TOKEN),
+			pooled(2, 2, 0, ORACLE),
+			pooled(3, 2, 1, ORACLE),
+			pooled(4, 2, 2, TOKEN),
+		]);
+		let mut ready = lanes(4).order(pool);
+		let first = ready.next().unwrap();
+		assert_eq!(first.id, 2);
+		ready.report_invalid(&first);
+
+		assert_eq!(ready.map(|tx| tx.id).collect::<Vec<_>>(), vec![1]);
+		assert_eq!(*reported.lock().unwrap(), vec![2]);
+	}
+
+	#[test]
+	fn without_lanes_the_pool_order_is_kept() {
+		let lanes = TxLanes::new(vec![], 4, 8);
+		let transactions = vec![pooled(1, 1, 0, TOKEN), pooled(2, 2, 0, ORACLE)];
+		assert_eq!(ordered(&lanes, transactions), vec![1, 2]);
+	}
+
+	#[test]
+	fn lane_transactions_far_ahead_of_their_sender_are_turned_away() {
+		let lanes = lanes(4);
+		let admits =
+			|nonce, to| lanes.admits(&transaction(1, nonce, to), |_| Ok::<_, ()>(3.into()));
+		assert_eq!(admits(10, ORACLE), Ok(true));
+		assert_eq!(admits(11, ORACLE), Ok(false));
+		assert_eq!(admits(11, TOKEN), Ok(true));
+	}
+}
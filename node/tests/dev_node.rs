@@ -0,0 +1,89 @@
+//! Smoke tests of the standalone development node, `--dev --sealing`.
+
+use std::{
+	io::{Read, Write},
+	net::{TcpListener, TcpStream},
+	process::{Child, Command, Stdio},
+	thread,
+	time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+const NODE: &str = env!("CARGO_BIN_EXE_fintradex-node");
+
+/// How long the node may take to open its RPC server.
+const STARTUP: Duration = Duration::from_secs(120);
+
+/// Kills the node when the test ends, whether it passes or not.
+struct Node(Child);
+
+impl Drop for Node {
+	fn drop(&mut self) {
+		let _ = self.0.kill();
+		let _ = self.0.wait();
+	}
+}
+
+fn free_port() -> u16 {
+	TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+/// The result of the JSON-RPC call `method(params)`, `None` while the server cannot be reached.
+fn call(port: u16, method: &str, params: Value) -> Option<Value> {
+	let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string();
+	let mut stream = TcpStream::connect(("127.0.0.1", port)).ok()?;
+	write!(
+		stream,
+		"POST / HTTP/1.1\r\nHost: 127.0.0.1\r\nContent-Type: application/json\r\n\
+		Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+		body.len()
+	)
+	.ok()?;
+	let mut response = String::new();
+	stream.read_to_string(&mut response).ok()?;
+	let (_, body) = response.split_once("\r\n\r\n")?;
+	let mut response: Value = serde_json::from_str(body).ok()?;
+	assert!(response.get("error").is_none(), "{method} failed: {response}");
+	Some(response["result"].take())
+}
+
+#[test]
+fn dev_node_seals_blocks_on_demand() {
+	let port = free_port();
+	let _node = Node(
+		Command::new(NODE)
+			.args(["--dev", "--tmp", "--sealing", "manual", "--no-hardware-benchmarks"])
+			.args(["--no-telemetry", "--no-prometheus", "--port", &free_port().to_string()])
+			.args(["--rpc-port", &port.to_string()])
+			.stdout(Stdio::null())
+			.stderr(Stdio::null())
+			.spawn()
+			.unwrap(),
+	);
+
+	let started = Instant::now();
+	while call(port, "system_health", json!([])).is_none() {
+		assert!(started.elapsed() < STARTUP, "the development node did not start");
+		thread::sleep(Duration::from_millis(500));
+	}
+
+	for number in ["0x1", "0x2"] {
+		call(port, "engine_createBlock", json!([true, true, null])).unwrap();
+		let header = call(port, "chain_getHeader", json!([])).unwrap();
+		assert_eq!(header["number"], number);
+	}
+}
+
+#[test]
+fn sealing_requires_a_development_chain() {
+	let output = Command::new(NODE)
+		.args(["--chain", "local", "--tmp", "--sealing", "instant", "--no-hardware-benchmarks"])
+		.args(["--no-telemetry", "--no-prometheus", "--port", &free_port().to_string()])
+		.args(["--rpc-port", &free_port().to_string()])
+		.output()
+		.unwrap();
+
+	assert!(!output.status.success());
+	assert!(String::from_utf8_lossy(&output.stderr).contains("requires `--dev`"));
+}
@@ -0,0 +1,34 @@
+//! Benchmarking setup for `pallet_account_mapping`.
+
+use super::*;
+use frame_benchmarking::v2::*;
+use frame_system::RawOrigin;
+use sp_core::{crypto::KeyTypeId, ecdsa};
+
+const BENCH_KEY_TYPE: KeyTypeId = KeyTypeId(*b"bnch");
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn claim_account() {
+		let caller: T::AccountId = whitelisted_caller();
+
+		let public = sp_io::crypto::ecdsa_generate(BENCH_KEY_TYPE, None);
+		let digest = eth_message_hash(&Pallet::<T>::claim_message(&caller));
+		let signature: ecdsa::Signature =
+			sp_io::crypto::ecdsa_sign_prehashed(BENCH_KEY_TYPE, &public, &digest)
+				.expect("key was just generated; qed");
+		let signature: EcdsaSignature = signature.0;
+		let address = Pallet::<T>::recover_signer(&caller, &signature)
+			.expect("signature was just made; qed");
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), address, signature);
+
+		assert_eq!(Accounts::<T>::get(address), Some(caller));
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
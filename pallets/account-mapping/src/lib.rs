@@ -0,0 +1,209 @@
+//! # Account Mapping Pallet
+//!
+//! Unifies the two identities of a user: the native `AccountId` used by Substrate extrinsics
+//! and the `H160` address used by `pallet_evm`.
+//!
+//! A user proves ownership of both keys by submitting [`Pallet::claim_account`] signed with
+//! their native key and carrying an Ethereum `personal_sign` signature of
+//! [`Pallet::claim_message`]. From then on [`UnifiedAddressMapping`] resolves the `H160` to the
+//! native account, so the same balance and nonce serve both worlds. Funds already held by the
+//! address' implicit account are swept into the native account when the binding is made.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod weights;
+pub use weights::*;
+
+use alloc::{format, vec::Vec};
+use core::marker::PhantomData;
+
+use codec::Encode;
+use frame_support::traits::{
+	fungible::{Inspect, Mutate},
+	tokens::{Fortitude, Preservation},
+};
+use pallet_evm::AddressMapping;
+use sp_core::{hexdisplay::HexDisplay, H160, H256};
+use sp_io::hashing::keccak_256;
+use sp_runtime::traits::Zero;
+
+/// An Ethereum `personal_sign` signature: `r || s || v`.
+pub type EcdsaSignature = [u8; 65];
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The currency whose balance is swept from the implicit account on binding.
+		type Currency: Mutate<Self::AccountId>;
+
+		/// Mapping used for addresses that are not bound to a native account.
+		type DefaultAddressMapping: AddressMapping<Self::AccountId>;
+
+		/// The EVM chain id, part of the signed message so claims cannot be replayed on
+		/// another chain.
+		#[pallet::constant]
+		type ChainId: Get<u64>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+	}
+
+	/// The native account an EVM address is bound to.
+	#[pallet::storage]
+	pub type Accounts<T: Config> = StorageMap<_, Twox64Concat, H160, T::AccountId, OptionQuery>;
+
+	/// The EVM address a native account is bound to.
+	#[pallet::storage]
+	pub type EvmAddresses<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, H160, OptionQuery>;
+
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		/// Bindings to create at genesis.
+		pub mappings: Vec<(T::AccountId, H160)>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			for (who, address) in &self.mappings {
+				assert!(
+					!EvmAddresses::<T>::contains_key(who) && !Accounts::<T>::contains_key(address),
+					"duplicate account mapping in genesis",
+				);
+				Accounts::<T>::insert(address, who);
+				EvmAddresses::<T>::insert(who, address);
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// `who` is now bound to `address`.
+		AccountClaimed { who: T::AccountId, address: H160 },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The native account is already bound to an EVM address.
+		AccountAlreadyMapped,
+		/// The EVM address is already bound to a native account.
+		EvmAddressAlreadyMapped,
+		/// The signature could not be recovered.
+		BadSignature,
+		/// The signature was not made by the claimed EVM address.
+		InvalidSignature,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Bind the caller to `address`.
+		///
+		/// `signature` must be a `personal_sign` of [`Pallet::claim_message`] for the caller,
+		/// made with the private key of `address`.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::claim_account())]
+		pub fn claim_account(
+			origin: OriginFor<T>,
+			address: H160,
+			signature: EcdsaSignature,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			ensure!(!EvmAddresses::<T>::contains_key(&who), Error::<T>::AccountAlreadyMapped);
+			ensure!(!Accounts::<T>::contains_key(address), Error::<T>::EvmAddressAlreadyMapped);
+
+			let signer = Self::recover_signer(&who, &signature).ok_or(Error::<T>::BadSignature)?;
+			ensure!(signer == address, Error::<T>::InvalidSignature);
+
+			Self::bind(&who, address)
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The message an EVM key has to `personal_sign` to be bound to `who`.
+	pub fn claim_message(who: &T::AccountId) -> Vec<u8> {
+		format!(
+			"FintradeX account binding\naccount: 0x{}\nchain id: {}",
+			HexDisplay::from(&who.encode()),
+			T::ChainId::get(),
+		)
+		.into_bytes()
+	}
+
+	/// The EVM address that signed the claim message of `who`, if any.
+	pub fn recover_signer(who: &T::AccountId, signature: &EcdsaSignature) -> Option<H160> {
+		let digest = eth_message_hash(&Self::claim_message(who));
+		let public = sp_io::crypto::secp256k1_ecdsa_recover(signature, &digest).ok()?;
+		Some(H160::from(H256::from(keccak_256(&public))))
+	}
+
+	fn bind(who: &T::AccountId, address: H160) -> frame_support::dispatch::DispatchResult {
+		let implicit = T::DefaultAddressMapping::into_account_id(address);
+		if implicit != *who {
+			// Ethereum transactions already signed for the implicit account must not become
+			// valid again under the bound account's nonce.
+			let implicit_nonce = frame_system::Pallet::<T>::account_nonce(&implicit);
+			frame_system::Account::<T>::mutate(who, |account| {
+				account.nonce = account.nonce.max(implicit_nonce)
+			});
+
+			let free = T::Currency::reducible_balance(
+				&implicit,
+				Preservation::Expendable,
+				Fortitude::Polite,
+			);
+			if !free.is_zero() {
+				T::Currency::transfer(&implicit, who, free, Preservation::Expendable)?;
+			}
+		}
+
+		Accounts::<T>::insert(address, who);
+		EvmAddresses::<T>::insert(who, address);
+		Self::deposit_event(Event::AccountClaimed { who: who.clone(), address });
+		Ok(())
+	}
+}
+
+/// Keccak hash of `message` as prefixed by Ethereum's `personal_sign`.
+pub fn eth_message_hash(message: &[u8]) -> [u8; 32] {
+	let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+	prefixed.extend_from_slice(message);
+	keccak_256(&prefixed)
+}
+
+/// [`AddressMapping`] resolving bound addresses to their native account and falling back to
+/// `T::DefaultAddressMapping` for the others.
+pub struct UnifiedAddressMapping<T>(PhantomData<T>);
+
+impl<T: Config> AddressMapping<T::AccountId> for UnifiedAddressMapping<T> {
+	fn into_account_id(address: H160) -> T::AccountId {
+		Accounts::<T>::get(address)
+			.unwrap_or_else(|| T::DefaultAddressMapping::into_account_id(address))
+	}
+}
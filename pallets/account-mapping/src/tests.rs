@@ -0,0 +1,90 @@
+use crate::{mock::*, Accounts, Error, EvmAddresses, Event, EcdsaSignature, Pallet};
+use frame_support::{assert_noop, assert_ok, traits::fungible::Mutate};
+use pallet_evm::{AddressMapping, HashedAddressMapping};
+use sp_core::{ecdsa, Pair, H160};
+use sp_runtime::{traits::BlakeTwo256, AccountId32};
+
+const ALICE: AccountId32 = AccountId32::new([1; 32]);
+const BOB: AccountId32 = AccountId32::new([2; 32]);
+
+fn evm_key(seed: u8) -> ecdsa::Pair {
+	ecdsa::Pair::from_seed(&[seed; 32])
+}
+
+/// `personal_sign` of the claim message of `who` with `key`, and the key's address.
+fn sign_claim(key: &ecdsa::Pair, who: &AccountId32) -> (H160, EcdsaSignature) {
+	let digest = crate::eth_message_hash(&Pallet::<Test>::claim_message(who));
+	let signature = key.sign_prehashed(&digest).0;
+	let public = sp_io::crypto::secp256k1_ecdsa_recover(&signature, &digest).unwrap();
+	let address = H160::from_slice(&sp_io::hashing::keccak_256(&public)[12..]);
+	(address, signature)
+}
+
+#[test]
+fn claim_binds_both_ways() {
+	new_test_ext().execute_with(|| {
+		let (address, signature) = sign_claim(&evm_key(7), &ALICE);
+
+		assert_ok!(AccountMapping::claim_account(RuntimeOrigin::signed(ALICE), address, signature));
+
+		assert_eq!(Accounts::<Test>::get(address), Some(ALICE));
+		assert_eq!(EvmAddresses::<Test>::get(ALICE), Some(address));
+		assert_eq!(crate::UnifiedAddressMapping::<Test>::into_account_id(address), ALICE);
+		System::assert_last_event(Event::AccountClaimed { who: ALICE, address }.into());
+	});
+}
+
+#[test]
+fn signature_for_another_account_is_rejected() {
+	new_test_ext().execute_with(|| {
+		let (address, signature) = sign_claim(&evm_key(7), &BOB);
+
+		assert_noop!(
+			AccountMapping::claim_account(RuntimeOrigin::signed(ALICE), address, signature),
+			Error::<Test>::InvalidSignature,
+		);
+		assert_eq!(
+			crate::UnifiedAddressMapping::<Test>::into_account_id(address),
+			HashedAddressMapping::<BlakeTwo256>::into_account_id(address),
+		);
+	});
+}
+
+#[test]
+fn bindings_are_exclusive() {
+	new_test_ext().execute_with(|| {
+		let (address, signature) = sign_claim(&evm_key(7), &ALICE);
+		assert_ok!(AccountMapping::claim_account(RuntimeOrigin::signed(ALICE), address, signature));
+
+		let (other, signature) = sign_claim(&evm_key(8), &ALICE);
+		assert_noop!(
+			AccountMapping::claim_account(RuntimeOrigin::signed(ALICE), other, signature),
+			Error::<Test>::AccountAlreadyMapped,
+		);
+
+		let (_, signature) = sign_claim(&evm_key(7), &BOB);
+		assert_noop!(
+			AccountMapping::claim_account(RuntimeOrigin::signed(BOB), address, signature),
+			Error::<Test>::EvmAddressAlreadyMapped,
+		);
+	});
+}
+
+#[test]
+fn claim_carries_over_balance_and_nonce() {
+	new_test_ext().execute_with(|| {
+		let (address, signature) = sign_claim(&evm_key(7), &ALICE);
+		let implicit = HashedAddressMapping::<BlakeTwo256>::into_account_id(address);
+		assert_ok!(Balances::mint_into(&ALICE, 10));
+		assert_ok!(Balances::mint_into(&implicit, 100));
+		for _ in 0..5 {
+			System::inc_account_nonce(&implicit);
+		}
+
+		assert_ok!(AccountMapping::claim_account(RuntimeOrigin::signed(ALICE), address, signature));
+
+		assert_eq!(Balances::free_balance(&ALICE), 110);
+		assert_eq!(Balances::free_balance(&implicit), 0);
+		assert_eq!(System::account_nonce(&ALICE), 5);
+	});
+}
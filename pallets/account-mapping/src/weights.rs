@@ -0,0 +1,37 @@
+//! Weights for `pallet_account_mapping`.
+//!
+//! Estimated from the storage accesses of each call until the benchmarks in
+//! `benchmarking.rs` are run on reference hardware.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use core::marker::PhantomData;
+use frame_support::{traits::Get, weights::Weight};
+
+/// Weight functions needed for `pallet_account_mapping`.
+pub trait WeightInfo {
+	fn claim_account() -> Weight;
+}
+
+/// Weights for `pallet_account_mapping` using the runtime's database weights.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `AccountMapping::EvmAddresses` (r:1 w:1)
+	/// Storage: `AccountMapping::Accounts` (r:1 w:1)
+	/// Storage: `System::Account` (r:2 w:2)
+	fn claim_account() -> Weight {
+		// Signature recovery plus the balance transfer.
+		Weight::from_parts(90_000_000, 7_000)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	fn claim_account() -> Weight {
+		Weight::from_parts(90_000_000, 7_000)
+	}
+}
@@ -1,6 +1,7 @@
 #[cfg(all(feature = "std", feature = "metadata-hash"))]
 #[docify::export(template_enable_metadata_hash)]
 fn main() {
+	evm_tracing();
 	substrate_wasm_builder::WasmBuilder::init_with_defaults()
 		.enable_metadata_hash("Fintra", 12)
 		.build();
@@ -8,10 +9,24 @@ fn main() {
 
 #[cfg(all(feature = "std", not(feature = "metadata-hash")))]
 fn main() {
+	evm_tracing();
 	substrate_wasm_builder::WasmBuilder::build_using_defaults();
 }
 
 /// The wasm builder is deactivated when compiling
 /// this crate for wasm to speed up the compilation.
 #[cfg(not(feature = "std"))]
-fn main() {}
+fn main() {
+	evm_tracing();
+}
+
+/// Compile the EVM tracers in with `FINTRADEX_EVM_TRACING=1`, see `src/evm_tracing.rs`.
+///
+/// The wasm build of the runtime inherits the variable, so it is set for both builds.
+fn evm_tracing() {
+	println!("cargo:rustc-check-cfg=cfg(evm_tracing)");
+	println!("cargo:rerun-if-env-changed=FINTRADEX_EVM_TRACING");
+	if std::env::var("FINTRADEX_EVM_TRACING").is_ok_and(|value| value == "1") {
+		println!("cargo:rustc-cfg=evm_tracing");
+	}
+}
@@ -30,7 +30,7 @@ use sp_version::RuntimeVersion;
 use super::{
 	AccountId, Balance, Block, ConsensusHook, Executive, InherentDataExt, Nonce, ParachainSystem,
 	Runtime, RuntimeCall, RuntimeGenesisConfig, SessionKeys, System, TransactionPayment,
-	SLOT_DURATION, VERSION,Ethereum,UncheckedExtrinsic
+	RELAY_PARENT_OFFSET, SLOT_DURATION, VERSION,Ethereum,UncheckedExtrinsic
 };
 
 // we move some impls outside so we can easily use them with `docify`.
@@ -49,16 +49,6 @@ impl Runtime {
 	}
 }
 
-// Add dispatch function for native execution
-pub fn dispatch(method: &str, data: &[u8]) -> Option<Vec<u8>> {
-	// This function is called by the native executor to dispatch runtime API calls
-	// The method parameter contains the API name and the data contains the encoded parameters
-	// For now, we return None as this is a placeholder implementation
-	// In a full implementation, this would decode the method and data and call the appropriate runtime API
-	None
-}
-
-
 impl_runtime_apis! {
 	impl sp_consensus_aura::AuraApi<Block, AuraId> for Runtime {
 		fn slot_duration() -> sp_consensus_aura::SlotDuration {
@@ -79,6 +69,21 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl cumulus_primitives_core::GetCoreSelectorApi<Block> for Runtime {
+		fn core_selector() -> (
+			cumulus_primitives_core::CoreSelector,
+			cumulus_primitives_core::ClaimQueueOffset,
+		) {
+			ParachainSystem::core_selector()
+		}
+	}
+
+	impl cumulus_primitives_core::RelayParentOffsetApi<Block> for Runtime {
+		fn relay_parent_offset() -> u32 {
+			RELAY_PARENT_OFFSET
+		}
+	}
+
 	impl sp_api::Core<Block> for Runtime {
 		fn version() -> RuntimeVersion {
 			VERSION
@@ -449,6 +454,33 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl crate::evm_tracing::DebugRuntimeApi<Block> for Runtime {
+		fn trace_transaction(
+			extrinsics: Vec<<Block as BlockT>::Extrinsic>,
+			transaction: H256,
+			tracer: crate::evm_tracing::Tracer,
+		) -> Result<crate::evm_tracing::Trace, crate::evm_tracing::TraceError> {
+			crate::evm_tracing::trace_transaction(extrinsics, transaction, tracer)
+		}
+
+		fn trace_block(
+			extrinsics: Vec<<Block as BlockT>::Extrinsic>,
+			tracer: crate::evm_tracing::Tracer,
+		) -> Result<Vec<(H256, crate::evm_tracing::Trace)>, crate::evm_tracing::TraceError> {
+			crate::evm_tracing::trace_block(extrinsics, tracer)
+		}
+	}
+
+	impl crate::simulate::SimulateRuntimeApi<Block> for Runtime {
+		fn simulate(
+			blocks: Vec<crate::simulate::SimulatedBlock>,
+			validation: bool,
+			gas_cap: u64,
+		) -> Result<Vec<crate::simulate::BlockResult>, crate::simulate::SimulateError> {
+			crate::simulate::simulate(blocks, validation, gas_cap)
+		}
+	}
+
 	impl fp_rpc::ConvertTransactionRuntimeApi<Block> for Runtime {
 		fn convert_transaction(transaction: EthereumTransaction) -> <Block as BlockT>::Extrinsic {
 			UncheckedExtrinsic::new_bare(
@@ -35,6 +35,7 @@ polkadot_sdk::frame_benchmarking::define_benchmarks!(
 	[pallet_scheduler, Scheduler]
 	[pallet_asset_conversion_ops, AssetConversionMigration]
 	[pallet_evm, EVM]
+	[pallet_account_mapping, AccountMapping]
 	[cumulus_pallet_parachain_system, ParachainSystem]
 	[cumulus_pallet_xcmp_queue, XcmpQueue]
 );
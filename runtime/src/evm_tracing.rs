@@ -0,0 +1,190 @@
+//! EVM tracing for the `debug_*` RPC.
+//!
+//! [`DebugRuntimeApi`] replays a block on top of its parent and records its Ethereum
+//! transactions with one of the [`Tracer`]s. Listening to the EVM slows down every transaction,
+//! so recording is only compiled in with the `evm_tracing` cfg, set by the build script when
+//! `FINTRADEX_EVM_TRACING=1`: the runtime on chain answers [`TraceError::Unsupported`], and
+//! tracing nodes run such a build through `--wasm-runtime-overrides`, see the runtime README.
+
+use alloc::{string::String, vec::Vec};
+
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::{H160, H256, U256};
+
+#[cfg(any(evm_tracing, test))]
+mod listener;
+
+/// What to record while replaying a transaction.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub enum Tracer {
+	/// Geth's `callTracer`: the tree of calls made by the transaction.
+	Call,
+	/// Geth's `prestateTracer`: the accounts touched by the transaction, as they were before it.
+	Prestate,
+	/// Geth's default struct logger: one entry per executed opcode.
+	Raw { disable_stack: bool, disable_memory: bool, disable_storage: bool },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub enum CallType {
+	Call,
+	CallCode,
+	DelegateCall,
+	StaticCall,
+	Create,
+	Create2,
+	SelfDestruct,
+}
+
+/// A call of the call tracer, with the calls it made.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub struct CallFrame {
+	pub call_type: CallType,
+	pub from: H160,
+	pub to: H160,
+	pub value: U256,
+	pub gas: u64,
+	pub gas_used: u64,
+	pub input: Vec<u8>,
+	pub output: Vec<u8>,
+	/// Why the call failed, `None` if it succeeded.
+	pub error: Option<String>,
+	pub calls: Vec<CallFrame>,
+}
+
+/// An account of the prestate tracer.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub struct AccountState {
+	pub address: H160,
+	pub balance: U256,
+	pub nonce: U256,
+	pub code: Vec<u8>,
+	/// The slots read or written by the transaction.
+	pub storage: Vec<(H256, H256)>,
+}
+
+/// An executed opcode of the raw tracer.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub struct StructLog {
+	pub pc: u64,
+	pub op: u8,
+	/// Gas left before the opcode.
+	pub gas: u64,
+	pub gas_cost: u64,
+	pub depth: u32,
+	pub stack: Option<Vec<H256>>,
+	pub memory: Option<Vec<u8>>,
+	/// Slots of the executing contract seen so far, set on `SLOAD` and `SSTORE`.
+	pub storage: Option<Vec<(H256, H256)>>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub struct RawTrace {
+	pub gas: u64,
+	pub failed: bool,
+	pub return_value: Vec<u8>,
+	pub struct_logs: Vec<StructLog>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub enum Trace {
+	Call(CallFrame),
+	Prestate(Vec<AccountState>),
+	Raw(RawTrace),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub enum TraceError {
+	/// This runtime was built without the `evm_tracing` cfg.
+	Unsupported,
+	/// The transaction is not part of the given extrinsics.
+	TransactionNotFound,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Replays blocks with an EVM tracer attached.
+	///
+	/// The block must be initialized with `Core::initialize_block` on the same API instance,
+	/// at its parent, before calling these.
+	pub trait DebugRuntimeApi {
+		/// Apply `extrinsics` up to `transaction` and trace it.
+		fn trace_transaction(
+			extrinsics: Vec<Block::Extrinsic>,
+			transaction: H256,
+			tracer: Tracer,
+		) -> Result<Trace, TraceError>;
+
+		/// Apply `extrinsics` and trace every Ethereum transaction among them.
+		fn trace_block(
+			extrinsics: Vec<Block::Extrinsic>,
+			tracer: Tracer,
+		) -> Result<Vec<(H256, Trace)>, TraceError>;
+	}
+}
+
+#[cfg(evm_tracing)]
+pub fn trace_transaction(
+	extrinsics: Vec<crate::UncheckedExtrinsic>,
+	transaction: H256,
+	tracer: Tracer,
+) -> Result<Trace, TraceError> {
+	for extrinsic in extrinsics {
+		if ethereum_transaction_hash(&extrinsic) == Some(transaction) {
+			return Ok(listener::trace(tracer, || {
+				let _ = crate::Executive::apply_extrinsic(extrinsic.clone());
+			}));
+		}
+		let _ = crate::Executive::apply_extrinsic(extrinsic);
+	}
+	Err(TraceError::TransactionNotFound)
+}
+
+#[cfg(evm_tracing)]
+pub fn trace_block(
+	extrinsics: Vec<crate::UncheckedExtrinsic>,
+	tracer: Tracer,
+) -> Result<Vec<(H256, Trace)>, TraceError> {
+	let mut traces = Vec::new();
+	for extrinsic in extrinsics {
+		match ethereum_transaction_hash(&extrinsic) {
+			Some(hash) => {
+				let trace = listener::trace(tracer, || {
+					let _ = crate::Executive::apply_extrinsic(extrinsic.clone());
+				});
+				traces.push((hash, trace));
+			},
+			None => {
+				let _ = crate::Executive::apply_extrinsic(extrinsic);
+			},
+		}
+	}
+	Ok(traces)
+}
+
+#[cfg(not(evm_tracing))]
+pub fn trace_transaction(
+	_: Vec<crate::UncheckedExtrinsic>,
+	_: H256,
+	_: Tracer,
+) -> Result<Trace, TraceError> {
+	Err(TraceError::Unsupported)
+}
+
+#[cfg(not(evm_tracing))]
+pub fn trace_block(
+	_: Vec<crate::UncheckedExtrinsic>,
+	_: Tracer,
+) -> Result<Vec<(H256, Trace)>, TraceError> {
+	Err(TraceError::Unsupported)
+}
+
+#[cfg(evm_tracing)]
+fn ethereum_transaction_hash(extrinsic: &crate::UncheckedExtrinsic) -> Option<H256> {
+	match &extrinsic.0.function {
+		crate::RuntimeCall::Ethereum(pallet_ethereum::Call::transact { transaction }) => {
+			Some(transaction.hash())
+		},
+		_ => None,
+	}
+}
@@ -0,0 +1,500 @@
+//! Listeners turning the events of the EVM, its interpreter and its gasometer into traces.
+
+use alloc::{
+	collections::{BTreeMap, BTreeSet},
+	format,
+	rc::Rc,
+	vec::Vec,
+};
+use core::cell::RefCell;
+
+use evm::{
+	tracing::{Event as EvmEvent, EventListener as EvmEventListener},
+	CreateScheme, ExitReason,
+};
+use evm_gasometer::tracing::{Event as GasEvent, EventListener as GasEventListener};
+use evm_runtime::tracing::{Event as StepEvent, EventListener as StepEventListener};
+use frame_support::storage::{with_transaction, TransactionOutcome};
+use sp_core::{H160, H256, U256};
+use sp_runtime::DispatchError;
+
+use super::{AccountState, CallFrame, CallType, RawTrace, StructLog, Trace, Tracer};
+use crate::Runtime;
+
+/// Run `apply` with `tracer` attached to the EVM.
+pub fn trace(tracer: Tracer, apply: impl Fn()) -> Trace {
+	match tracer {
+		Tracer::Call => Trace::Call(listen(CallListener::default(), &apply).finish()),
+		Tracer::Raw { disable_stack, disable_memory, disable_storage } => {
+			let listener = RawListener {
+				disable_stack,
+				disable_memory,
+				disable_storage,
+				..Default::default()
+			};
+			Trace::Raw(listen(listener, &apply).finish())
+		},
+		Tracer::Prestate => {
+			// Find what the transaction touches in a throwaway run, read it as it was before,
+			// then apply the transaction for good.
+			let touched = with_transaction(|| {
+				let touched = listen(PrestateListener::default(), &apply);
+				TransactionOutcome::Rollback(Ok::<_, DispatchError>(touched))
+			})
+			.expect("the closure always returns Ok; qed");
+			let state = touched.read_state();
+			apply();
+			Trace::Prestate(state)
+		},
+	}
+}
+
+/// Receives the events of all three EVM layers.
+trait Listener: 'static {
+	fn evm(&mut self, _event: EvmEvent) {}
+	fn step(&mut self, _event: StepEvent) {}
+	fn gas(&mut self, _event: GasEvent) {}
+}
+
+/// Hands the events of one layer to a shared [`Listener`].
+struct Forward<L>(Rc<RefCell<L>>);
+
+impl<L: Listener> EvmEventListener for Forward<L> {
+	fn event(&mut self, event: EvmEvent) {
+		self.0.borrow_mut().evm(event)
+	}
+}
+
+impl<L: Listener> StepEventListener for Forward<L> {
+	fn event(&mut self, event: StepEvent) {
+		self.0.borrow_mut().step(event)
+	}
+}
+
+impl<L: Listener> GasEventListener for Forward<L> {
+	fn event(&mut self, event: GasEvent) {
+		self.0.borrow_mut().gas(event)
+	}
+}
+
+fn listen<L: Listener>(listener: L, apply: &impl Fn()) -> L {
+	let shared = Rc::new(RefCell::new(listener));
+	let mut evm = Forward(shared.clone());
+	let mut step = Forward(shared.clone());
+	let mut gas = Forward(shared.clone());
+	evm::tracing::using(&mut evm, || {
+		evm_runtime::tracing::using(&mut step, || evm_gasometer::tracing::using(&mut gas, apply))
+	});
+	drop((evm, step, gas));
+
+	Rc::try_unwrap(shared)
+		.ok()
+		.expect("the forwarders are dropped; qed")
+		.into_inner()
+}
+
+/// Gas of a call frame, following its gasometer.
+#[derive(Clone, Copy, Default)]
+struct FrameGas {
+	limit: u64,
+	left: u64,
+}
+
+impl FrameGas {
+	fn new(limit: u64) -> Self {
+		Self { limit, left: limit }
+	}
+
+	fn used(&self) -> u64 {
+		self.limit.saturating_sub(self.left)
+	}
+
+	/// Track `event`, returning the gas it charged.
+	fn record(&mut self, event: &GasEvent) -> u64 {
+		let (snapshot, cost, stipend) = match *event {
+			GasEvent::RecordCost { cost, snapshot } |
+			GasEvent::RecordTransaction { cost, snapshot } => (snapshot, cost, 0),
+			GasEvent::RecordDynamicCost { gas_cost, memory_gas, snapshot, .. } => {
+				let memory_cost = memory_gas.saturating_sub(snapshot.map_or(0, |s| s.memory_gas));
+				(snapshot, gas_cost.saturating_add(memory_cost), 0)
+			},
+			GasEvent::RecordStipend { stipend, snapshot } => (snapshot, 0, stipend),
+			GasEvent::RecordRefund { snapshot, .. } => (snapshot, 0, 0),
+		};
+		if let Some(snapshot) = snapshot {
+			self.limit = snapshot.gas_limit;
+			self.left = snapshot
+				.gas_limit
+				.saturating_sub(snapshot.used_gas.saturating_add(snapshot.memory_gas))
+				.saturating_add(stipend);
+		}
+		self.left = self.left.saturating_sub(cost);
+		cost
+	}
+}
+
+fn exit_error(reason: &ExitReason) -> Option<alloc::string::String> {
+	match reason {
+		ExitReason::Succeed(_) => None,
+		ExitReason::Revert(_) => Some("execution reverted".into()),
+		ExitReason::Error(error) => Some(format!("{error:?}")),
+		ExitReason::Fatal(fatal) => Some(format!("{fatal:?}")),
+	}
+}
+
+#[derive(Default)]
+struct CallListener {
+	/// Open calls, innermost last.
+	stack: Vec<(CallFrame, FrameGas)>,
+	/// Gas limit of the transaction, which covers more than its outermost call.
+	transaction_gas: Option<u64>,
+	root: Option<CallFrame>,
+}
+
+impl CallListener {
+	fn open(&mut self, mut frame: CallFrame) {
+		if self.stack.is_empty() {
+			frame.gas = self.transaction_gas.unwrap_or(frame.gas);
+		}
+		let gas = FrameGas::new(frame.gas);
+		self.stack.push((frame, gas));
+	}
+
+	fn finish(self) -> CallFrame {
+		self.root.unwrap_or(CallFrame {
+			call_type: CallType::Call,
+			from: H160::zero(),
+			to: H160::zero(),
+			value: U256::zero(),
+			gas: 0,
+			gas_used: 0,
+			input: Vec::new(),
+			output: Vec::new(),
+			error: Some("no EVM execution".into()),
+			calls: Vec::new(),
+		})
+	}
+}
+
+impl Listener for CallListener {
+	fn evm(&mut self, event: EvmEvent) {
+		match event {
+			EvmEvent::TransactCall { gas_limit, .. } |
+			EvmEvent::TransactCreate { gas_limit, .. } |
+			EvmEvent::TransactCreate2 { gas_limit, .. } => self.transaction_gas = Some(gas_limit),
+			EvmEvent::Call { code_address, transfer, input, target_gas, is_static, context } => {
+				let call_type = if is_static {
+					CallType::StaticCall
+				} else if context.address == code_address {
+					CallType::Call
+				} else if transfer.is_some() {
+					CallType::CallCode
+				} else {
+					CallType::DelegateCall
+				};
+				self.open(CallFrame {
+					call_type,
+					from: context.caller,
+					to: code_address,
+					value: transfer.as_ref().map_or(U256::zero(), |transfer| transfer.value),
+					gas: target_gas.unwrap_or_default(),
+					gas_used: 0,
+					input: input.to_vec(),
+					output: Vec::new(),
+					error: None,
+					calls: Vec::new(),
+				});
+			},
+			EvmEvent::Create { caller, address, scheme, value, init_code, target_gas } => {
+				let call_type = match scheme {
+					CreateScheme::Create2 { .. } => CallType::Create2,
+					_ => CallType::Create,
+				};
+				self.open(CallFrame {
+					call_type,
+					from: caller,
+					to: address,
+					value,
+					gas: target_gas.unwrap_or_default(),
+					gas_used: 0,
+					input: init_code.to_vec(),
+					output: Vec::new(),
+					error: None,
+					calls: Vec::new(),
+				});
+			},
+			EvmEvent::Suicide { address, target, balance } => {
+				if let Some((parent, _)) = self.stack.last_mut() {
+					parent.calls.push(CallFrame {
+						call_type: CallType::SelfDestruct,
+						from: address,
+						to: target,
+						value: balance,
+						gas: 0,
+						gas_used: 0,
+						input: Vec::new(),
+						output: Vec::new(),
+						error: None,
+						calls: Vec::new(),
+					});
+				}
+			},
+			EvmEvent::Exit { reason, return_value } => {
+				let Some((mut frame, gas)) = self.stack.pop() else { return };
+				frame.gas_used = gas.used();
+				frame.output = return_value.to_vec();
+				frame.error = exit_error(reason);
+				match self.stack.last_mut() {
+					Some((parent, _)) => parent.calls.push(frame),
+					None => self.root = Some(frame),
+				}
+			},
+			_ => {},
+		}
+	}
+
+	fn gas(&mut self, event: GasEvent) {
+		if let Some((_, gas)) = self.stack.last_mut() {
+			gas.record(&event);
+		}
+	}
+}
+
+#[derive(Default)]
+struct RawListener {
+	disable_stack: bool,
+	disable_memory: bool,
+	disable_storage: bool,
+	/// Gas of the open calls, innermost last.
+	frames: Vec<FrameGas>,
+	/// Slots seen so far, per contract.
+	storage: BTreeMap<H160, BTreeMap<H256, H256>>,
+	logs: Vec<StructLog>,
+	gas_used: u64,
+	failed: bool,
+	return_value: Vec<u8>,
+}
+
+impl RawListener {
+	fn record_slot(&mut self, address: H160, index: H256, value: H256) {
+		let slots = self.storage.entry(address).or_default();
+		slots.insert(index, value);
+		if !self.disable_storage {
+			if let Some(log) = self.logs.last_mut() {
+				log.storage = Some(slots.iter().map(|(index, value)| (*index, *value)).collect());
+			}
+		}
+	}
+
+	fn finish(self) -> RawTrace {
+		RawTrace {
+			gas: self.gas_used,
+			failed: self.failed,
+			return_value: self.return_value,
+			struct_logs: self.logs,
+		}
+	}
+}
+
+impl Listener for RawListener {
+	fn evm(&mut self, event: EvmEvent) {
+		match event {
+			EvmEvent::Call { target_gas, .. } | EvmEvent::Create { target_gas, .. } => {
+				self.frames.push(FrameGas::new(target_gas.unwrap_or_default()));
+			},
+			EvmEvent::Exit { reason, return_value } => {
+				let Some(gas) = self.frames.pop() else { return };
+				if self.frames.is_empty() {
+					self.gas_used = gas.used();
+					self.failed = !reason.is_succeed();
+					self.return_value = return_value.to_vec();
+				}
+			},
+			_ => {},
+		}
+	}
+
+	fn step(&mut self, event: StepEvent) {
+		match event {
+			StepEvent::Step { opcode, position, stack, memory, .. } => {
+				self.logs.push(StructLog {
+					pc: position.as_ref().map_or(0, |pc| *pc as u64),
+					op: opcode.0,
+					gas: self.frames.last().map_or(0, |gas| gas.left),
+					gas_cost: 0,
+					depth: self.frames.len() as u32,
+					stack: (!self.disable_stack).then(|| stack.data().clone()),
+					memory: (!self.disable_memory).then(|| memory.data().clone()),
+					storage: None,
+				});
+			},
+			StepEvent::SLoad { address, index, value } |
+			StepEvent::SStore { address, index, value } => self.record_slot(address, index, value),
+			_ => {},
+		}
+	}
+
+	fn gas(&mut self, event: GasEvent) {
+		let Some(gas) = self.frames.last_mut() else { return };
+		let cost = gas.record(&event);
+		// Charges made before the next step are for the last opcode.
+		if let Some(log) = self.logs.last_mut() {
+			log.gas_cost = log.gas_cost.saturating_add(cost);
+		}
+	}
+}
+
+#[derive(Default)]
+struct PrestateListener {
+	touched: BTreeMap<H160, BTreeSet<H256>>,
+}
+
+impl PrestateListener {
+	fn touch(&mut self, address: H160) {
+		self.touched.entry(address).or_default();
+	}
+
+	fn read_state(&self) -> Vec<AccountState> {
+		self.touched
+			.iter()
+			.map(|(address, slots)| {
+				let (account, _) = pallet_evm::Pallet::<Runtime>::account_basic(address);
+				AccountState {
+					address: *address,
+					balance: account.balance,
+					nonce: account.nonce,
+					code: pallet_evm::AccountCodes::<Runtime>::get(address),
+					storage: slots
+						.iter()
+						.map(|index| {
+							(*index, pallet_evm::AccountStorages::<Runtime>::get(address, index))
+						})
+						.collect(),
+				}
+			})
+			.collect()
+	}
+}
+
+impl Listener for PrestateListener {
+	fn evm(&mut self, event: EvmEvent) {
+		match event {
+			EvmEvent::TransactCall { caller, address, .. } |
+			EvmEvent::TransactCreate { caller, address, .. } |
+			EvmEvent::TransactCreate2 { caller, address, .. } |
+			EvmEvent::Create { caller, address, .. } => {
+				self.touch(caller);
+				self.touch(address);
+			},
+			EvmEvent::Call { code_address, context, .. } => {
+				self.touch(context.caller);
+				self.touch(context.address);
+				self.touch(code_address);
+			},
+			EvmEvent::Suicide { address, target, .. } => {
+				self.touch(address);
+				self.touch(target);
+			},
+			_ => {},
+		}
+	}
+
+	fn step(&mut self, event: StepEvent) {
+		if let StepEvent::SLoad { address, index, .. } | StepEvent::SStore { address, index, .. } =
+			event
+		{
+			self.touched.entry(address).or_default().insert(index);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{new_test_ext, BaseFee};
+	use pallet_evm::{FeeCalculator, Runner};
+
+	/// The well-known dev key Alith, bound to Alice at genesis.
+	const ALITH: H160 = H160(hex_literal::hex!("f24ff3a9cf04c71dbc94d0b566f7a27b94566cac"));
+	const CONTRACT: H160 = H160::repeat_byte(0x42);
+	/// `PUSH1 0x2a PUSH1 0 SSTORE STOP`.
+	const CODE: [u8; 6] = hex_literal::hex!("602a60005500");
+
+	/// Trace a call of Alith to [`CONTRACT`], running [`CODE`].
+	fn trace_call(tracer: Tracer) -> Trace {
+		pallet_evm::AccountCodes::<Runtime>::insert(CONTRACT, CODE.to_vec());
+		let (base_fee, _) = BaseFee::min_gas_price();
+		trace(tracer, || {
+			let info = <Runtime as pallet_evm::Config>::Runner::call(
+				ALITH,
+				CONTRACT,
+				vec![],
+				U256::zero(),
+				100_000,
+				Some(base_fee),
+				None,
+				None,
+				vec![],
+				true,
+				true,
+				None,
+				None,
+				<Runtime as pallet_evm::Config>::config(),
+			)
+			.expect("the call is valid");
+			assert!(info.exit_reason.is_succeed());
+		})
+	}
+
+	#[test]
+	fn call_tracer_records_the_call() {
+		new_test_ext().execute_with(|| {
+			let Trace::Call(frame) = trace_call(Tracer::Call) else {
+				panic!("the call tracer returns a call frame");
+			};
+			assert_eq!(frame.call_type, CallType::Call);
+			assert_eq!((frame.from, frame.to), (ALITH, CONTRACT));
+			assert_eq!(frame.gas, 100_000);
+			assert!(frame.gas_used > 0);
+			assert_eq!(frame.error, None);
+			assert!(frame.calls.is_empty());
+		})
+	}
+
+	#[test]
+	fn raw_tracer_records_every_opcode() {
+		new_test_ext().execute_with(|| {
+			let tracer =
+				Tracer::Raw { disable_stack: false, disable_memory: true, disable_storage: false };
+			let Trace::Raw(trace) = trace_call(tracer) else {
+				panic!("the raw tracer returns a raw trace");
+			};
+			assert!(!trace.failed);
+			let ops: Vec<_> = trace.struct_logs.iter().map(|log| log.op).collect();
+			assert_eq!(ops, [0x60, 0x60, 0x55, 0x00]);
+			let sstore = &trace.struct_logs[2];
+			assert_eq!(sstore.stack.as_ref().map(Vec::len), Some(2));
+			let slot = (H256::zero(), H256::from_low_u64_be(0x2a));
+			assert_eq!(sstore.storage, Some(vec![slot]));
+		})
+	}
+
+	#[test]
+	fn prestate_tracer_reads_the_state_before_the_call() {
+		new_test_ext().execute_with(|| {
+			let Trace::Prestate(accounts) = trace_call(Tracer::Prestate) else {
+				panic!("the prestate tracer returns accounts");
+			};
+			let contract = accounts
+				.iter()
+				.find(|account| account.address == CONTRACT)
+				.expect("the contract is touched");
+			assert_eq!(contract.code, CODE);
+			assert_eq!(contract.storage, vec![(H256::zero(), H256::zero())]);
+			assert!(accounts.iter().any(|account| account.address == ALITH));
+			// The call is applied once the prestate is read.
+			let stored = pallet_evm::AccountStorages::<Runtime>::get(CONTRACT, H256::zero());
+			assert_eq!(stored, H256::from_low_u64_be(0x2a));
+		})
+	}
+}
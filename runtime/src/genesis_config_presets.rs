@@ -1,22 +1,215 @@
 use crate::{
-	AccountId, BalancesConfig, CollatorSelectionConfig, ParachainInfoConfig, PolkadotXcmConfig,
-	RuntimeGenesisConfig, SessionConfig, SessionKeys, SudoConfig, EXISTENTIAL_DEPOSIT,
+	AccountId, AccountMappingConfig, AssetsConfig, Balance, BalancesConfig,
+	CollatorSelectionConfig, CouncilConfig, EVMChainIdConfig, EVMConfig,
+	ParachainInfoConfig, PolkadotXcmConfig, Runtime, RuntimeGenesisConfig, SessionConfig,
+	SessionKeys, SudoConfig, TechnicalCommitteeConfig, EXISTENTIAL_DEPOSIT, UNIT,
 };
 
-use alloc::{vec, vec::Vec};
+use alloc::{
+	collections::{BTreeMap, BTreeSet},
+	format,
+	string::String,
+	vec,
+	vec::Vec,
+};
 
 use polkadot_sdk::{staging_xcm as xcm, *};
 
 use cumulus_primitives_core::ParaId;
 use parachains_common::AuraId;
+use serde::Deserialize;
 use serde_json::Value;
 use sp_genesis_builder::PresetId;
+use sp_core::{H160, U256};
 use sp_keyring::Sr25519Keyring;
 
+mod system_contracts;
+
+pub use system_contracts::SystemContract;
+
 /// The default XCM version to set in genesis config.
 const SAFE_XCM_VERSION: u32 = xcm::prelude::XCM_VERSION;
 /// Parachain id used for gensis config presets of parachain template.
 const PARACHAIN_ID: u32 = 1000;
+/// EVM chain id of the development and local testnet presets.
+const DEV_EVM_CHAIN_ID: u64 = 1000;
+
+/// Preset of the staging network, on Paseo.
+pub const STAGING_RUNTIME_PRESET: &str = "fintradex-staging";
+/// Preset of the Kusama network.
+pub const KUSAMA_RUNTIME_PRESET: &str = "fintradex-kusama";
+/// Preset of the Polkadot network.
+pub const POLKADOT_RUNTIME_PRESET: &str = "fintradex-polkadot";
+
+/// The networks of `runtime/presets` and their file, each checked by the `network_files_validate`
+/// test.
+const NETWORKS: [(&str, &str); 1] =
+	[(STAGING_RUNTIME_PRESET, include_str!("../presets/fintradex-staging.json"))];
+
+/// The networks not launched yet, without a file until their para id is registered and their
+/// collators and governance are known.
+const PENDING_NETWORKS: [&str; 2] = [KUSAMA_RUNTIME_PRESET, POLKADOT_RUNTIME_PRESET];
+
+/// A collator of a network, invulnerable from genesis.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Collator {
+	pub account: AccountId,
+	pub aura: AuraId,
+}
+
+/// An asset of `pallet-assets` created at genesis.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Asset {
+	pub id: u32,
+	pub owner: AccountId,
+	pub name: String,
+	pub symbol: String,
+	pub decimals: u8,
+	/// Minimum balance of an account, in the smallest unit of the asset.
+	pub min_balance: Balance,
+	pub is_sufficient: bool,
+	/// Initial holders, in the smallest unit of the asset.
+	#[serde(default)]
+	pub balances: Vec<(AccountId, Balance)>,
+}
+
+/// A network launched from a file of `runtime/presets`.
+///
+/// Accounts and keys are SS58 addresses; native balances are whole units.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NetworkConfig {
+	pub name: String,
+	/// The relay chain, for the chain spec extensions.
+	pub relay_chain: String,
+	/// The para id registered on the relay chain.
+	pub para_id: u32,
+	pub evm_chain_id: u64,
+	pub collators: Vec<Collator>,
+	#[serde(default)]
+	pub endowments: Vec<(AccountId, u64)>,
+	/// Initial balance of the treasury account.
+	#[serde(default)]
+	pub treasury: u64,
+	#[serde(default)]
+	pub council: Vec<AccountId>,
+	#[serde(default)]
+	pub technical_committee: Vec<AccountId>,
+	#[serde(default)]
+	pub sudo: Option<AccountId>,
+	#[serde(default)]
+	pub assets: Vec<Asset>,
+	/// Contracts deployed in the EVM at their canonical addresses.
+	#[serde(default)]
+	pub system_contracts: Vec<SystemContract>,
+}
+
+/// Whether all of `items` are distinct.
+fn distinct<T: Ord>(items: impl IntoIterator<Item = T>) -> bool {
+	let mut seen = BTreeSet::new();
+	items.into_iter().all(|item| seen.insert(item))
+}
+
+impl NetworkConfig {
+	/// Check that the network can produce blocks.
+	pub fn validate(&self) -> Result<(), String> {
+		if self.para_id < 2000 {
+			return Err(format!("para id {} is reserved for system parachains", self.para_id));
+		}
+		if self.evm_chain_id == 0 {
+			return Err("the EVM chain id is not set".into());
+		}
+		if self.collators.is_empty() {
+			return Err("no collators".into());
+		}
+		if !distinct(self.collators.iter().map(|c| &c.account)) ||
+			!distinct(self.collators.iter().map(|c| &c.aura))
+		{
+			return Err("a collator account or Aura key is listed twice".into());
+		}
+		if !distinct(self.endowments.iter().map(|(account, _)| account)) {
+			return Err("an account is endowed twice".into());
+		}
+		if !distinct(&self.council) || !distinct(&self.technical_committee) {
+			return Err("a governance member is listed twice".into());
+		}
+		if self.council.is_empty() && self.sudo.is_none() {
+			return Err("neither a council nor a sudo key governs the network".into());
+		}
+		if !distinct(self.assets.iter().map(|asset| asset.id)) {
+			return Err("an asset id is listed twice".into());
+		}
+		for asset in &self.assets {
+			if asset.name.is_empty() || asset.symbol.is_empty() {
+				return Err(format!("asset {} has no name or symbol", asset.id));
+			}
+			if asset.min_balance == 0 {
+				return Err(format!("asset {} has a zero minimum balance", asset.id));
+			}
+			if asset.balances.iter().any(|(_, balance)| *balance < asset.min_balance) {
+				return Err(format!("asset {} is held below its minimum balance", asset.id));
+			}
+		}
+		self.validate_system_contracts()
+	}
+
+	/// Check that the network is not run by well-known development keys.
+	pub fn validate_dev_keys(&self) -> Result<(), String> {
+		let dev_accounts: BTreeSet<_> =
+			Sr25519Keyring::iter().map(|keyring| keyring.to_account_id()).collect();
+		let dev_keys: BTreeSet<AuraId> =
+			Sr25519Keyring::iter().map(|keyring| keyring.public().into()).collect();
+		let privileged = self
+			.collators
+			.iter()
+			.map(|c| &c.account)
+			.chain(&self.council)
+			.chain(&self.technical_committee)
+			.chain(&self.sudo);
+		if privileged.any(|account| dev_accounts.contains(account)) ||
+			self.collators.iter().any(|c| dev_keys.contains(&c.aura))
+		{
+			return Err("a well-known development key is a collator or governs the network".into());
+		}
+		Ok(())
+	}
+
+	/// Check that the bytecode of the system contracts is vendored.
+	pub fn validate_system_contracts(&self) -> Result<(), String> {
+		if !distinct(&self.system_contracts) {
+			return Err("a system contract is listed twice".into());
+		}
+		self.system_contracts.iter().try_for_each(|contract| contract.code().map(|_| ()))
+	}
+}
+
+/// The file of `runtime/presets` describing the network of the preset `id`.
+fn network_file(id: &str) -> Option<&'static str> {
+	NETWORKS.iter().find(|(network, _)| *network == id).map(|(_, file)| *file)
+}
+
+/// The validated network of the preset `id`, if it is one of `runtime/presets` or a network not
+/// launched yet, which is an error.
+pub fn network_config(id: &str) -> Option<Result<NetworkConfig, String>> {
+	if PENDING_NETWORKS.contains(&id) {
+		return Some(Err(format!(
+			"{id} is not launched yet: runtime/presets/{id}.json, with its para id, collators and \
+			 governance, is missing"
+		)));
+	}
+	let config = serde_json::from_str::<NetworkConfig>(network_file(id)?)
+		.map_err(|e| format!("{id}: {e}"))
+		.and_then(|config| {
+			config
+				.validate()
+				.and_then(|()| config.validate_dev_keys())
+				.map(|()| config)
+				.map_err(|e| format!("{id}: {e}"))
+		});
+	Some(config)
+}
 
 /// Generate the session keys from individual elements.
 ///
@@ -25,12 +218,53 @@ pub fn template_session_keys(keys: AuraId) -> SessionKeys {
 	SessionKeys { aura: keys }
 }
 
+/// EVM accounts of the development and local testnet presets.
+fn testnet_evm_accounts() -> BTreeMap<H160, fp_evm::GenesisAccount> {
+	let account = |balance| fp_evm::GenesisAccount {
+		balance,
+		code: Default::default(),
+		nonce: Default::default(),
+		storage: Default::default(),
+	};
+	BTreeMap::from([
+		// The first 20 bytes of Alice's sr25519 key, funded for tools deriving it that way.
+		(
+			H160(hex_literal::hex!("d43593c715fdd31c61141abd04a99fd6822c8558")),
+			account(U256::from(u128::MAX)),
+		),
+		// CI test runner.
+		(
+			H160(hex_literal::hex!("6be02d1d3665660d22ff9624b7be0551ee1ac91b")),
+			account(U256::from(u128::MAX)),
+		),
+		// Benchmarks.
+		(
+			H160(hex_literal::hex!("1000000000000000000000000000000000000001")),
+			fp_evm::GenesisAccount {
+				nonce: U256::from(1),
+				balance: U256::from(1_000_000_000_000_000_000_000_000u128),
+				storage: Default::default(),
+				code: vec![0x00],
+			},
+		),
+		// Dev account, private key
+		// 0xb9d2ea9a615f3165812e8d44de0d24da9bbd164b65c4f0573e1ce2c8dbd9c8df.
+		(
+			H160(hex_literal::hex!("C0F0f4ab324C46e55D02D0033343B4Be8A55532d")),
+			account(U256::from(0xef000000000000000000000000000u128)),
+		),
+	])
+}
+
 fn testnet_genesis(
 	invulnerables: Vec<(AccountId, AuraId)>,
 	endowed_accounts: Vec<AccountId>,
 	root: AccountId,
 	id: ParaId,
 ) -> Value {
+	let alice = Sr25519Keyring::Alice.to_account_id();
+	let bob = Sr25519Keyring::Bob.to_account_id();
+
 	let config = RuntimeGenesisConfig {
 		balances: BalancesConfig {
 			balances: endowed_accounts
@@ -64,6 +298,41 @@ fn testnet_genesis(
 			..Default::default()
 		},
 		sudo: SudoConfig { key: Some(root) },
+		// Assets "ALT1" and "ALT2", owned by Alice and Bob.
+		assets: AssetsConfig {
+			assets: vec![
+				(1, alice.clone(), true, 10_000_000_0000),
+				(2, bob.clone(), true, 10_000_000_0000),
+			],
+			metadata: vec![
+				(1, "asset-1".into(), "ALT1".into(), 10),
+				(2, "asset-2".into(), "ALT2".into(), 10),
+			],
+			accounts: vec![(1, alice.clone(), 50_000_000_0000), (2, bob, 50_000_000_0000)],
+			..Default::default()
+		},
+		council: CouncilConfig {
+			members: endowed_accounts.iter().step_by(2).cloned().collect(),
+			..Default::default()
+		},
+		evm_chain_id: EVMChainIdConfig { chain_id: DEV_EVM_CHAIN_ID, ..Default::default() },
+		// Alice's native account is also reachable from the EVM through the well-known dev key
+		// Alith (private key 0x5fb92d6e98884f76de468fa3f6278f8807c48bebc13595d45af5bdc4da702133).
+		account_mapping: AccountMappingConfig {
+			mappings: vec![(
+				alice,
+				H160(hex_literal::hex!("f24ff3a9cf04c71dbc94d0b566f7a27b94566cac")),
+			)],
+		},
+		evm: EVMConfig {
+			accounts: {
+				let mut accounts = system_contracts::genesis_accounts(&SystemContract::ALL)
+					.expect("vendored contracts are valid; qed");
+				accounts.extend(testnet_evm_accounts());
+				accounts
+			},
+			..Default::default()
+		},
 		..Default::default()
 	};
 
@@ -77,7 +346,7 @@ fn local_testnet_genesis() -> Value {
 			(Sr25519Keyring::Alice.to_account_id(), Sr25519Keyring::Alice.public().into()),
 			(Sr25519Keyring::Bob.to_account_id(), Sr25519Keyring::Bob.public().into()),
 		],
-		Sr25519Keyring::well_known().map(|k| k.to_account_id()).collect(),
+		Sr25519Keyring::well_known().map(|keyring| keyring.to_account_id()).collect(),
 		Sr25519Keyring::Alice.to_account_id(),
 		PARACHAIN_ID.into(),
 	)
@@ -90,18 +359,97 @@ fn development_config_genesis() -> Value {
 			(Sr25519Keyring::Alice.to_account_id(), Sr25519Keyring::Alice.public().into()),
 			(Sr25519Keyring::Bob.to_account_id(), Sr25519Keyring::Bob.public().into()),
 		],
-		Sr25519Keyring::well_known().map(|k| k.to_account_id()).collect(),
+		Sr25519Keyring::well_known().map(|keyring| keyring.to_account_id()).collect(),
 		Sr25519Keyring::Alice.to_account_id(),
 		PARACHAIN_ID.into(),
 	)
 }
 
+/// Genesis config of `network`, validated or not.
+pub fn network_genesis(network: NetworkConfig) -> Value {
+	let mut balances: Vec<_> = network
+		.endowments
+		.into_iter()
+		.map(|(account, units)| (account, Balance::from(units) * UNIT))
+		.collect();
+	if network.treasury > 0 {
+		balances.push((
+			pallet_treasury::Pallet::<Runtime>::account_id(),
+			Balance::from(network.treasury) * UNIT,
+		));
+	}
+
+	let config = RuntimeGenesisConfig {
+		balances: BalancesConfig { balances, dev_accounts: None },
+		parachain_info: ParachainInfoConfig {
+			parachain_id: network.para_id.into(),
+			..Default::default()
+		},
+		collator_selection: CollatorSelectionConfig {
+			invulnerables: network.collators.iter().map(|c| c.account.clone()).collect(),
+			candidacy_bond: EXISTENTIAL_DEPOSIT * 16,
+			..Default::default()
+		},
+		session: SessionConfig {
+			keys: network
+				.collators
+				.into_iter()
+				.map(|c| (c.account.clone(), c.account, template_session_keys(c.aura)))
+				.collect(),
+			..Default::default()
+		},
+		polkadot_xcm: PolkadotXcmConfig {
+			safe_xcm_version: Some(SAFE_XCM_VERSION),
+			..Default::default()
+		},
+		sudo: SudoConfig { key: network.sudo },
+		council: CouncilConfig { members: network.council, ..Default::default() },
+		technical_committee: TechnicalCommitteeConfig {
+			members: network.technical_committee,
+			..Default::default()
+		},
+		evm_chain_id: EVMChainIdConfig { chain_id: network.evm_chain_id, ..Default::default() },
+		evm: EVMConfig {
+			accounts: system_contracts::genesis_accounts(&network.system_contracts)
+				.unwrap_or_else(|e| panic!("{e}")),
+			..Default::default()
+		},
+		assets: AssetsConfig {
+			assets: network
+				.assets
+				.iter()
+				.map(|a| (a.id, a.owner.clone(), a.is_sufficient, a.min_balance))
+				.collect(),
+			metadata: network
+				.assets
+				.iter()
+				.map(|a| (a.id, a.name.clone().into(), a.symbol.clone().into(), a.decimals))
+				.collect(),
+			accounts: network
+				.assets
+				.into_iter()
+				.flat_map(|a| {
+					let id = a.id;
+					a.balances.into_iter().map(move |(who, balance)| (id, who, balance))
+				})
+				.collect(),
+			..Default::default()
+		},
+		..Default::default()
+	};
+
+	serde_json::to_value(config).expect("Could not build genesis config.")
+}
+
 /// Provides the JSON representation of predefined genesis config for given `id`.
 pub fn get_preset(id: &PresetId) -> Option<vec::Vec<u8>> {
 	let patch = match id.as_ref() {
 		sp_genesis_builder::LOCAL_TESTNET_RUNTIME_PRESET => local_testnet_genesis(),
 		sp_genesis_builder::DEV_RUNTIME_PRESET => development_config_genesis(),
-		_ => return None,
+		id if PENDING_NETWORKS.contains(&id) => return None,
+		id => network_genesis(
+			network_config(id)?.expect("checked by the `network_files_validate` test; qed"),
+		),
 	};
 	Some(
 		serde_json::to_string(&patch)
@@ -112,8 +460,102 @@ pub fn get_preset(id: &PresetId) -> Option<vec::Vec<u8>> {
 
 /// List of supported presets.
 pub fn preset_names() -> Vec<PresetId> {
-	vec![
-		PresetId::from(sp_genesis_builder::DEV_RUNTIME_PRESET),
-		PresetId::from(sp_genesis_builder::LOCAL_TESTNET_RUNTIME_PRESET),
-	]
+	[sp_genesis_builder::DEV_RUNTIME_PRESET, sp_genesis_builder::LOCAL_TESTNET_RUNTIME_PRESET]
+		.into_iter()
+		.chain(NETWORKS.iter().map(|(id, _)| *id))
+		.map(PresetId::from)
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn network(collators: Vec<Collator>) -> NetworkConfig {
+		NetworkConfig {
+			name: "Test".into(),
+			relay_chain: "paseo".into(),
+			para_id: 2000,
+			evm_chain_id: 7331,
+			collators,
+			endowments: vec![],
+			treasury: 0,
+			council: vec![],
+			technical_committee: vec![],
+			sudo: Some(AccountId::from([1; 32])),
+			assets: vec![],
+			system_contracts: vec![],
+		}
+	}
+
+	#[test]
+	fn network_files_validate() {
+		let chain_ids: Vec<_> = NETWORKS
+			.iter()
+			.map(|(id, _)| network_config(id).unwrap().unwrap_or_else(|e| panic!("{e}")))
+			.map(|network| network.evm_chain_id)
+			.collect();
+		assert!(distinct(chain_ids), "networks share an EVM chain id");
+		for (id, _) in NETWORKS {
+			assert!(get_preset(&PresetId::from(id)).is_some(), "{id}");
+		}
+		assert_eq!(preset_names().len(), 2 + NETWORKS.len());
+	}
+
+	#[test]
+	fn pending_networks_are_not_presets() {
+		for id in PENDING_NETWORKS {
+			let e = network_config(id).unwrap().err().unwrap();
+			assert!(e.contains("is not launched yet"), "{e}");
+			assert!(get_preset(&PresetId::from(id)).is_none());
+			assert!(!preset_names().contains(&PresetId::from(id)));
+		}
+	}
+
+	#[test]
+	fn staging_creates_its_assets() {
+		let network = network_config(STAGING_RUNTIME_PRESET).unwrap().unwrap();
+		assert!(!network.assets.is_empty());
+		assert!(network.assets.iter().all(|asset| !asset.balances.is_empty()));
+	}
+
+	#[test]
+	fn dev_keys_are_rejected() {
+		let alice = Collator {
+			account: Sr25519Keyring::Alice.to_account_id(),
+			aura: Sr25519Keyring::Alice.public().into(),
+		};
+		let run_by_alice = network(vec![alice]);
+		assert_eq!(run_by_alice.validate(), Ok(()));
+		assert!(run_by_alice.validate_dev_keys().is_err());
+		assert!(network(vec![]).validate().is_err());
+
+		let collator = Collator {
+			account: AccountId::from([2; 32]),
+			aura: sp_core::sr25519::Public::from_raw([2; 32]).into(),
+		};
+		let network = network(vec![collator]);
+		assert_eq!(network.validate(), Ok(()));
+		assert_eq!(network.validate_dev_keys(), Ok(()));
+	}
+
+	#[test]
+	fn system_contracts_of_the_presets_are_vendored() {
+		for (id, file) in NETWORKS {
+			let network = serde_json::from_str::<NetworkConfig>(file).unwrap();
+			for contract in network.system_contracts {
+				let code = contract.code().unwrap_or_else(|e| panic!("{id}: {e}"));
+				assert!(!code.is_empty(), "{id}: {contract:?}");
+			}
+		}
+		for contract in SystemContract::ALL {
+			let code = contract.code().unwrap_or_else(|e| panic!("{e}"));
+			assert!(!code.is_empty(), "{contract:?}");
+		}
+		assert_eq!(
+			SystemContract::Create2Deployer.code().unwrap()[..2],
+			[0x7f, 0xff],
+			"PUSH32 of the deterministic deployment proxy"
+		);
+	}
 }
@@ -0,0 +1,161 @@
+//! Contracts deployed in the EVM at genesis, at the addresses tooling expects on every chain.
+//!
+//! Their runtime bytecode is vendored in `runtime/presets/contracts` by
+//! `scripts/fetch-system-contracts.sh`, from the contracts deployed on Ethereum, with the code hash
+//! of their Ethereum account, which the bytecode must match to be deployed.
+
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+
+use serde::Deserialize;
+use sp_core::{hashing::keccak_256, H160, H256, U256};
+
+/// Name of the wrapped native token.
+const WRAPPED_NATIVE_NAME: &str = "Wrapped Fintra";
+/// Symbol of the wrapped native token.
+const WRAPPED_NATIVE_SYMBOL: &str = "WFINTRA";
+/// Decimals of the native token, as seen by the EVM.
+const NATIVE_DECIMALS: u8 = 12;
+
+/// A contract deployed at genesis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SystemContract {
+	/// Multicall3, batching calls.
+	Multicall3,
+	/// The deterministic deployment proxy, deploying contracts with CREATE2.
+	Create2Deployer,
+	/// Permit2, signature-based token approvals.
+	Permit2,
+	/// WETH9, wrapping the native token into an ERC-20.
+	WrappedNative,
+}
+
+impl SystemContract {
+	pub const ALL: [Self; 4] =
+		[Self::Multicall3, Self::Create2Deployer, Self::Permit2, Self::WrappedNative];
+
+	pub fn address(self) -> H160 {
+		H160(match self {
+			Self::Multicall3 => hex_literal::hex!("cA11bde05977b3631167028862bE2a173976CA11"),
+			Self::Create2Deployer => hex_literal::hex!("4e59b44847b379578588920cA78FbF26c0B4956C"),
+			Self::Permit2 => hex_literal::hex!("000000000022D473030F116dDEE9F6B43aC78BA3"),
+			// No address is canonical across chains, this one is fixed on every network.
+			Self::WrappedNative => hex_literal::hex!("F100000000000000000000000000000000000001"),
+		})
+	}
+
+	/// The hex-encoded runtime bytecode, empty while not vendored.
+	fn artifact(self) -> &'static str {
+		match self {
+			Self::Multicall3 => include_str!("../../presets/contracts/multicall3.hex"),
+			Self::Create2Deployer => include_str!("../../presets/contracts/create2-deployer.hex"),
+			Self::Permit2 => include_str!("../../presets/contracts/permit2.hex"),
+			Self::WrappedNative => include_str!("../../presets/contracts/weth9.hex"),
+		}
+		.trim()
+	}
+
+	/// The hex-encoded code hash of the contract on Ethereum, empty while not vendored.
+	fn code_hash_artifact(self) -> &'static str {
+		match self {
+			Self::Multicall3 => include_str!("../../presets/contracts/multicall3.codehash"),
+			Self::Create2Deployer =>
+				include_str!("../../presets/contracts/create2-deployer.codehash"),
+			Self::Permit2 => include_str!("../../presets/contracts/permit2.codehash"),
+			Self::WrappedNative => include_str!("../../presets/contracts/weth9.codehash"),
+		}
+		.trim()
+	}
+
+	pub fn code(self) -> Result<Vec<u8>, String> {
+		let artifact = self.artifact();
+		if artifact.is_empty() {
+			return Err(format!(
+				"the bytecode of {self:?} is not vendored, run scripts/fetch-system-contracts.sh"
+			));
+		}
+		let code = sp_core::bytes::from_hex(artifact)
+			.map_err(|e| format!("invalid bytecode of {self:?}: {e}"))?;
+		self.check_code_hash(&code)?;
+		Ok(code)
+	}
+
+	/// Check that `code` is the bytecode of the contract on Ethereum.
+	fn check_code_hash(self, code: &[u8]) -> Result<(), String> {
+		let expected = sp_core::bytes::from_hex(self.code_hash_artifact())
+			.ok()
+			.filter(|hash| hash.len() == 32)
+			.ok_or_else(|| {
+				format!(
+					"the code hash of {self:?} is not vendored, run \
+					 scripts/fetch-system-contracts.sh"
+				)
+			})?;
+		if keccak_256(code)[..] != expected[..] {
+			return Err(format!("the bytecode of {self:?} does not match its code hash"));
+		}
+		Ok(())
+	}
+
+	/// Storage a constructor would have written, as the runtime bytecode is deployed as is.
+	fn storage(self) -> BTreeMap<H256, H256> {
+		match self {
+			// WETH9 keeps `name`, `symbol` and `decimals` in its first slots.
+			Self::WrappedNative => BTreeMap::from([
+				(H256::from_low_u64_be(0), short_string(WRAPPED_NATIVE_NAME)),
+				(H256::from_low_u64_be(1), short_string(WRAPPED_NATIVE_SYMBOL)),
+				(H256::from_low_u64_be(2), H256::from_low_u64_be(NATIVE_DECIMALS.into())),
+			]),
+			_ => BTreeMap::new(),
+		}
+	}
+
+	pub fn genesis_account(self) -> Result<fp_evm::GenesisAccount, String> {
+		Ok(fp_evm::GenesisAccount {
+			nonce: U256::one(),
+			balance: U256::zero(),
+			storage: self.storage(),
+			code: self.code()?,
+		})
+	}
+}
+
+/// A Solidity string shorter than 32 bytes, as stored in its slot.
+fn short_string(s: &str) -> H256 {
+	let mut slot = [0; 32];
+	slot[..s.len()].copy_from_slice(s.as_bytes());
+	slot[31] = s.len() as u8 * 2;
+	H256(slot)
+}
+
+/// The EVM accounts of `contracts`.
+pub fn genesis_accounts(
+	contracts: &[SystemContract],
+) -> Result<BTreeMap<H160, fp_evm::GenesisAccount>, String> {
+	contracts
+		.iter()
+		.map(|contract| Ok((contract.address(), contract.genesis_account()?)))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn bytecode_must_match_the_code_hash() {
+		let code = SystemContract::Create2Deployer.code().unwrap();
+		let mut tampered = code.clone();
+		tampered[0] = 0x00;
+		assert!(SystemContract::Create2Deployer.check_code_hash(&tampered).is_err());
+		assert!(SystemContract::Multicall3.check_code_hash(&code).is_err());
+	}
+
+	#[test]
+	fn wrapped_native_metadata_is_stored() {
+		let storage = SystemContract::WrappedNative.storage();
+		let name = storage[&H256::from_low_u64_be(0)];
+		assert_eq!(&name[..14], b"Wrapped Fintra");
+		assert_eq!(name[31], 28);
+	}
+}
@@ -11,8 +11,10 @@ pub mod apis;
 mod benchmarks;
 pub mod configs;
 pub mod constants;
+pub mod evm_tracing;
 pub mod precompiles;
-mod genesis_config_presets;
+pub mod simulate;
+pub mod genesis_config_presets;
 mod weights;
 mod voter_bags;
 
@@ -28,7 +30,7 @@ use sp_runtime::{
 	generic, impl_opaque_keys,
 	traits::{BlakeTwo256, Block as BlockT, IdentifyAccount, Verify,NumberFor,DispatchInfoOf,PostDispatchInfoOf},
 	transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity,TransactionValidityError},
-	ApplyExtrinsicResult, MultiSignature,MultiAddress
+	ApplyExtrinsicResult,
 };
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata,H160,U256,H256,Get};
 #[cfg(feature = "std")]
@@ -54,7 +56,7 @@ pub use weights::*;
 //use weights::ExtrinsicBaseWeight;
 
 /// Alias to 512-bit hash when used in the context of a transaction signature on the chain.
-pub type Signature = MultiSignature;
+pub type Signature = sp_runtime::MultiSignature;
 
 /// Some way of identifying an account on the chain. We intentionally make it equivalent
 /// to the public key of our transaction signing scheme.
@@ -73,7 +75,7 @@ pub type Hash = sp_core::H256;
 pub type BlockNumber = u32;
 
 /// The address format for describing accounts.
-pub type Address = MultiAddress<AccountId, ()>;
+pub type Address = sp_runtime::MultiAddress<AccountId, ()>;
 
 /// Block header type as expected by this runtime.
 pub type Header = generic::Header<BlockNumber, BlakeTwo256>;
@@ -296,12 +298,14 @@ mod block_times {
 	/// `pallet_timestamp` which is in turn picked up by `pallet_aura` to implement `fn
 	/// slot_duration()`.
 	///
-	/// Change this to adjust the block time.
-	pub const MILLI_SECS_PER_BLOCK: u64 = 6000;
+	/// With elastic scaling, one block is authored per core in each slot: change `CORES` to
+	/// adjust the block time.
+	pub const MILLI_SECS_PER_BLOCK: u64 = SLOT_DURATION / super::CORES as u64;
 
 	// NOTE: Currently it is not possible to change the slot duration after the chain has started.
-	// Attempting to do so will brick block production.
-	pub const SLOT_DURATION: u64 = MILLI_SECS_PER_BLOCK;
+	// Attempting to do so will brick block production. It stays the relay chain slot whatever
+	// the number of cores.
+	pub const SLOT_DURATION: u64 = super::RELAY_CHAIN_SLOT_DURATION_MILLIS as u64;
 }
 pub use block_times::*;
 
@@ -363,7 +367,8 @@ const AVERAGE_ON_INITIALIZE_RATIO: Perbill = Perbill::from_percent(5);
 const NORMAL_DISPATCH_RATIO: Perbill = Perbill::from_percent(75);
 
 #[docify::export(max_block_weight)]
-/// We allow for 2 seconds of compute with a 6 second average block time.
+/// We allow for 2 seconds of compute per block, every block being validated on a core of its
+/// own.
 const MAXIMUM_BLOCK_WEIGHT: Weight = Weight::from_parts(
 	WEIGHT_REF_TIME_PER_SECOND.saturating_mul(2),
 	cumulus_primitives_core::relay_chain::MAX_POV_SIZE as u64,
@@ -371,16 +376,25 @@ const MAXIMUM_BLOCK_WEIGHT: Weight = Weight::from_parts(
 
 #[docify::export]
 mod async_backing_params {
-	/// Maximum number of blocks simultaneously accepted by the Runtime, not yet included
-	/// into the relay chain.
-	pub(crate) const UNINCLUDED_SEGMENT_CAPACITY: u32 = 3;
+	/// Number of relay chain cores the parachain may use, and so of blocks it authors per slot
+	/// (elastic scaling). The collators author one block per core actually assigned. The slot
+	/// duration does not depend on it, so a runtime upgrade can change it.
+	pub const CORES: u32 = 3;
+	/// Number of relay chain blocks the collators build behind the relay chain tip, so that
+	/// parachain blocks survive short relay chain forks.
+	pub const RELAY_PARENT_OFFSET: u32 = 1;
 	/// How many parachain blocks are processed by the relay chain per parent. Limits the
-	/// number of blocks authored per slot.
-	pub(crate) const BLOCK_PROCESSING_VELOCITY: u32 = 1;
+	/// number of blocks authored per slot, one per core.
+	pub(crate) const BLOCK_PROCESSING_VELOCITY: u32 = CORES;
+	/// Maximum number of blocks simultaneously accepted by the Runtime, not yet included
+	/// into the relay chain: the blocks of the relay parents between the offset one and the tip,
+	/// of the tip, and of the one being backed, plus the block being built.
+	pub(crate) const UNINCLUDED_SEGMENT_CAPACITY: u32 =
+		(2 + RELAY_PARENT_OFFSET) * BLOCK_PROCESSING_VELOCITY + 1;
 	/// Relay chain slot duration, in milliseconds.
-	pub(crate) const RELAY_CHAIN_SLOT_DURATION_MILLIS: u32 = 6000;
+	pub const RELAY_CHAIN_SLOT_DURATION_MILLIS: u32 = 6000;
 }
-pub(crate) use async_backing_params::*;
+pub use async_backing_params::*;
 
 #[docify::export]
 /// Aura consensus hook
@@ -391,6 +405,10 @@ type ConsensusHook = cumulus_pallet_aura_ext::FixedVelocityConsensusHook<
 	UNINCLUDED_SEGMENT_CAPACITY,
 >;
 
+/// Core selector of `cumulus_pallet_parachain_system`: the blocks of a relay parent go to the
+/// cores assigned at the next relay block, the one they are backed in.
+type SelectCore = cumulus_pallet_parachain_system::LookaheadCoreSelector<Runtime>;
+
 /// The version information used to identify this runtime when compiled natively.
 #[cfg(feature = "std")]
 pub fn native_version() -> NativeVersion {
@@ -568,6 +586,95 @@ mod runtime {
 	#[runtime::pallet_index(57)]
 	pub type Parameters = pallet_parameters::Pallet<Runtime>;
 
+	#[runtime::pallet_index(58)]
+	pub type AccountMapping = pallet_account_mapping::Pallet<Runtime>;
+
+}
+
+// `pallet_evm::Config::AddressMapping`, in `configs`, is
+// `pallet_account_mapping::UnifiedAddressMapping<Self>`: bound EVM addresses act as their native
+// account and the others fall back to `DefaultAddressMapping`.
+impl pallet_account_mapping::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	type DefaultAddressMapping = pallet_evm::HashedAddressMapping<BlakeTwo256>;
+	type ChainId = EVMChainId;
+	type WeightInfo = pallet_account_mapping::SubstrateWeight<Runtime>;
+}
+
+/// Externalities holding the genesis state of the development preset.
+#[cfg(test)]
+pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
+	let mut ext = sp_io::TestExternalities::default();
+	ext.execute_with(|| {
+		let preset = sp_genesis_builder::DEV_RUNTIME_PRESET.into();
+		let preset = genesis_config_presets::get_preset(&preset)
+			.expect("the development preset exists; qed");
+		frame_support::genesis_builder_helper::build_state::<RuntimeGenesisConfig>(preset)
+			.expect("the development genesis is valid; qed");
+	});
+	ext
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::traits::fungible::Inspect;
+	use pallet_evm::{AddressMapping, FeeCalculator, HashedAddressMapping, Runner};
+	use sp_keyring::Sr25519Keyring;
+
+	/// The well-known dev key Alith, bound to Alice at genesis.
+	const ALITH: H160 = H160(hex_literal::hex!("f24ff3a9cf04c71dbc94d0b566f7a27b94566cac"));
+
+	fn account(address: H160) -> AccountId {
+		<Runtime as pallet_evm::Config>::AddressMapping::into_account_id(address)
+	}
+
+	#[test]
+	fn evm_calls_debit_the_mapped_native_account() {
+		new_test_ext().execute_with(|| {
+			let alice = Sr25519Keyring::Alice.to_account_id();
+			assert_eq!(account(ALITH), alice);
+			let receiver = H160::repeat_byte(0x42);
+			let before = Balances::balance(&alice);
+			let (base_fee, _) = BaseFee::min_gas_price();
+
+			let info = <Runtime as pallet_evm::Config>::Runner::call(
+				ALITH,
+				receiver,
+				vec![],
+				U256::from(UNIT),
+				21_000,
+				Some(base_fee),
+				None,
+				None,
+				vec![],
+				true,
+				true,
+				None,
+				None,
+				<Runtime as pallet_evm::Config>::config(),
+			)
+			.expect("the transfer is valid");
+			assert!(info.exit_reason.is_succeed());
+
+			let fee = (base_fee * info.used_gas.effective).low_u128();
+			assert_eq!(Balances::balance(&alice), before - UNIT - fee);
+			assert_eq!(Balances::balance(&account(receiver)), UNIT);
+			// The implicit account of Alith is not used once it is bound.
+			let implicit = HashedAddressMapping::<BlakeTwo256>::into_account_id(ALITH);
+			assert_eq!(Balances::balance(&implicit), 0);
+		})
+	}
+
+	#[test]
+	fn unbound_addresses_use_their_hashed_account() {
+		new_test_ext().execute_with(|| {
+			let address = H160::repeat_byte(0x42);
+			let hashed = HashedAddressMapping::<BlakeTwo256>::into_account_id(address);
+			assert_eq!(account(address), hashed);
+		})
+	}
 }
 
 #[docify::export(register_validate_block)]
@@ -1,37 +1,100 @@
 use core::marker::PhantomData;
+use frame_support::{
+	dispatch::{GetDispatchInfo, PostDispatchInfo},
+	traits::fungible::NativeOrWithId,
+};
 use pallet_evm::{
-	IsPrecompileResult, Precompile, PrecompileHandle, PrecompileResult, PrecompileSet,
+	IsPrecompileResult, Precompile, PrecompileFailure, PrecompileHandle, PrecompileResult,
+	PrecompileSet,
 };
 use sp_core::H160;
+use sp_runtime::traits::Dispatchable;
 
+use pallet_evm_precompile_blake2::Blake2F;
+use pallet_evm_precompile_bn128::{Bn128Add, Bn128Mul, Bn128Pairing};
+use pallet_evm_precompile_dispatch::{Dispatch, DispatchValidateT};
 use pallet_evm_precompile_modexp::Modexp;
 use pallet_evm_precompile_sha3fips::Sha3FIPS256;
 use pallet_evm_precompile_simple::{ECRecover, ECRecoverPublicKey, Identity, Ripemd160, Sha256};
 
+mod asset_conversion;
+mod batch;
+mod call_permit;
+mod dispatch;
+mod erc20;
+mod p256;
+mod point_evaluation;
+#[cfg(test)]
+mod tests;
+
+pub use asset_conversion::AssetConversionPrecompile;
+pub use batch::BatchPrecompile;
+pub use call_permit::CallPermitPrecompile;
+pub use dispatch::{DispatchCallFilter, FilteredDispatchValidator};
+pub use erc20::{
+	address_to_asset_id, asset_id_to_address, Erc20AssetsPrecompileSet, ASSET_ADDRESS_PREFIX,
+};
+pub use p256::P256Verify;
+pub use point_evaluation::PointEvaluation;
+
+/// Validator of the calls submitted through the `Dispatch` precompile.
+pub type DispatchValidator = FilteredDispatchValidator<DispatchCallFilter>;
+
+/// Address standing for the native currency wherever a precompile expects a token address.
+pub const NATIVE_ASSET_ADDRESS: H160 = H160([0xee; 20]);
+
 pub struct FrontierPrecompiles<R>(PhantomData<R>);
 
 impl<R> FrontierPrecompiles<R>
 where
-	R: pallet_evm::Config,
+	R: pallet_evm::Config
+		+ pallet_timestamp::Config
+		+ pallet_asset_conversion::Config<AssetKind = NativeOrWithId<u32>, Balance = u128>
+		+ pallet_assets::Config<pallet_assets::Instance1, AssetId = u32, Balance = u128>,
+	R::RuntimeCall: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo,
+	R::RuntimeCall: From<pallet_asset_conversion::Call<R>>,
+	R::RuntimeCall: From<pallet_assets::Call<R, pallet_assets::Instance1>>,
+	<R::RuntimeCall as Dispatchable>::RuntimeOrigin: From<Option<R::AccountId>>,
+	R::RuntimeCall: codec::Decode,
+	DispatchValidator: DispatchValidateT<R::AccountId, R::RuntimeCall>,
 {
 	pub fn new() -> Self {
 		Self(Default::default())
 	}
-	pub fn used_addresses() -> [H160; 7] {
+	pub fn used_addresses() -> [H160; 17] {
 		[
 			hash(1),
 			hash(2),
 			hash(3),
 			hash(4),
 			hash(5),
+			hash(6),
+			hash(7),
+			hash(8),
+			hash(9),
+			hash(10),
+			hash(256),
 			hash(1024),
 			hash(1025),
+			hash(1026),
+			hash(2048),
+			hash(2049),
+			hash(2050),
 		]
 	}
 }
 impl<R> PrecompileSet for FrontierPrecompiles<R>
 where
-	R: pallet_evm::Config,
+	R: pallet_evm::Config
+		+ pallet_timestamp::Config
+		+ pallet_asset_conversion::Config<AssetKind = NativeOrWithId<u32>, Balance = u128>
+		+ pallet_assets::Config<pallet_assets::Instance1, AssetId = u32, Balance = u128>,
+	R::RuntimeCall: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo,
+	R::RuntimeCall: From<pallet_asset_conversion::Call<R>>,
+	R::RuntimeCall: From<pallet_assets::Call<R, pallet_assets::Instance1>>,
+	<R::RuntimeCall as Dispatchable>::RuntimeOrigin: From<Option<R::AccountId>>,
+	R::RuntimeCall: codec::Decode,
+	DispatchValidator: DispatchValidateT<R::AccountId, R::RuntimeCall>,
 {
 	fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
 		match handle.code_address() {
@@ -41,21 +104,48 @@ where
 			a if a == hash(3) => Some(Ripemd160::execute(handle)),
 			a if a == hash(4) => Some(Identity::execute(handle)),
 			a if a == hash(5) => Some(Modexp::execute(handle)),
+			a if a == hash(6) => Some(Bn128Add::execute(handle)),
+			a if a == hash(7) => Some(Bn128Mul::execute(handle)),
+			a if a == hash(8) => Some(Bn128Pairing::execute(handle)),
+			a if a == hash(9) => Some(Blake2F::execute(handle)),
+			a if a == hash(10) => Some(PointEvaluation::execute(handle)),
+			// RIP-7212 precompiles :
+			a if a == hash(256) => Some(P256Verify::execute(handle)),
 			// Non-Frontier specific nor Ethereum precompiles :
 			a if a == hash(1024) => Some(Sha3FIPS256::execute(handle)),
 			a if a == hash(1025) => Some(ECRecoverPublicKey::execute(handle)),
-			_ => None,
+			a if a == hash(1026) => Some(
+				ensure_not_delegate_call(handle)
+					.and_then(|()| Dispatch::<R, DispatchValidator>::execute(handle)),
+			),
+			// FintradeX specific precompiles :
+			a if a == hash(2048) => Some(AssetConversionPrecompile::<R>::execute(handle)),
+			a if a == hash(2049) => Some(BatchPrecompile::<R>::execute(handle)),
+			a if a == hash(2050) => Some(CallPermitPrecompile::<R>::execute(handle)),
+			// ERC-20 interface of the `Assets` tokens :
+			_ => Erc20AssetsPrecompileSet::<R>::default().execute(handle),
 		}
 	}
 
-	fn is_precompile(&self, address: H160, _gas: u64) -> IsPrecompileResult {
-		IsPrecompileResult::Answer {
-			is_precompile: Self::used_addresses().contains(&address),
-			extra_cost: 0,
+	fn is_precompile(&self, address: H160, gas: u64) -> IsPrecompileResult {
+		if Self::used_addresses().contains(&address) {
+			return IsPrecompileResult::Answer { is_precompile: true, extra_cost: 0 };
 		}
+		Erc20AssetsPrecompileSet::<R>::default().is_precompile(address, gas)
 	}
 }
 
 fn hash(a: u64) -> H160 {
 	H160::from_low_u64_be(a)
-}
\ No newline at end of file
+}
+
+/// Precompiles that act on behalf of `msg.sender` must not run in the context of another
+/// contract, which could otherwise impersonate its own caller.
+fn ensure_not_delegate_call(handle: &mut impl PrecompileHandle) -> Result<(), PrecompileFailure> {
+	if handle.code_address() != handle.context().address {
+		return Err(precompile_utils::prelude::revert(
+			"cannot be called with DELEGATECALL or CALLCODE",
+		));
+	}
+	Ok(())
+}
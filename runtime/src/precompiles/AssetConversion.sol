@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: MIT-0
+pragma solidity >=0.8.3;
+
+/// @dev The AssetConversion precompile address.
+address constant ASSET_CONVERSION_ADDRESS = 0x0000000000000000000000000000000000000800;
+
+/// @dev The AssetConversion precompile instance.
+AssetConversion constant ASSET_CONVERSION_CONTRACT = AssetConversion(ASSET_CONVERSION_ADDRESS);
+
+/// @title Native AMM (`pallet_asset_conversion`) interface.
+/// @notice The native currency is `0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE`; an `Assets`
+/// token is the address of its ERC-20 precompile (see `ERC20.sol`).
+/// @custom:address 0x0000000000000000000000000000000000000800
+interface AssetConversion {
+    /// @dev Swap an exact `amountIn` of `path[0]` for at least `amountOutMin` of the last asset.
+    function swapExactTokensForTokens(
+        address[] memory path,
+        uint256 amountIn,
+        uint256 amountOutMin,
+        address sendTo,
+        bool keepAlive
+    ) external;
+
+    /// @dev Swap at most `amountInMax` of `path[0]` for exactly `amountOut` of the last asset.
+    function swapTokensForExactTokens(
+        address[] memory path,
+        uint256 amountOut,
+        uint256 amountInMax,
+        address sendTo,
+        bool keepAlive
+    ) external;
+
+    /// @dev Provide liquidity to the `asset1`/`asset2` pool, minting LP tokens to `mintTo`.
+    function addLiquidity(
+        address asset1,
+        address asset2,
+        uint256 amount1Desired,
+        uint256 amount2Desired,
+        uint256 amount1Min,
+        uint256 amount2Min,
+        address mintTo
+    ) external;
+
+    /// @dev Burn `lpTokenBurn` LP tokens and withdraw the underlying assets to `withdrawTo`.
+    function removeLiquidity(
+        address asset1,
+        address asset2,
+        uint256 lpTokenBurn,
+        uint256 amount1MinReceive,
+        uint256 amount2MinReceive,
+        address withdrawTo
+    ) external;
+
+    /// @dev Amount of `asset2` received for exactly `amount` of `asset1`.
+    function quoteExactTokensForTokens(
+        address asset1,
+        address asset2,
+        uint256 amount,
+        bool includeFee
+    ) external view returns (uint256);
+
+    /// @dev Amount of `asset1` needed to receive exactly `amount` of `asset2`.
+    function quoteTokensForExactTokens(
+        address asset1,
+        address asset2,
+        uint256 amount,
+        bool includeFee
+    ) external view returns (uint256);
+
+    /// @dev Reserves of the `asset1`/`asset2` pool, in the given order.
+    function getReserves(address asset1, address asset2)
+        external
+        view
+        returns (uint256, uint256);
+}
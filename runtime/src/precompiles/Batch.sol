@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: MIT-0
+pragma solidity >=0.8.3;
+
+/// @dev The Batch precompile address.
+address constant BATCH_ADDRESS = 0x0000000000000000000000000000000000000801;
+
+/// @dev The Batch precompile instance.
+Batch constant BATCH_CONTRACT = Batch(BATCH_ADDRESS);
+
+/// @title Batch interface.
+/// @notice Performs several calls with the caller as `msg.sender`. `value`, `callData` and
+/// `gasLimit` may be shorter than `to`; missing entries default to 0, empty input and all
+/// remaining gas. A gas limit of 0 also means all remaining gas.
+/// @custom:address 0x0000000000000000000000000000000000000801
+interface Batch {
+    /// @dev Perform every subcall, skipping the ones that fail.
+    function batchSome(
+        address[] memory to,
+        uint256[] memory value,
+        bytes[] memory callData,
+        uint64[] memory gasLimit
+    ) external;
+
+    /// @dev Perform subcalls until one fails, keeping the effects of the previous ones.
+    function batchSomeUntilFailure(
+        address[] memory to,
+        uint256[] memory value,
+        bytes[] memory callData,
+        uint64[] memory gasLimit
+    ) external;
+
+    /// @dev Perform every subcall atomically: any failure reverts the whole batch.
+    function batchAll(
+        address[] memory to,
+        uint256[] memory value,
+        bytes[] memory callData,
+        uint64[] memory gasLimit
+    ) external;
+
+    /// @dev Emitted when subcall `index` succeeded.
+    event SubcallSucceeded(uint256 index);
+
+    /// @dev Emitted when subcall `index` failed.
+    event SubcallFailed(uint256 index);
+}
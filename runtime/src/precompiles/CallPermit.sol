@@ -0,0 +1,35 @@
+// SPDX-License-Identifier: MIT-0
+pragma solidity >=0.8.3;
+
+/// @dev The CallPermit precompile address.
+address constant CALL_PERMIT_ADDRESS = 0x0000000000000000000000000000000000000802;
+
+/// @dev The CallPermit precompile instance.
+CallPermit constant CALL_PERMIT_CONTRACT = CallPermit(CALL_PERMIT_ADDRESS);
+
+/// @title Call permit interface.
+/// @notice Dispatches a call on behalf of `from` given an EIP-712 signature of
+/// `CallPermit(address from,address to,uint256 value,bytes data,uint64 gaslimit,uint256 nonce,uint256 deadline)`
+/// under the domain `{ name: "Call Permit Precompile", version: "1", chainId, verifyingContract: CALL_PERMIT_ADDRESS }`.
+/// @custom:address 0x0000000000000000000000000000000000000802
+interface CallPermit {
+    /// @dev Dispatch the call described by the permit. `deadline` is a UNIX timestamp in seconds.
+    /// @return output The output of the dispatched call.
+    function dispatch(
+        address from,
+        address to,
+        uint256 value,
+        bytes memory data,
+        uint64 gaslimit,
+        uint256 deadline,
+        uint8 v,
+        bytes32 r,
+        bytes32 s
+    ) external returns (bytes memory output);
+
+    /// @dev Nonce to include in the next permit signed by `owner`.
+    function nonces(address owner) external view returns (uint256);
+
+    /// @dev EIP-712 domain separator of the permits.
+    function DOMAIN_SEPARATOR() external view returns (bytes32);
+}
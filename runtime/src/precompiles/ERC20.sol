@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: MIT-0
+pragma solidity >=0.8.3;
+
+/// @title ERC-20 interface of an `Assets` token.
+/// @notice The token with id `n` answers at `0xFFFFFFFF` followed by `n` as a 16 byte
+/// big-endian integer, once the asset exists. Calls changing balances or allowances cannot be
+/// made with DELEGATECALL.
+interface IERC20 {
+    /// @dev Name of the token, from the asset metadata.
+    function name() external view returns (string memory);
+
+    /// @dev Symbol of the token, from the asset metadata.
+    function symbol() external view returns (string memory);
+
+    /// @dev Decimals of the token, from the asset metadata.
+    function decimals() external view returns (uint8);
+
+    /// @dev Total issuance of the asset.
+    function totalSupply() external view returns (uint256);
+
+    /// @dev Balance of `who`.
+    function balanceOf(address who) external view returns (uint256);
+
+    /// @dev Amount `spender` may transfer from `owner`.
+    function allowance(address owner, address spender) external view returns (uint256);
+
+    /// @dev Transfer `value` from the caller to `to`.
+    function transfer(address to, uint256 value) external returns (bool);
+
+    /// @dev Let `spender` transfer up to `value` from the caller, replacing any previous
+    /// allowance. An approval reserves the asset approval deposit of the caller.
+    function approve(address spender, uint256 value) external returns (bool);
+
+    /// @dev Transfer `value` from `from` to `to`, within the allowance of the caller.
+    function transferFrom(address from, address to, uint256 value) external returns (bool);
+
+    event Transfer(address indexed from, address indexed to, uint256 value);
+
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+}
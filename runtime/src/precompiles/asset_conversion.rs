@@ -0,0 +1,245 @@
+//! Precompile exposing `pallet_asset_conversion` (the native AMM) to Solidity contracts.
+//!
+//! Tokens are identified by their EVM address: [`NATIVE_ASSET_ADDRESS`] for the native
+//! currency and the address of their ERC-20 precompile (see [`super::Erc20AssetsPrecompileSet`])
+//! for the `Assets` tokens.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::marker::PhantomData;
+
+use frame_support::{
+	dispatch::{GetDispatchInfo, PostDispatchInfo},
+	traits::fungible::NativeOrWithId,
+};
+use pallet_evm::AddressMapping;
+use precompile_utils::prelude::*;
+use sp_core::{ConstU32, H160, U256};
+use sp_runtime::traits::Dispatchable;
+
+use super::{address_to_asset_id, ensure_not_delegate_call, NATIVE_ASSET_ADDRESS};
+
+/// Maximum number of hops in a swap path.
+pub type GetPathLimit = ConstU32<4>;
+
+type AssetKindOf = NativeOrWithId<u32>;
+
+pub struct AssetConversionPrecompile<Runtime>(PhantomData<Runtime>);
+
+#[precompile_utils::precompile]
+impl<Runtime> AssetConversionPrecompile<Runtime>
+where
+	Runtime: pallet_asset_conversion::Config<AssetKind = AssetKindOf, Balance = u128>
+		+ pallet_evm::Config,
+	Runtime::RuntimeCall: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo,
+	Runtime::RuntimeCall: From<pallet_asset_conversion::Call<Runtime>>,
+	<Runtime::RuntimeCall as Dispatchable>::RuntimeOrigin: From<Option<Runtime::AccountId>>,
+{
+	#[precompile::public(
+		"swapExactTokensForTokens(address[],uint256,uint256,address,bool)"
+	)]
+	fn swap_exact_tokens_for_tokens(
+		handle: &mut impl PrecompileHandle,
+		path: BoundedVec<Address, GetPathLimit>,
+		amount_in: U256,
+		amount_out_min: U256,
+		send_to: Address,
+		keep_alive: bool,
+	) -> EvmResult {
+		let path = Self::path(path, "path")?;
+		let amount_in = Self::balance(amount_in, "amountIn")?;
+		let amount_out_min = Self::balance(amount_out_min, "amountOutMin")?;
+		let send_to = Runtime::AddressMapping::into_account_id(send_to.into());
+
+		let call = pallet_asset_conversion::Call::<Runtime>::swap_exact_tokens_for_tokens {
+			path,
+			amount_in,
+			amount_out_min,
+			send_to,
+			keep_alive,
+		};
+		Self::dispatch(handle, call)
+	}
+
+	#[precompile::public(
+		"swapTokensForExactTokens(address[],uint256,uint256,address,bool)"
+	)]
+	fn swap_tokens_for_exact_tokens(
+		handle: &mut impl PrecompileHandle,
+		path: BoundedVec<Address, GetPathLimit>,
+		amount_out: U256,
+		amount_in_max: U256,
+		send_to: Address,
+		keep_alive: bool,
+	) -> EvmResult {
+		let path = Self::path(path, "path")?;
+		let amount_out = Self::balance(amount_out, "amountOut")?;
+		let amount_in_max = Self::balance(amount_in_max, "amountInMax")?;
+		let send_to = Runtime::AddressMapping::into_account_id(send_to.into());
+
+		let call = pallet_asset_conversion::Call::<Runtime>::swap_tokens_for_exact_tokens {
+			path,
+			amount_out,
+			amount_in_max,
+			send_to,
+			keep_alive,
+		};
+		Self::dispatch(handle, call)
+	}
+
+	#[precompile::public(
+		"addLiquidity(address,address,uint256,uint256,uint256,uint256,address)"
+	)]
+	fn add_liquidity(
+		handle: &mut impl PrecompileHandle,
+		asset1: Address,
+		asset2: Address,
+		amount1_desired: U256,
+		amount2_desired: U256,
+		amount1_min: U256,
+		amount2_min: U256,
+		mint_to: Address,
+	) -> EvmResult {
+		let call = pallet_asset_conversion::Call::<Runtime>::add_liquidity {
+			asset1: Box::new(Self::asset_kind(asset1, "asset1")?),
+			asset2: Box::new(Self::asset_kind(asset2, "asset2")?),
+			amount1_desired: Self::balance(amount1_desired, "amount1Desired")?,
+			amount2_desired: Self::balance(amount2_desired, "amount2Desired")?,
+			amount1_min: Self::balance(amount1_min, "amount1Min")?,
+			amount2_min: Self::balance(amount2_min, "amount2Min")?,
+			mint_to: Runtime::AddressMapping::into_account_id(mint_to.into()),
+		};
+		Self::dispatch(handle, call)
+	}
+
+	#[precompile::public(
+		"removeLiquidity(address,address,uint256,uint256,uint256,address)"
+	)]
+	fn remove_liquidity(
+		handle: &mut impl PrecompileHandle,
+		asset1: Address,
+		asset2: Address,
+		lp_token_burn: U256,
+		amount1_min_receive: U256,
+		amount2_min_receive: U256,
+		withdraw_to: Address,
+	) -> EvmResult {
+		let call = pallet_asset_conversion::Call::<Runtime>::remove_liquidity {
+			asset1: Box::new(Self::asset_kind(asset1, "asset1")?),
+			asset2: Box::new(Self::asset_kind(asset2, "asset2")?),
+			lp_token_burn: Self::balance(lp_token_burn, "lpTokenBurn")?,
+			amount1_min_receive: Self::balance(amount1_min_receive, "amount1MinReceive")?,
+			amount2_min_receive: Self::balance(amount2_min_receive, "amount2MinReceive")?,
+			withdraw_to: Runtime::AddressMapping::into_account_id(withdraw_to.into()),
+		};
+		Self::dispatch(handle, call)
+	}
+
+	#[precompile::public("quoteExactTokensForTokens(address,address,uint256,bool)")]
+	#[precompile::view]
+	fn quote_exact_tokens_for_tokens(
+		handle: &mut impl PrecompileHandle,
+		asset1: Address,
+		asset2: Address,
+		amount: U256,
+		include_fee: bool,
+	) -> EvmResult<U256> {
+		// Pool lookup plus both reserves.
+		handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost().saturating_mul(3))?;
+
+		let quote = pallet_asset_conversion::Pallet::<Runtime>::quote_price_exact_tokens_for_tokens(
+			Self::asset_kind(asset1, "asset1")?,
+			Self::asset_kind(asset2, "asset2")?,
+			Self::balance(amount, "amount")?,
+			include_fee,
+		)
+		.ok_or_else(|| revert("no quote available"))?;
+
+		Ok(quote.into())
+	}
+
+	#[precompile::public("quoteTokensForExactTokens(address,address,uint256,bool)")]
+	#[precompile::view]
+	fn quote_tokens_for_exact_tokens(
+		handle: &mut impl PrecompileHandle,
+		asset1: Address,
+		asset2: Address,
+		amount: U256,
+		include_fee: bool,
+	) -> EvmResult<U256> {
+		handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost().saturating_mul(3))?;
+
+		let quote = pallet_asset_conversion::Pallet::<Runtime>::quote_price_tokens_for_exact_tokens(
+			Self::asset_kind(asset1, "asset1")?,
+			Self::asset_kind(asset2, "asset2")?,
+			Self::balance(amount, "amount")?,
+			include_fee,
+		)
+		.ok_or_else(|| revert("no quote available"))?;
+
+		Ok(quote.into())
+	}
+
+	#[precompile::public("getReserves(address,address)")]
+	#[precompile::view]
+	fn get_reserves(
+		handle: &mut impl PrecompileHandle,
+		asset1: Address,
+		asset2: Address,
+	) -> EvmResult<(U256, U256)> {
+		handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost().saturating_mul(3))?;
+
+		let (reserve1, reserve2) = pallet_asset_conversion::Pallet::<Runtime>::get_reserves(
+			Self::asset_kind(asset1, "asset1")?,
+			Self::asset_kind(asset2, "asset2")?,
+		)
+		.map_err(|_| revert("pool not found"))?;
+
+		Ok((reserve1.into(), reserve2.into()))
+	}
+}
+
+impl<Runtime> AssetConversionPrecompile<Runtime>
+where
+	Runtime: pallet_asset_conversion::Config<AssetKind = AssetKindOf, Balance = u128>
+		+ pallet_evm::Config,
+	Runtime::RuntimeCall: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo,
+	Runtime::RuntimeCall: From<pallet_asset_conversion::Call<Runtime>>,
+	<Runtime::RuntimeCall as Dispatchable>::RuntimeOrigin: From<Option<Runtime::AccountId>>,
+{
+	fn dispatch(
+		handle: &mut impl PrecompileHandle,
+		call: pallet_asset_conversion::Call<Runtime>,
+	) -> EvmResult {
+		ensure_not_delegate_call(handle)?;
+
+		let origin = Runtime::AddressMapping::into_account_id(handle.context().caller);
+		RuntimeHelper::<Runtime>::try_dispatch(handle, Some(origin).into(), call, 0)?;
+		Ok(())
+	}
+
+	fn asset_kind(address: Address, field: &'static str) -> EvmResult<AssetKindOf> {
+		let address: H160 = address.into();
+		if address == NATIVE_ASSET_ADDRESS {
+			return Ok(NativeOrWithId::Native);
+		}
+		address_to_asset_id(address)
+			.map(NativeOrWithId::WithId)
+			.ok_or_else(|| RevertReason::custom("unknown asset address").in_field(field).into())
+	}
+
+	fn path(
+		path: BoundedVec<Address, GetPathLimit>,
+		field: &'static str,
+	) -> EvmResult<Vec<Box<AssetKindOf>>> {
+		Vec::from(path)
+			.into_iter()
+			.map(|address| Self::asset_kind(address, field).map(Box::new))
+			.collect()
+	}
+
+	fn balance(value: U256, field: &'static str) -> EvmResult<u128> {
+		value
+			.try_into()
+			.map_err(|_| RevertReason::value_is_too_large("balance type").in_field(field).into())
+	}
+}
@@ -0,0 +1,193 @@
+//! Precompile performing several EVM calls on behalf of the caller in one transaction.
+//!
+//! Subcalls keep the original caller as `msg.sender`, which is why the precompile refuses
+//! to run under `DELEGATECALL`.
+
+use alloc::vec::Vec;
+use core::{iter::repeat, marker::PhantomData};
+
+use fp_evm::{Context, ExitError, ExitReason, Log, PrecompileFailure, Transfer};
+use precompile_utils::{
+	evm::{costs::call_cost, logs::log1},
+	prelude::*,
+};
+use sp_core::{ConstU32, H160, U256};
+
+use super::ensure_not_delegate_call;
+
+/// Maximum number of subcalls in a single batch.
+pub type GetArrayLimit = ConstU32<{ 2u32.pow(9) }>;
+/// Maximum size of the input of a single subcall.
+pub type GetCallDataLimit = ConstU32<{ 2u32.pow(16) }>;
+
+pub const LOG_SUBCALL_SUCCEEDED: [u8; 32] = keccak256!("SubcallSucceeded(uint256)");
+pub const LOG_SUBCALL_FAILED: [u8; 32] = keccak256!("SubcallFailed(uint256)");
+
+/// How a batch reacts to a failing subcall.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Mode {
+	/// Skip the failing subcall and carry on with the next one.
+	BatchSome,
+	/// Stop at the first failing subcall, keeping the effects of the previous ones.
+	BatchSomeUntilFailure,
+	/// Revert the whole batch if any subcall fails.
+	BatchAll,
+}
+
+pub struct BatchPrecompile<Runtime>(PhantomData<Runtime>);
+
+#[precompile_utils::precompile]
+impl<Runtime> BatchPrecompile<Runtime>
+where
+	Runtime: pallet_evm::Config,
+{
+	#[precompile::public("batchSome(address[],uint256[],bytes[],uint64[])")]
+	fn batch_some(
+		handle: &mut impl PrecompileHandle,
+		to: BoundedVec<Address, GetArrayLimit>,
+		value: BoundedVec<U256, GetArrayLimit>,
+		call_data: BoundedVec<BoundedBytes<GetCallDataLimit>, GetArrayLimit>,
+		gas_limit: BoundedVec<u64, GetArrayLimit>,
+	) -> EvmResult {
+		Self::inner_batch(Mode::BatchSome, handle, to, value, call_data, gas_limit)
+	}
+
+	#[precompile::public("batchSomeUntilFailure(address[],uint256[],bytes[],uint64[])")]
+	fn batch_some_until_failure(
+		handle: &mut impl PrecompileHandle,
+		to: BoundedVec<Address, GetArrayLimit>,
+		value: BoundedVec<U256, GetArrayLimit>,
+		call_data: BoundedVec<BoundedBytes<GetCallDataLimit>, GetArrayLimit>,
+		gas_limit: BoundedVec<u64, GetArrayLimit>,
+	) -> EvmResult {
+		Self::inner_batch(Mode::BatchSomeUntilFailure, handle, to, value, call_data, gas_limit)
+	}
+
+	#[precompile::public("batchAll(address[],uint256[],bytes[],uint64[])")]
+	fn batch_all(
+		handle: &mut impl PrecompileHandle,
+		to: BoundedVec<Address, GetArrayLimit>,
+		value: BoundedVec<U256, GetArrayLimit>,
+		call_data: BoundedVec<BoundedBytes<GetCallDataLimit>, GetArrayLimit>,
+		gas_limit: BoundedVec<u64, GetArrayLimit>,
+	) -> EvmResult {
+		Self::inner_batch(Mode::BatchAll, handle, to, value, call_data, gas_limit)
+	}
+}
+
+impl<Runtime> BatchPrecompile<Runtime>
+where
+	Runtime: pallet_evm::Config,
+{
+	/// Runs the subcalls in order. `value`, `call_data` and `gas_limit` may be shorter than
+	/// `to`: missing values default to zero, empty input and all remaining gas respectively.
+	fn inner_batch(
+		mode: Mode,
+		handle: &mut impl PrecompileHandle,
+		to: BoundedVec<Address, GetArrayLimit>,
+		value: BoundedVec<U256, GetArrayLimit>,
+		call_data: BoundedVec<BoundedBytes<GetCallDataLimit>, GetArrayLimit>,
+		gas_limit: BoundedVec<u64, GetArrayLimit>,
+	) -> EvmResult {
+		ensure_not_delegate_call(handle)?;
+
+		let addresses = Vec::from(to).into_iter().enumerate();
+		let values = Vec::from(value).into_iter().map(Some).chain(repeat(None));
+		let calls_data = Vec::from(call_data).into_iter().map(Some).chain(repeat(None));
+		let gas_limits = Vec::from(gas_limit)
+			.into_iter()
+			.map(|limit| if limit == 0 { None } else { Some(limit) })
+			.chain(repeat(None));
+
+		// The log does not depend on the subcall index, so its cost can be computed once.
+		let log_cost = log_subcall_failed(handle.code_address(), 0)
+			.compute_cost()
+			.map_err(|_| revert("failed to compute log cost"))?;
+
+		for ((index, address), (value, (call_data, gas_limit))) in
+			addresses.zip(values.zip(calls_data.zip(gas_limits)))
+		{
+			let address: H160 = address.into();
+			let value = value.unwrap_or_default();
+			let call_data: Vec<u8> = call_data.map(Into::into).unwrap_or_default();
+			let caller = handle.context().caller;
+
+			// Keep enough gas aside to emit the final log of this subcall.
+			let forwarded_gas = match (handle.remaining_gas().checked_sub(log_cost), mode) {
+				(Some(remaining), _) => remaining,
+				(None, Mode::BatchAll) => {
+					return Err(PrecompileFailure::Error { exit_status: ExitError::OutOfGas })
+				},
+				(None, _) => return Ok(()),
+			};
+
+			// The batch precompile pays for the call itself, as a CALL opcode would.
+			let forwarded_gas = forwarded_gas
+				.checked_sub(call_cost(value, <Runtime as pallet_evm::Config>::config()));
+			let forwarded_gas = match (forwarded_gas, gas_limit) {
+				(Some(remaining), None) => Some(remaining),
+				(Some(remaining), Some(limit)) if limit <= remaining => Some(limit),
+				_ => None,
+			};
+			let Some(forwarded_gas) = forwarded_gas else {
+				let log = log_subcall_failed(handle.code_address(), index);
+				handle.record_log_costs(&[&log])?;
+				log.record(handle)?;
+
+				match mode {
+					Mode::BatchAll => {
+						return Err(PrecompileFailure::Error { exit_status: ExitError::OutOfGas })
+					},
+					Mode::BatchSomeUntilFailure => return Ok(()),
+					Mode::BatchSome => continue,
+				}
+			};
+
+			let sub_context = Context { caller, address, apparent_value: value };
+			let transfer =
+				(!value.is_zero()).then_some(Transfer { source: caller, target: address, value });
+
+			let (reason, output) =
+				handle.call(address, transfer, call_data, Some(forwarded_gas), false, &sub_context);
+
+			// Gas for the log was reserved above.
+			let log = match reason {
+				ExitReason::Succeed(_) => Some(log_subcall_succeeded(handle.code_address(), index)),
+				ExitReason::Revert(_) | ExitReason::Error(_) => {
+					Some(log_subcall_failed(handle.code_address(), index))
+				},
+				ExitReason::Fatal(_) => None,
+			};
+			if let Some(log) = log {
+				handle.record_log_costs(&[&log])?;
+				log.record(handle)?;
+			}
+
+			match (mode, reason) {
+				(_, ExitReason::Fatal(exit_status)) => {
+					return Err(PrecompileFailure::Fatal { exit_status })
+				},
+				(Mode::BatchAll, ExitReason::Revert(exit_status)) => {
+					return Err(PrecompileFailure::Revert { exit_status, output })
+				},
+				(Mode::BatchAll, ExitReason::Error(exit_status)) => {
+					return Err(PrecompileFailure::Error { exit_status })
+				},
+				(Mode::BatchSomeUntilFailure, ExitReason::Revert(_) | ExitReason::Error(_)) => {
+					return Ok(())
+				},
+				_ => (),
+			}
+		}
+
+		Ok(())
+	}
+}
+
+fn log_subcall_succeeded(address: H160, index: usize) -> Log {
+	log1(address, LOG_SUBCALL_SUCCEEDED, solidity::encode_event_data(U256::from(index)))
+}
+
+fn log_subcall_failed(address: H160, index: usize) -> Log {
+	log1(address, LOG_SUBCALL_FAILED, solidity::encode_event_data(U256::from(index)))
+}
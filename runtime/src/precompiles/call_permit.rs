@@ -0,0 +1,199 @@
+//! Precompile dispatching an EVM call on behalf of a signer holding an EIP-712 permit.
+//!
+//! The relayer submitting the transaction pays its gas, while the call itself is executed with
+//! the signer as `msg.sender`. Each permit carries the signer's current nonce and a deadline
+//! (in seconds) so it can neither be replayed nor used after it expired.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use fp_evm::{Context, ExitReason, PrecompileFailure, Transfer};
+use frame_support::{
+	storage::types::{StorageMap, ValueQuery},
+	traits::{Get, StorageInstance},
+	Blake2_128Concat,
+};
+use precompile_utils::{evm::costs::call_cost, prelude::*};
+use sp_core::{H160, H256, U256};
+use sp_io::hashing::keccak_256;
+use sp_runtime::traits::UniqueSaturatedInto;
+
+use super::{batch::GetCallDataLimit, ensure_not_delegate_call};
+
+/// Storage prefix of the permit nonces.
+pub struct NoncesStorageInstance;
+
+impl StorageInstance for NoncesStorageInstance {
+	const STORAGE_PREFIX: &'static str = "Nonces";
+
+	fn pallet_prefix() -> &'static str {
+		"PrecompileCallPermit"
+	}
+}
+
+/// Next permit nonce of each signer.
+pub type NoncesStorage = StorageMap<NoncesStorageInstance, Blake2_128Concat, H160, U256, ValueQuery>;
+
+pub const PERMIT_TYPEHASH: [u8; 32] = keccak256!(
+	"CallPermit(address from,address to,uint256 value,bytes data,uint64 gaslimit,uint256 nonce,uint256 deadline)"
+);
+
+const PERMIT_DOMAIN: [u8; 32] = keccak256!(
+	"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)"
+);
+
+pub struct CallPermitPrecompile<Runtime>(PhantomData<Runtime>);
+
+#[precompile_utils::precompile]
+impl<Runtime> CallPermitPrecompile<Runtime>
+where
+	Runtime: pallet_evm::Config + pallet_timestamp::Config,
+{
+	#[precompile::public(
+		"dispatch(address,address,uint256,bytes,uint64,uint256,uint8,bytes32,bytes32)"
+	)]
+	fn dispatch(
+		handle: &mut impl PrecompileHandle,
+		from: Address,
+		to: Address,
+		value: U256,
+		data: BoundedBytes<GetCallDataLimit>,
+		gas_limit: u64,
+		deadline: U256,
+		v: u8,
+		r: H256,
+		s: H256,
+	) -> EvmResult<UnboundedBytes> {
+		ensure_not_delegate_call(handle)?;
+
+		// Nonce and timestamp reads, nonce write, then the subcall itself.
+		handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost().saturating_mul(2))?;
+		handle.record_cost(RuntimeHelper::<Runtime>::db_write_gas_cost())?;
+		let call_cost = call_cost(value, <Runtime as pallet_evm::Config>::config());
+		handle.record_cost(call_cost)?;
+		if handle.remaining_gas() < gas_limit {
+			return Err(revert("gaslimit is too low to dispatch provided call"));
+		}
+
+		let now: u64 = pallet_timestamp::Now::<Runtime>::get().unique_saturated_into();
+		if deadline < U256::from(now / 1000) {
+			return Err(revert("permit expired"));
+		}
+
+		let from: H160 = from.into();
+		let to: H160 = to.into();
+		let data: Vec<u8> = data.into();
+		let nonce = NoncesStorage::get(from);
+
+		let permit = Self::generate_permit(
+			handle.context().address,
+			from,
+			to,
+			value,
+			&data,
+			gas_limit,
+			nonce,
+			deadline,
+		);
+
+		let mut signature = [0u8; 65];
+		signature[0..32].copy_from_slice(r.as_bytes());
+		signature[32..64].copy_from_slice(s.as_bytes());
+		signature[64] = v;
+
+		let signer = sp_io::crypto::secp256k1_ecdsa_recover(&signature, &permit)
+			.map_err(|_| revert("invalid permit"))?;
+		let signer = H160::from(H256::from(keccak_256(&signer)));
+
+		if signer.is_zero() || signer != from {
+			return Err(revert("invalid permit"));
+		}
+
+		NoncesStorage::insert(from, nonce.saturating_add(U256::one()));
+
+		let sub_context = Context { caller: from, address: to, apparent_value: value };
+		let transfer = (!value.is_zero()).then_some(Transfer { source: from, target: to, value });
+
+		let (reason, output) =
+			handle.call(to, transfer, data, Some(gas_limit), false, &sub_context);
+
+		match reason {
+			ExitReason::Succeed(_) => Ok(output.into()),
+			ExitReason::Revert(exit_status) => {
+				Err(PrecompileFailure::Revert { exit_status, output })
+			},
+			ExitReason::Error(exit_status) => Err(PrecompileFailure::Error { exit_status }),
+			ExitReason::Fatal(exit_status) => Err(PrecompileFailure::Fatal { exit_status }),
+		}
+	}
+
+	#[precompile::public("nonces(address)")]
+	#[precompile::view]
+	fn nonces(handle: &mut impl PrecompileHandle, owner: Address) -> EvmResult<U256> {
+		handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+
+		Ok(NoncesStorage::get(H160::from(owner)))
+	}
+
+	#[precompile::public("DOMAIN_SEPARATOR()")]
+	#[precompile::view]
+	fn domain_separator(handle: &mut impl PrecompileHandle) -> EvmResult<H256> {
+		// ChainId storage read.
+		handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+
+		Ok(Self::compute_domain_separator(handle.context().address).into())
+	}
+}
+
+impl<Runtime> CallPermitPrecompile<Runtime>
+where
+	Runtime: pallet_evm::Config + pallet_timestamp::Config,
+{
+	fn compute_domain_separator(address: H160) -> [u8; 32] {
+		let name: H256 = keccak256!("Call Permit Precompile").into();
+		let version: H256 = keccak256!("1").into();
+		let chain_id: U256 = Runtime::ChainId::get().into();
+
+		let domain_separator_inner = solidity::encode_arguments((
+			H256::from(PERMIT_DOMAIN),
+			name,
+			version,
+			chain_id,
+			Address(address),
+		));
+
+		keccak_256(&domain_separator_inner)
+	}
+
+	/// The EIP-712 digest a signer must sign to authorize a call.
+	pub fn generate_permit(
+		address: H160,
+		from: H160,
+		to: H160,
+		value: U256,
+		data: &[u8],
+		gas_limit: u64,
+		nonce: U256,
+		deadline: U256,
+	) -> [u8; 32] {
+		let domain_separator = Self::compute_domain_separator(address);
+
+		let permit_content = solidity::encode_arguments((
+			H256::from(PERMIT_TYPEHASH),
+			Address(from),
+			Address(to),
+			value,
+			H256::from(keccak_256(data)),
+			gas_limit,
+			nonce,
+			deadline,
+		));
+		let permit_content = keccak_256(&permit_content);
+
+		let mut pre_digest = Vec::with_capacity(2 + 32 + 32);
+		pre_digest.extend_from_slice(b"\x19\x01");
+		pre_digest.extend_from_slice(&domain_separator);
+		pre_digest.extend_from_slice(&permit_content);
+		keccak_256(&pre_digest)
+	}
+}
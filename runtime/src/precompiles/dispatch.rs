@@ -0,0 +1,129 @@
+//! Filtering of the runtime calls the `Dispatch` precompile may submit.
+//!
+//! Calls are dispatched with the caller's mapped `AccountId` as a signed origin and pay for
+//! their weight in gas; on top of that only the pallets allowed by [`DispatchCallFilter`]
+//! can be reached from the EVM.
+
+use core::marker::PhantomData;
+
+use fp_evm::{ExitError, PrecompileFailure};
+use frame_support::{
+	dispatch::{DispatchClass, GetDispatchInfo, Pays},
+	traits::Contains,
+};
+use pallet_evm_precompile_dispatch::DispatchValidateT;
+
+use crate::RuntimeCall;
+
+/// Runtime calls reachable through the `Dispatch` precompile.
+///
+/// Anything not listed is denied, in particular `Sudo`, `System` (`set_code` & co.) and the
+/// EVM pallets themselves. Calls wrapping other calls are only allowed if every inner call is.
+pub struct DispatchCallFilter;
+
+impl Contains<RuntimeCall> for DispatchCallFilter {
+	fn contains(call: &RuntimeCall) -> bool {
+		match call {
+			RuntimeCall::Utility(
+				pallet_utility::Call::batch { calls } |
+				pallet_utility::Call::batch_all { calls } |
+				pallet_utility::Call::force_batch { calls },
+			) => calls.iter().all(Self::contains),
+			RuntimeCall::Utility(pallet_utility::Call::as_derivative { call, .. }) => {
+				Self::contains(call)
+			},
+			RuntimeCall::Multisig(
+				pallet_multisig::Call::as_multi_threshold_1 { call, .. } |
+				pallet_multisig::Call::as_multi { call, .. },
+			) => Self::contains(call),
+			RuntimeCall::Multisig(_) |
+			RuntimeCall::Vesting(_) |
+			RuntimeCall::Balances(_) |
+			RuntimeCall::Assets(_) |
+			RuntimeCall::AssetConversion(_) |
+			RuntimeCall::Staking(_) |
+			RuntimeCall::NominationPools(_) |
+			RuntimeCall::ConvictionVoting(_) => true,
+			_ => false,
+		}
+	}
+}
+
+/// [`DispatchValidateT`] rejecting calls outside of `F`, on top of the free and mandatory
+/// calls already refused by the default validator.
+pub struct FilteredDispatchValidator<F>(PhantomData<F>);
+
+impl<AccountId, Call, F> DispatchValidateT<AccountId, Call> for FilteredDispatchValidator<F>
+where
+	Call: GetDispatchInfo,
+	F: Contains<Call>,
+{
+	fn validate_before_dispatch(_origin: &AccountId, call: &Call) -> Option<PrecompileFailure> {
+		let info = call.get_dispatch_info();
+		if info.pays_fee == Pays::No || info.class == DispatchClass::Mandatory {
+			return Some(PrecompileFailure::Error {
+				exit_status: ExitError::Other("invalid call".into()),
+			});
+		}
+		if !F::contains(call) {
+			return Some(PrecompileFailure::Error {
+				exit_status: ExitError::Other("call not allowed from the EVM".into()),
+			});
+		}
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Runtime;
+	use alloc::{boxed::Box, vec, vec::Vec};
+
+	fn burn() -> RuntimeCall {
+		pallet_balances::Call::<Runtime>::burn { value: 1, keep_alive: true }.into()
+	}
+
+	fn set_code() -> RuntimeCall {
+		frame_system::Call::<Runtime>::set_code { code: vec![] }.into()
+	}
+
+	fn batch(calls: Vec<RuntimeCall>) -> RuntimeCall {
+		pallet_utility::Call::<Runtime>::batch { calls }.into()
+	}
+
+	#[test]
+	fn allowed_pallets_pass() {
+		assert!(DispatchCallFilter::contains(&burn()));
+		assert!(DispatchCallFilter::contains(&pallet_staking::Call::<Runtime>::chill {}.into()));
+		assert!(DispatchCallFilter::contains(&pallet_vesting::Call::<Runtime>::vest {}.into()));
+		assert!(DispatchCallFilter::contains(&batch(vec![burn(), burn()])));
+	}
+
+	#[test]
+	fn privileged_calls_are_rejected() {
+		assert!(!DispatchCallFilter::contains(&set_code()));
+		assert!(!DispatchCallFilter::contains(
+			&pallet_sudo::Call::<Runtime>::sudo { call: Box::new(burn()) }.into()
+		));
+	}
+
+	#[test]
+	fn wrapped_calls_are_filtered_recursively() {
+		assert!(!DispatchCallFilter::contains(&batch(vec![burn(), set_code()])));
+		assert!(!DispatchCallFilter::contains(
+			&pallet_utility::Call::<Runtime>::batch_all { calls: vec![batch(vec![set_code()])] }.into()
+		));
+		assert!(!DispatchCallFilter::contains(
+			&pallet_utility::Call::<Runtime>::as_derivative { index: 0, call: Box::new(set_code()) }
+				.into()
+		));
+		assert!(!DispatchCallFilter::contains(
+			&pallet_multisig::Call::<Runtime>::as_multi_threshold_1 {
+				other_signatories: vec![],
+				call: Box::new(set_code()),
+			}
+			.into()
+		));
+	}
+}
@@ -0,0 +1,291 @@
+//! ERC-20 interface of the `Assets` tokens, one precompile per existing asset.
+//!
+//! The token `id` answers at [`asset_id_to_address`]: `0xFFFFFFFF` followed by `id` as a 16
+//! byte big-endian integer, the address scheme of the assets ERC-20 precompiles of other
+//! Frontier chains. The AssetConversion precompile identifies its tokens by the same addresses.
+
+use core::marker::PhantomData;
+
+use frame_support::{
+	dispatch::{GetDispatchInfo, PostDispatchInfo},
+	traits::fungibles::{approvals, metadata},
+};
+use pallet_evm::AddressMapping;
+use precompile_utils::{
+	evm::logs::{log3, LogExt},
+	precompile_set::DiscriminantResult,
+	prelude::*,
+};
+use sp_core::{H160, U256};
+use sp_runtime::traits::{Dispatchable, StaticLookup};
+
+use super::ensure_not_delegate_call;
+
+/// Leading bytes of the ERC-20 address of an `Assets` token.
+pub const ASSET_ADDRESS_PREFIX: [u8; 4] = [0xff; 4];
+
+pub const SELECTOR_LOG_TRANSFER: [u8; 32] = keccak256!("Transfer(address,address,uint256)");
+pub const SELECTOR_LOG_APPROVAL: [u8; 32] = keccak256!("Approval(address,address,uint256)");
+
+type AssetsInstance = pallet_assets::Instance1;
+type Assets<Runtime> = pallet_assets::Pallet<Runtime, AssetsInstance>;
+
+/// The ERC-20 address of the `Assets` token `id`.
+pub fn asset_id_to_address(id: u32) -> H160 {
+	let mut address = [0u8; 20];
+	address[..4].copy_from_slice(&ASSET_ADDRESS_PREFIX);
+	address[4..].copy_from_slice(&u128::from(id).to_be_bytes());
+	H160(address)
+}
+
+/// The `Assets` token id behind an ERC-20 address, if it is one.
+pub fn address_to_asset_id(address: H160) -> Option<u32> {
+	let (prefix, id) = address.as_bytes().split_at(4);
+	if prefix != ASSET_ADDRESS_PREFIX {
+		return None;
+	}
+	u128::from_be_bytes(id.try_into().ok()?).try_into().ok()
+}
+
+pub struct Erc20AssetsPrecompileSet<Runtime>(PhantomData<Runtime>);
+
+impl<Runtime> Default for Erc20AssetsPrecompileSet<Runtime> {
+	fn default() -> Self {
+		Self(PhantomData)
+	}
+}
+
+#[precompile_utils::precompile]
+#[precompile::precompile_set]
+impl<Runtime> Erc20AssetsPrecompileSet<Runtime>
+where
+	Runtime: pallet_assets::Config<AssetsInstance, AssetId = u32, Balance = u128>
+		+ pallet_evm::Config,
+	Runtime::RuntimeCall: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo,
+	Runtime::RuntimeCall: From<pallet_assets::Call<Runtime, AssetsInstance>>,
+	<Runtime::RuntimeCall as Dispatchable>::RuntimeOrigin: From<Option<Runtime::AccountId>>,
+{
+	/// The asset behind the called address, which must exist.
+	#[precompile::discriminant]
+	fn discriminant(address: H160, gas: u64) -> DiscriminantResult<u32> {
+		let Some(asset_id) = address_to_asset_id(address) else {
+			return DiscriminantResult::None(0);
+		};
+		let extra_cost = RuntimeHelper::<Runtime>::db_read_gas_cost();
+		if gas < extra_cost {
+			return DiscriminantResult::OutOfGas;
+		}
+		match Assets::<Runtime>::maybe_total_supply(asset_id) {
+			Some(_) => DiscriminantResult::Some(asset_id, extra_cost),
+			None => DiscriminantResult::None(extra_cost),
+		}
+	}
+
+	#[precompile::public("totalSupply()")]
+	#[precompile::view]
+	fn total_supply(asset_id: u32, handle: &mut impl PrecompileHandle) -> EvmResult<U256> {
+		handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+
+		Ok(Assets::<Runtime>::total_supply(asset_id).into())
+	}
+
+	#[precompile::public("balanceOf(address)")]
+	#[precompile::view]
+	fn balance_of(
+		asset_id: u32,
+		handle: &mut impl PrecompileHandle,
+		who: Address,
+	) -> EvmResult<U256> {
+		handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+
+		let who = Runtime::AddressMapping::into_account_id(who.into());
+		Ok(Assets::<Runtime>::balance(asset_id, who).into())
+	}
+
+	#[precompile::public("allowance(address,address)")]
+	#[precompile::view]
+	fn allowance(
+		asset_id: u32,
+		handle: &mut impl PrecompileHandle,
+		owner: Address,
+		spender: Address,
+	) -> EvmResult<U256> {
+		handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+
+		Ok(Self::allowance_of(asset_id, owner.into(), spender.into()).into())
+	}
+
+	#[precompile::public("name()")]
+	#[precompile::view]
+	fn name(asset_id: u32, handle: &mut impl PrecompileHandle) -> EvmResult<UnboundedBytes> {
+		handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+
+		let name = <Assets<Runtime> as metadata::Inspect<Runtime::AccountId>>::name(asset_id);
+		Ok(name.as_slice().into())
+	}
+
+	#[precompile::public("symbol()")]
+	#[precompile::view]
+	fn symbol(asset_id: u32, handle: &mut impl PrecompileHandle) -> EvmResult<UnboundedBytes> {
+		handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+
+		let symbol = <Assets<Runtime> as metadata::Inspect<Runtime::AccountId>>::symbol(asset_id);
+		Ok(symbol.as_slice().into())
+	}
+
+	#[precompile::public("decimals()")]
+	#[precompile::view]
+	fn decimals(asset_id: u32, handle: &mut impl PrecompileHandle) -> EvmResult<u8> {
+		handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+
+		Ok(<Assets<Runtime> as metadata::Inspect<Runtime::AccountId>>::decimals(asset_id))
+	}
+
+	#[precompile::public("approve(address,uint256)")]
+	fn approve(
+		asset_id: u32,
+		handle: &mut impl PrecompileHandle,
+		spender: Address,
+		value: U256,
+	) -> EvmResult<bool> {
+		handle.record_cost(RuntimeHelper::<Runtime>::db_read_gas_cost())?;
+		handle.record_log_costs_manual(3, 32)?;
+
+		let owner = handle.context().caller;
+		let delegate = Self::lookup_source(spender.into());
+		// `approve_transfer` adds to the current allowance, which ERC-20 replaces.
+		if Self::allowance_of(asset_id, owner, spender.into()) > 0 {
+			let call = pallet_assets::Call::<Runtime, AssetsInstance>::cancel_approval {
+				id: asset_id.into(),
+				delegate: delegate.clone(),
+			};
+			Self::dispatch(handle, call)?;
+		}
+		// Allowances beyond the balance type are unlimited in practice.
+		let amount = value.try_into().unwrap_or(u128::MAX);
+		if amount > 0 {
+			let call = pallet_assets::Call::<Runtime, AssetsInstance>::approve_transfer {
+				id: asset_id.into(),
+				delegate,
+				amount,
+			};
+			Self::dispatch(handle, call)?;
+		}
+
+		log3(
+			handle.context().address,
+			SELECTOR_LOG_APPROVAL,
+			owner,
+			spender.0,
+			solidity::encode_event_data(value),
+		)
+		.record(handle)?;
+		Ok(true)
+	}
+
+	#[precompile::public("transfer(address,uint256)")]
+	fn transfer(
+		asset_id: u32,
+		handle: &mut impl PrecompileHandle,
+		to: Address,
+		value: U256,
+	) -> EvmResult<bool> {
+		handle.record_log_costs_manual(3, 32)?;
+
+		let call = pallet_assets::Call::<Runtime, AssetsInstance>::transfer {
+			id: asset_id.into(),
+			target: Self::lookup_source(to.into()),
+			amount: Self::amount(value)?,
+		};
+		Self::dispatch(handle, call)?;
+
+		log3(
+			handle.context().address,
+			SELECTOR_LOG_TRANSFER,
+			handle.context().caller,
+			to.0,
+			solidity::encode_event_data(value),
+		)
+		.record(handle)?;
+		Ok(true)
+	}
+
+	#[precompile::public("transferFrom(address,address,uint256)")]
+	fn transfer_from(
+		asset_id: u32,
+		handle: &mut impl PrecompileHandle,
+		from: Address,
+		to: Address,
+		value: U256,
+	) -> EvmResult<bool> {
+		handle.record_log_costs_manual(3, 32)?;
+
+		let amount = Self::amount(value)?;
+		if from.0 == handle.context().caller {
+			let call = pallet_assets::Call::<Runtime, AssetsInstance>::transfer {
+				id: asset_id.into(),
+				target: Self::lookup_source(to.into()),
+				amount,
+			};
+			Self::dispatch(handle, call)?;
+		} else {
+			let call = pallet_assets::Call::<Runtime, AssetsInstance>::transfer_approved {
+				id: asset_id.into(),
+				owner: Self::lookup_source(from.into()),
+				destination: Self::lookup_source(to.into()),
+				amount,
+			};
+			Self::dispatch(handle, call)?;
+		}
+
+		log3(
+			handle.context().address,
+			SELECTOR_LOG_TRANSFER,
+			from.0,
+			to.0,
+			solidity::encode_event_data(value),
+		)
+		.record(handle)?;
+		Ok(true)
+	}
+}
+
+impl<Runtime> Erc20AssetsPrecompileSet<Runtime>
+where
+	Runtime: pallet_assets::Config<AssetsInstance, AssetId = u32, Balance = u128>
+		+ pallet_evm::Config,
+	Runtime::RuntimeCall: Dispatchable<PostInfo = PostDispatchInfo> + GetDispatchInfo,
+	Runtime::RuntimeCall: From<pallet_assets::Call<Runtime, AssetsInstance>>,
+	<Runtime::RuntimeCall as Dispatchable>::RuntimeOrigin: From<Option<Runtime::AccountId>>,
+{
+	/// Dispatch `call` on behalf of the caller, which must not be impersonated by a contract
+	/// using `DELEGATECALL`.
+	fn dispatch(
+		handle: &mut impl PrecompileHandle,
+		call: pallet_assets::Call<Runtime, AssetsInstance>,
+	) -> EvmResult {
+		ensure_not_delegate_call(handle)?;
+
+		let origin = Runtime::AddressMapping::into_account_id(handle.context().caller);
+		RuntimeHelper::<Runtime>::try_dispatch(handle, Some(origin).into(), call, 0)?;
+		Ok(())
+	}
+
+	fn allowance_of(asset_id: u32, owner: H160, spender: H160) -> u128 {
+		<Assets<Runtime> as approvals::Inspect<Runtime::AccountId>>::allowance(
+			asset_id,
+			&Runtime::AddressMapping::into_account_id(owner),
+			&Runtime::AddressMapping::into_account_id(spender),
+		)
+	}
+
+	fn lookup_source(address: H160) -> <Runtime::Lookup as StaticLookup>::Source {
+		Runtime::Lookup::unlookup(Runtime::AddressMapping::into_account_id(address))
+	}
+
+	fn amount(value: U256) -> EvmResult<u128> {
+		value
+			.try_into()
+			.map_err(|_| RevertReason::value_is_too_large("balance type").in_field("value").into())
+	}
+}
@@ -0,0 +1,83 @@
+//! secp256r1 signature verification as specified by RIP-7212.
+//!
+//! Input is `hash || r || s || x || y`, 32 bytes each. A valid signature returns `1` as a
+//! 32-byte word, anything else (including malformed input) returns empty output.
+
+use alloc::vec::Vec;
+
+use fp_evm::{ExitSucceed, LinearCostPrecompile, PrecompileFailure};
+use p256::{
+	ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey},
+	elliptic_curve::generic_array::GenericArray,
+	EncodedPoint,
+};
+
+pub struct P256Verify;
+
+impl LinearCostPrecompile for P256Verify {
+	const BASE: u64 = 3450;
+	const WORD: u64 = 0;
+
+	fn execute(input: &[u8], _: u64) -> Result<(ExitSucceed, Vec<u8>), PrecompileFailure> {
+		let output = if verify(input) {
+			let mut word = [0u8; 32];
+			word[31] = 1;
+			word.to_vec()
+		} else {
+			Vec::new()
+		};
+		Ok((ExitSucceed::Returned, output))
+	}
+}
+
+fn verify(input: &[u8]) -> bool {
+	if input.len() != 160 {
+		return false;
+	}
+	let (hash, rest) = input.split_at(32);
+	let (signature, key) = rest.split_at(64);
+
+	let Ok(signature) = Signature::from_slice(signature) else { return false };
+	let point = EncodedPoint::from_untagged_bytes(GenericArray::from_slice(key));
+	let Ok(key) = VerifyingKey::from_encoded_point(&point) else { return false };
+
+	key.verify_prehash(hash, &signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const VALID: &str = "67b80eac006aa0effce1802f4fdd49785f5378ea81f103fb67a404278eb2c9da\
+		2fc8efef905f53570533570aa6531064cccecf5c5259a431a9f35859a204a5ff\
+		786683cb432eaf3a4f126bf9f784d54d1546f2319eee57ece5c3d971895976f0\
+		bd7c73b88b2e9b4ceda62022b2da8be13193a5b56edc26e7df7842e24cd0b5eb\
+		0605ada7bda83ac6a2b80d7e314040fa47ff16b83bac85cedb014451bb7ce71a";
+
+	#[test]
+	fn valid_signature_returns_one() {
+		let input = sp_core::bytes::from_hex(VALID).unwrap();
+		let (_, output) = P256Verify::execute(&input, 0).unwrap();
+
+		let mut expected = [0u8; 32];
+		expected[31] = 1;
+		assert_eq!(output, expected.to_vec());
+	}
+
+	#[test]
+	fn tampered_hash_returns_empty() {
+		let mut input = sp_core::bytes::from_hex(VALID).unwrap();
+		input[0] ^= 1;
+		let (_, output) = P256Verify::execute(&input, 0).unwrap();
+
+		assert!(output.is_empty());
+	}
+
+	#[test]
+	fn wrong_length_returns_empty() {
+		let input = sp_core::bytes::from_hex(VALID).unwrap();
+		let (_, output) = P256Verify::execute(&input[..159], 0).unwrap();
+
+		assert!(output.is_empty());
+	}
+}
@@ -0,0 +1,211 @@
+//! KZG point evaluation precompile of EIP-4844.
+//!
+//! Input is `versioned_hash || z || y || commitment || proof` (32, 32, 32, 48 and 48 bytes).
+//! It proves that the blob committed to by `commitment` evaluates to `y` at `z`, and returns
+//! `FIELD_ELEMENTS_PER_BLOB || BLS_MODULUS` on success.
+
+use alloc::vec::Vec;
+
+use fp_evm::{ExitError, ExitSucceed, LinearCostPrecompile, PrecompileFailure};
+use kzg_rs::{Bytes32, Bytes48, KzgProof, KzgSettings};
+use sp_core::U256;
+
+/// Version byte of a KZG commitment's versioned hash.
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+/// Number of field elements in a blob.
+pub const FIELD_ELEMENTS_PER_BLOB: u64 = 4096;
+/// Modulus of the BLS12-381 scalar field.
+pub const BLS_MODULUS: [u8; 32] = [
+	0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8, 0x05,
+	0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+];
+
+pub struct PointEvaluation;
+
+impl LinearCostPrecompile for PointEvaluation {
+	const BASE: u64 = 50_000;
+	const WORD: u64 = 0;
+
+	fn execute(input: &[u8], _: u64) -> Result<(ExitSucceed, Vec<u8>), PrecompileFailure> {
+		if input.len() != 192 {
+			return Err(failure("invalid input length"));
+		}
+		let versioned_hash = &input[..32];
+		let z = &input[32..64];
+		let y = &input[64..96];
+		let commitment = &input[96..144];
+		let proof = &input[144..192];
+
+		if kzg_to_versioned_hash(commitment) != versioned_hash {
+			return Err(failure("mismatched versioned hash"));
+		}
+		if !verify_kzg_proof(commitment, z, y, proof) {
+			return Err(failure("invalid proof"));
+		}
+
+		let mut output = U256::from(FIELD_ELEMENTS_PER_BLOB).to_big_endian().to_vec();
+		output.extend_from_slice(&BLS_MODULUS);
+		Ok((ExitSucceed::Returned, output))
+	}
+}
+
+fn kzg_to_versioned_hash(commitment: &[u8]) -> [u8; 32] {
+	let mut hash = sp_io::hashing::sha2_256(commitment);
+	hash[0] = VERSIONED_HASH_VERSION_KZG;
+	hash
+}
+
+fn verify_kzg_proof(commitment: &[u8], z: &[u8], y: &[u8], proof: &[u8]) -> bool {
+	let (Ok(commitment), Ok(z), Ok(y), Ok(proof)) = (
+		Bytes48::from_slice(commitment),
+		Bytes32::from_slice(z),
+		Bytes32::from_slice(y),
+		Bytes48::from_slice(proof),
+	) else {
+		return false;
+	};
+	let Ok(settings) = kzg_settings() else { return false };
+
+	KzgProof::verify_kzg_proof(&commitment, &z, &y, &proof, &settings).unwrap_or(false)
+}
+
+/// The trusted setup of EIP-4844.
+///
+/// `kzg-rs` compiles the setup in its precomputed, in-memory form: loading it only points at
+/// the embedded points, without parsing or decompressing them. Each call thus costs the same,
+/// whether or not an earlier call of the block ran in the same wasm instance, and the flat gas
+/// of EIP-4844 covers it (see the `benchmark` test).
+fn kzg_settings() -> Result<KzgSettings, kzg_rs::KzgError> {
+	KzgSettings::load_trusted_setup_file()
+}
+
+fn failure(reason: &'static str) -> PrecompileFailure {
+	PrecompileFailure::Error { exit_status: ExitError::Other(reason.into()) }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// The zero polynomial: its commitment and proof are both the point at infinity and it
+	/// evaluates to zero everywhere.
+	fn zero_polynomial_input() -> Vec<u8> {
+		let mut infinity = [0u8; 48];
+		infinity[0] = 0xc0;
+
+		let mut input = kzg_to_versioned_hash(&infinity).to_vec();
+		input.extend_from_slice(&[0x2a; 32]); // z
+		input.extend_from_slice(&[0u8; 32]); // y
+		input.extend_from_slice(&infinity); // commitment
+		input.extend_from_slice(&infinity); // proof
+		input
+	}
+
+	#[test]
+	fn versioned_hash_of_infinity() {
+		let mut infinity = [0u8; 48];
+		infinity[0] = 0xc0;
+
+		assert_eq!(
+			sp_core::bytes::to_hex(&kzg_to_versioned_hash(&infinity), false),
+			"0x010657f37554c781402a22917dee2f75def7ab966d7b770905398eba3c444014",
+		);
+	}
+
+	#[test]
+	fn valid_proof_returns_blob_parameters() {
+		let (_, output) = PointEvaluation::execute(&zero_polynomial_input(), 0).unwrap();
+
+		assert_eq!(&output[..32], &U256::from(FIELD_ELEMENTS_PER_BLOB).to_big_endian());
+		assert_eq!(&output[32..], &BLS_MODULUS);
+	}
+
+	/// The point evaluation vector of the EIP-4844 reference tests.
+	fn reference_input() -> Vec<u8> {
+		let commitment = hex_literal::hex!(
+			"8f59a8d2a1a625a17f3fea0fe5eb8c896db3764f3185481b"
+			"c22f91b4aaffcca25f26936857bc3a7c2539ea8ec3a952b7"
+		);
+		let mut input = kzg_to_versioned_hash(&commitment).to_vec();
+		input.extend_from_slice(&hex_literal::hex!(
+			"73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000000"
+		));
+		input.extend_from_slice(&hex_literal::hex!(
+			"1522a4a7f34e1ea350ae07c29c96c7e79655aa926122e95fe69fcbd932ca49e9"
+		));
+		input.extend_from_slice(&commitment);
+		input.extend_from_slice(&hex_literal::hex!(
+			"a62ad71d14c5719385c0686f1871430475bf3a00f0aa3f7b"
+			"8dd99a9abc2160744faf0070725e00b60ad9a026a15b1a8c"
+		));
+		input
+	}
+
+	#[test]
+	fn reference_vector() {
+		let input = reference_input();
+		assert_eq!(
+			input[..32],
+			hex_literal::hex!("01e798154708fe7789429634053cbf9f99b619f9f084048927333fce637f549b")
+		);
+
+		let (_, output) = PointEvaluation::execute(&input, 0).unwrap();
+		assert_eq!(&output[..32], &U256::from(FIELD_ELEMENTS_PER_BLOB).to_big_endian());
+		assert_eq!(&output[32..], &BLS_MODULUS);
+
+		let mut tampered = input;
+		tampered[95] ^= 1; // y
+		assert!(PointEvaluation::execute(&tampered, 0).is_err());
+	}
+
+	/// Time a call, loading the trusted setup included, against the weight of its gas.
+	///
+	/// `cargo test --release -p fintradex-runtime point_evaluation -- --ignored --nocapture`
+	#[test]
+	#[ignore = "benchmark"]
+	fn benchmark() {
+		use frame_support::traits::Get;
+		use std::time::Instant;
+
+		const RUNS: u32 = 100;
+		let input = reference_input();
+
+		let started = Instant::now();
+		for _ in 0..RUNS {
+			kzg_settings().unwrap();
+		}
+		let load = started.elapsed() / RUNS;
+		let started = Instant::now();
+		for _ in 0..RUNS {
+			PointEvaluation::execute(&input, 0).unwrap();
+		}
+		let call = started.elapsed() / RUNS;
+
+		let weight_per_gas = <crate::Runtime as pallet_evm::Config>::WeightPerGas::get();
+		// Weight is in picoseconds.
+		let budget = weight_per_gas.ref_time() * PointEvaluation::BASE / 1_000;
+		println!("setup load: {load:?}, call: {call:?}, budget of the gas: {budget} ns");
+		assert!((call.as_nanos() as u64) < budget, "the gas of a call does not cover it");
+	}
+
+	#[test]
+	fn wrong_evaluation_fails() {
+		let mut input = zero_polynomial_input();
+		input[95] = 1; // y = 1
+
+		assert!(PointEvaluation::execute(&input, 0).is_err());
+	}
+
+	#[test]
+	fn mismatched_versioned_hash_fails() {
+		let mut input = zero_polynomial_input();
+		input[1] ^= 1;
+
+		assert!(PointEvaluation::execute(&input, 0).is_err());
+	}
+
+	#[test]
+	fn wrong_length_fails() {
+		assert!(PointEvaluation::execute(&zero_polynomial_input()[..191], 0).is_err());
+	}
+}
@@ -0,0 +1,727 @@
+//! Tests of the FintradeX precompiles, run against the runtime with the development genesis.
+
+use alloc::{boxed::Box, rc::Rc};
+use core::cell::RefCell;
+
+use fp_evm::{Context, Log};
+use frame_support::{
+	assert_ok,
+	traits::{
+		fungible::{Inspect as _, Mutate as _},
+		fungibles::{metadata, Inspect as _, Mutate as _},
+	},
+};
+use pallet_asset_conversion::PoolLocator;
+use pallet_evm::AddressMapping;
+use precompile_utils::{evm::logs::log3, prelude::*, testing::*};
+use sp_core::{Pair, H160, H256, U256};
+
+use super::*;
+use crate::{new_test_ext, AccountId, Assets, Balances, PoolAssets, Runtime, RuntimeOrigin, UNIT};
+
+/// The well-known dev key Alith.
+const ALITH: H160 = H160(hex_literal::hex!("f24ff3a9cf04c71dbc94d0b566f7a27b94566cac"));
+/// A contract delegating calls to the precompiles.
+const CONTRACT: H160 = H160([0xcc; 20]);
+/// Asset `ALT1` of the development genesis.
+const ASSET: u32 = 1;
+
+fn precompiles() -> FrontierPrecompiles<Runtime> {
+	FrontierPrecompiles::new()
+}
+
+/// The native account of the EVM address `address`.
+fn account(address: H160) -> AccountId {
+	<Runtime as pallet_evm::Config>::AddressMapping::into_account_id(address)
+}
+
+/// Run `input` against the precompile at `code_address` from the context of `CONTRACT`, as
+/// `DELEGATECALL` would.
+fn delegate_call(code_address: H160, input: Vec<u8>) -> PrecompileResult {
+	let mut handle = MockHandle::new(
+		code_address,
+		Context { address: CONTRACT, caller: ALITH, apparent_value: U256::zero() },
+	);
+	handle.input = input;
+	precompiles().execute(&mut handle).expect("a precompile lives at the address; qed")
+}
+
+fn is_delegate_call_rejection(result: PrecompileResult) -> bool {
+	matches!(
+		result,
+		Err(PrecompileFailure::Revert { output, .. })
+			if decode_revert_message(&output) == b"cannot be called with DELEGATECALL or CALLCODE"
+	)
+}
+
+// AssetConversion
+
+type AssetConversionCall = asset_conversion::AssetConversionPrecompileCall<Runtime>;
+
+fn asset_conversion() -> H160 {
+	hash(2048)
+}
+
+fn native() -> Address {
+	Address(NATIVE_ASSET_ADDRESS)
+}
+
+fn asset() -> Address {
+	Address(asset_id_to_address(ASSET))
+}
+
+/// Fund Alith and open the native/`ASSET` pool.
+fn create_pool() -> AccountId {
+	let who = account(ALITH);
+	Balances::set_balance(&who, 1_000_000 * UNIT);
+	assert_ok!(Assets::mint_into(ASSET, &who, 1_000 * UNIT));
+	assert_ok!(crate::AssetConversion::create_pool(
+		RuntimeOrigin::signed(who.clone()),
+		Box::new(NativeOrWithId::Native),
+		Box::new(NativeOrWithId::WithId(ASSET)),
+	));
+	who
+}
+
+fn add_liquidity(native_amount: u128, asset_amount: u128) -> Vec<u8> {
+	AssetConversionCall::add_liquidity {
+		asset1: native(),
+		asset2: asset(),
+		amount1_desired: native_amount.into(),
+		amount2_desired: asset_amount.into(),
+		amount1_min: U256::zero(),
+		amount2_min: U256::zero(),
+		mint_to: Address(ALITH),
+	}
+	.into()
+}
+
+fn lp_balance(who: &AccountId) -> u128 {
+	let pool_id = <Runtime as pallet_asset_conversion::Config>::PoolLocator::pool_id(
+		&NativeOrWithId::Native,
+		&NativeOrWithId::WithId(ASSET),
+	)
+	.expect("native can be paired with an asset; qed");
+	let lp_token = pallet_asset_conversion::Pools::<Runtime>::get(pool_id)
+		.expect("the pool was created; qed")
+		.lp_token;
+	PoolAssets::balance(lp_token, who)
+}
+
+#[test]
+fn add_liquidity_mints_lp_tokens_to_the_caller() {
+	new_test_ext().execute_with(|| {
+		let who = create_pool();
+
+		precompiles()
+			.prepare_test(ALITH, asset_conversion(), add_liquidity(1_000 * UNIT, 100 * UNIT))
+			.execute_returns(());
+
+		assert!(lp_balance(&who) > 0);
+		precompiles()
+			.prepare_test(
+				ALITH,
+				asset_conversion(),
+				AssetConversionCall::get_reserves { asset1: native(), asset2: asset() },
+			)
+			.execute_returns((U256::from(1_000 * UNIT), U256::from(100 * UNIT)));
+	})
+}
+
+#[test]
+fn swap_exact_tokens_debits_the_caller() {
+	new_test_ext().execute_with(|| {
+		let who = create_pool();
+		precompiles()
+			.prepare_test(ALITH, asset_conversion(), add_liquidity(1_000 * UNIT, 100 * UNIT))
+			.execute_returns(());
+		let native_before = Balances::balance(&who);
+		let asset_before = Assets::balance(ASSET, &who);
+
+		precompiles()
+			.prepare_test(
+				ALITH,
+				asset_conversion(),
+				AssetConversionCall::swap_exact_tokens_for_tokens {
+					path: vec![native(), asset()].into(),
+					amount_in: (10 * UNIT).into(),
+					amount_out_min: U256::one(),
+					send_to: Address(ALITH),
+					keep_alive: true,
+				},
+			)
+			.execute_returns(());
+
+		assert_eq!(Balances::balance(&who), native_before - 10 * UNIT);
+		assert!(Assets::balance(ASSET, &who) > asset_before);
+	})
+}
+
+#[test]
+fn swap_reverts_below_the_minimum_output() {
+	new_test_ext().execute_with(|| {
+		create_pool();
+		precompiles()
+			.prepare_test(ALITH, asset_conversion(), add_liquidity(1_000 * UNIT, 100 * UNIT))
+			.execute_returns(());
+
+		precompiles()
+			.prepare_test(
+				ALITH,
+				asset_conversion(),
+				AssetConversionCall::swap_exact_tokens_for_tokens {
+					path: vec![native(), asset()].into(),
+					amount_in: (10 * UNIT).into(),
+					amount_out_min: (10 * UNIT).into(),
+					send_to: Address(ALITH),
+					keep_alive: true,
+				},
+			)
+			.execute_reverts(|output| !output.is_empty());
+	})
+}
+
+#[test]
+fn remove_liquidity_burns_lp_tokens() {
+	new_test_ext().execute_with(|| {
+		let who = create_pool();
+		precompiles()
+			.prepare_test(ALITH, asset_conversion(), add_liquidity(1_000 * UNIT, 100 * UNIT))
+			.execute_returns(());
+		let lp_tokens = lp_balance(&who);
+
+		precompiles()
+			.prepare_test(
+				ALITH,
+				asset_conversion(),
+				AssetConversionCall::remove_liquidity {
+					asset1: native(),
+					asset2: asset(),
+					lp_token_burn: (lp_tokens / 2).into(),
+					amount1_min_receive: U256::zero(),
+					amount2_min_receive: U256::zero(),
+					withdraw_to: Address(ALITH),
+				},
+			)
+			.execute_returns(());
+
+		assert_eq!(lp_balance(&who), lp_tokens - lp_tokens / 2);
+	})
+}
+
+#[test]
+fn unknown_token_address_reverts() {
+	new_test_ext().execute_with(|| {
+		create_pool();
+
+		precompiles()
+			.prepare_test(
+				ALITH,
+				asset_conversion(),
+				AssetConversionCall::get_reserves { asset1: native(), asset2: Address(CONTRACT) },
+			)
+			.execute_reverts(|output| output.ends_with(b"unknown asset address"));
+	})
+}
+
+#[test]
+fn asset_conversion_rejects_delegate_call() {
+	new_test_ext().execute_with(|| {
+		let who = create_pool();
+
+		assert!(is_delegate_call_rejection(delegate_call(
+			asset_conversion(),
+			add_liquidity(1_000 * UNIT, 100 * UNIT),
+		)));
+		assert_eq!(lp_balance(&who), 0);
+	})
+}
+
+// ERC-20
+
+type Erc20Call = erc20::Erc20AssetsPrecompileSetCall<Runtime>;
+
+/// The well-known dev key Baltathar.
+const BALTATHAR: H160 = H160(hex_literal::hex!("3cd0a705a2dc65e5b1e1205896baa2be8a07c6e0"));
+
+/// Fund Alith with `ASSET` and the native deposit of an approval.
+fn fund_alith() -> AccountId {
+	let who = account(ALITH);
+	Balances::set_balance(&who, 1_000 * UNIT);
+	assert_ok!(Assets::mint_into(ASSET, &who, 100 * UNIT));
+	who
+}
+
+fn transfer_log(from: H160, to: H160, value: u128) -> Log {
+	log3(
+		asset().0,
+		erc20::SELECTOR_LOG_TRANSFER,
+		from,
+		to,
+		solidity::encode_event_data(U256::from(value)),
+	)
+}
+
+#[test]
+fn swap_tokens_are_callable_erc20_precompiles() {
+	new_test_ext().execute_with(|| {
+		let is_precompile = |address| {
+			matches!(
+				precompiles().is_precompile(address, u64::MAX),
+				IsPrecompileResult::Answer { is_precompile: true, .. }
+			)
+		};
+		assert!(is_precompile(asset().0));
+		assert!(!is_precompile(asset_id_to_address(ASSET + 1_000)));
+		assert_eq!(address_to_asset_id(asset().0), Some(ASSET));
+
+		precompiles()
+			.prepare_test(ALITH, asset().0, Erc20Call::decimals {})
+			.execute_returns(<Assets as metadata::Inspect<AccountId>>::decimals(ASSET));
+	})
+}
+
+#[test]
+fn erc20_transfer_moves_assets() {
+	new_test_ext().execute_with(|| {
+		let who = fund_alith();
+
+		precompiles()
+			.prepare_test(
+				ALITH,
+				asset().0,
+				Erc20Call::transfer { to: Address(BALTATHAR), value: (10 * UNIT).into() },
+			)
+			.expect_log(transfer_log(ALITH, BALTATHAR, 10 * UNIT))
+			.execute_returns(true);
+
+		assert_eq!(Assets::balance(ASSET, &who), 90 * UNIT);
+		precompiles()
+			.prepare_test(ALITH, asset().0, Erc20Call::balance_of { who: Address(BALTATHAR) })
+			.execute_returns(U256::from(10 * UNIT));
+	})
+}
+
+#[test]
+fn erc20_transfer_from_spends_the_allowance() {
+	new_test_ext().execute_with(|| {
+		let who = fund_alith();
+		let allowance = || {
+			Erc20Call::allowance { owner: Address(ALITH), spender: Address(BALTATHAR) }
+		};
+
+		precompiles()
+			.prepare_test(
+				ALITH,
+				asset().0,
+				Erc20Call::approve { spender: Address(BALTATHAR), value: (30 * UNIT).into() },
+			)
+			.execute_returns(true);
+		// A second approval replaces the first one.
+		precompiles()
+			.prepare_test(
+				ALITH,
+				asset().0,
+				Erc20Call::approve { spender: Address(BALTATHAR), value: (20 * UNIT).into() },
+			)
+			.execute_returns(true);
+		precompiles()
+			.prepare_test(ALITH, asset().0, allowance())
+			.execute_returns(U256::from(20 * UNIT));
+
+		precompiles()
+			.prepare_test(
+				BALTATHAR,
+				asset().0,
+				Erc20Call::transfer_from {
+					from: Address(ALITH),
+					to: Address(BALTATHAR),
+					value: (5 * UNIT).into(),
+				},
+			)
+			.expect_log(transfer_log(ALITH, BALTATHAR, 5 * UNIT))
+			.execute_returns(true);
+
+		assert_eq!(Assets::balance(ASSET, &who), 95 * UNIT);
+		precompiles()
+			.prepare_test(ALITH, asset().0, allowance())
+			.execute_returns(U256::from(15 * UNIT));
+	})
+}
+
+#[test]
+fn erc20_rejects_delegate_call() {
+	new_test_ext().execute_with(|| {
+		let who = fund_alith();
+
+		assert!(is_delegate_call_rejection(delegate_call(
+			asset().0,
+			Erc20Call::transfer { to: Address(CONTRACT), value: UNIT.into() }.into(),
+		)));
+		assert_eq!(Assets::balance(ASSET, &who), 100 * UNIT);
+	})
+}
+
+// Batch
+
+type BatchCall = batch::BatchPrecompileCall<Runtime>;
+
+fn batch() -> H160 {
+	hash(2049)
+}
+
+/// Three subcalls, the second of which reverts.
+fn subcalls() -> [Address; 3] {
+	[Address(H160::repeat_byte(1)), Address(H160::repeat_byte(2)), Address(H160::repeat_byte(3))]
+}
+
+/// A subcall handle reverting calls to the second subcall target, and the targets it was
+/// called with.
+fn subcall_handle() -> (impl FnMut(Subcall) -> SubcallOutput + 'static, Rc<RefCell<Vec<H160>>>) {
+	let called = Rc::new(RefCell::new(Vec::new()));
+	let handle = {
+		let called = called.clone();
+		move |Subcall { address, context, .. }: Subcall| {
+			assert_eq!(context.caller, ALITH, "subcalls keep the batch caller");
+			called.borrow_mut().push(address);
+			if address == subcalls()[1].0 {
+				SubcallOutput::revert()
+			} else {
+				SubcallOutput::succeed()
+			}
+		}
+	};
+	(handle, called)
+}
+
+fn batch_input(mode: batch::Mode) -> Vec<u8> {
+	let to = Vec::from(subcalls()).into();
+	match mode {
+		batch::Mode::BatchSome => BatchCall::batch_some {
+			to,
+			value: vec![].into(),
+			call_data: vec![].into(),
+			gas_limit: vec![].into(),
+		},
+		batch::Mode::BatchSomeUntilFailure => BatchCall::batch_some_until_failure {
+			to,
+			value: vec![].into(),
+			call_data: vec![].into(),
+			gas_limit: vec![].into(),
+		},
+		batch::Mode::BatchAll => BatchCall::batch_all {
+			to,
+			value: vec![].into(),
+			call_data: vec![].into(),
+			gas_limit: vec![].into(),
+		},
+	}
+	.into()
+}
+
+#[test]
+fn batch_some_skips_failing_subcalls() {
+	new_test_ext().execute_with(|| {
+		let (handle, called) = subcall_handle();
+
+		precompiles()
+			.prepare_test(ALITH, batch(), batch_input(batch::Mode::BatchSome))
+			.with_subcall_handle(handle)
+			.execute_returns(());
+
+		assert_eq!(*called.borrow(), subcalls().map(|address| address.0));
+	})
+}
+
+#[test]
+fn batch_some_until_failure_stops_at_the_failing_subcall() {
+	new_test_ext().execute_with(|| {
+		let (handle, called) = subcall_handle();
+
+		precompiles()
+			.prepare_test(ALITH, batch(), batch_input(batch::Mode::BatchSomeUntilFailure))
+			.with_subcall_handle(handle)
+			.execute_returns(());
+
+		let expected: Vec<_> = subcalls()[..2].iter().map(|address| address.0).collect();
+		assert_eq!(*called.borrow(), expected);
+	})
+}
+
+#[test]
+fn batch_all_reverts_on_a_failing_subcall() {
+	new_test_ext().execute_with(|| {
+		let (handle, called) = subcall_handle();
+
+		precompiles()
+			.prepare_test(ALITH, batch(), batch_input(batch::Mode::BatchAll))
+			.with_subcall_handle(handle)
+			.execute_reverts(|_| true);
+
+		assert_eq!(called.borrow().len(), 2);
+	})
+}
+
+#[test]
+fn batch_rejects_delegate_call() {
+	new_test_ext().execute_with(|| {
+		assert!(is_delegate_call_rejection(delegate_call(
+			batch(),
+			batch_input(batch::Mode::BatchAll),
+		)));
+	})
+}
+
+// CallPermit
+
+type CallPermitCall = call_permit::CallPermitPrecompileCall<Runtime>;
+
+/// Private key of Alith.
+const ALITH_KEY: [u8; 32] =
+	hex_literal::hex!("5fb92d6e98884f76de468fa3f6278f8807c48bebc13595d45af5bdc4da702133");
+/// A relayer submitting permits of Alith.
+const RELAYER: H160 = H160([0xaa; 20]);
+/// Timestamp of the tests, in seconds.
+const NOW: u64 = 1_700_000_000;
+
+fn call_permit() -> H160 {
+	hash(2050)
+}
+
+/// A permit of Alith for a call to `CONTRACT`, signed with `key`.
+fn permit(key: [u8; 32], nonce: U256, deadline: u64) -> Vec<u8> {
+	let data = b"call data".to_vec();
+	let digest = call_permit::CallPermitPrecompile::<Runtime>::generate_permit(
+		call_permit(),
+		ALITH,
+		CONTRACT,
+		U256::zero(),
+		&data,
+		100_000,
+		nonce,
+		deadline.into(),
+	);
+	let signature = sp_core::ecdsa::Pair::from_seed(&key).sign_prehashed(&digest).0;
+
+	CallPermitCall::dispatch {
+		from: Address(ALITH),
+		to: Address(CONTRACT),
+		value: U256::zero(),
+		data: data.into(),
+		gas_limit: 100_000,
+		deadline: deadline.into(),
+		v: signature[64] + 27,
+		r: H256::from_slice(&signature[..32]),
+		s: H256::from_slice(&signature[32..64]),
+	}
+	.into()
+}
+
+fn nonce_of_alith() -> U256 {
+	call_permit::NoncesStorage::get(ALITH)
+}
+
+fn new_permit_test_ext() -> sp_io::TestExternalities {
+	let mut ext = new_test_ext();
+	ext.execute_with(|| pallet_timestamp::Now::<Runtime>::put(NOW * 1000));
+	ext
+}
+
+#[test]
+fn permit_dispatches_as_the_signer() {
+	new_permit_test_ext().execute_with(|| {
+		precompiles()
+			.prepare_test(RELAYER, call_permit(), permit(ALITH_KEY, U256::zero(), NOW + 60))
+			.with_subcall_handle(|Subcall { address, context, input, .. }| {
+				assert_eq!(address, CONTRACT);
+				assert_eq!(context.caller, ALITH);
+				assert_eq!(input, b"call data");
+				SubcallOutput::succeed()
+			})
+			.execute_returns(UnboundedBytes::from(Vec::new()));
+
+		assert_eq!(nonce_of_alith(), U256::one());
+	})
+}
+
+#[test]
+fn permit_cannot_be_replayed() {
+	new_permit_test_ext().execute_with(|| {
+		let input = permit(ALITH_KEY, U256::zero(), NOW + 60);
+		precompiles()
+			.prepare_test(RELAYER, call_permit(), input.clone())
+			.with_subcall_handle(|_| SubcallOutput::succeed())
+			.execute_returns(UnboundedBytes::from(Vec::new()));
+
+		precompiles()
+			.prepare_test(RELAYER, call_permit(), input)
+			.with_subcall_handle(|_| panic!("a replayed permit must not dispatch"))
+			.execute_reverts(|output| output == b"invalid permit");
+		assert_eq!(nonce_of_alith(), U256::one());
+	})
+}
+
+#[test]
+fn permit_of_another_signer_is_invalid() {
+	new_permit_test_ext().execute_with(|| {
+		precompiles()
+			.prepare_test(RELAYER, call_permit(), permit([1; 32], U256::zero(), NOW + 60))
+			.with_subcall_handle(|_| panic!("a forged permit must not dispatch"))
+			.execute_reverts(|output| output == b"invalid permit");
+		assert_eq!(nonce_of_alith(), U256::zero());
+	})
+}
+
+#[test]
+fn expired_permit_is_rejected() {
+	new_permit_test_ext().execute_with(|| {
+		precompiles()
+			.prepare_test(RELAYER, call_permit(), permit(ALITH_KEY, U256::zero(), NOW - 1))
+			.with_subcall_handle(|_| panic!("an expired permit must not dispatch"))
+			.execute_reverts(|output| output == b"permit expired");
+		assert_eq!(nonce_of_alith(), U256::zero());
+	})
+}
+
+#[test]
+fn call_permit_rejects_delegate_call() {
+	new_permit_test_ext().execute_with(|| {
+		assert!(is_delegate_call_rejection(delegate_call(
+			call_permit(),
+			permit(ALITH_KEY, U256::zero(), NOW + 60),
+		)));
+		assert_eq!(nonce_of_alith(), U256::zero());
+	})
+}
+
+// Ethereum precompiles, against the reference vectors of their EIP.
+
+fn call(address: H160, input: Vec<u8>) -> PrecompileResult {
+	let mut handle = MockHandle::new(
+		address,
+		Context { address, caller: ALITH, apparent_value: U256::zero() },
+	);
+	handle.input = input;
+	precompiles().execute(&mut handle).expect("a precompile lives at the address; qed")
+}
+
+fn output(result: PrecompileResult) -> Vec<u8> {
+	result.expect("the precompile succeeds").output
+}
+
+const G1: [u8; 64] = hex_literal::hex!(
+	"0000000000000000000000000000000000000000000000000000000000000001"
+	"0000000000000000000000000000000000000000000000000000000000000002"
+);
+/// The negation of `G1`.
+const MINUS_G1: [u8; 64] = hex_literal::hex!(
+	"0000000000000000000000000000000000000000000000000000000000000001"
+	"30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45"
+);
+const TWO_G1: [u8; 64] = hex_literal::hex!(
+	"030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3"
+	"15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4"
+);
+const THREE_G1: [u8; 64] = hex_literal::hex!(
+	"0769bf9ac56bea3ff40232bcb1b6bd159315d84715b8e679f2d355961915abf0"
+	"2ab799bee0489429554fdb7c8d086475319e63b40b9c5b57cdf1ff3dd9fe2261"
+);
+/// The generator of G2, imaginary parts first.
+const G2: [u8; 128] = hex_literal::hex!(
+	"198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2"
+	"1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed"
+	"090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b"
+	"12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa"
+);
+
+fn u256_word(value: u64) -> [u8; 32] {
+	U256::from(value).to_big_endian()
+}
+
+#[test]
+fn bn128_add() {
+	assert_eq!(output(call(hash(6), [G1, G1].concat())), TWO_G1);
+	assert_eq!(output(call(hash(6), [G1, TWO_G1].concat())), THREE_G1);
+	// Missing input is zero, the point at infinity.
+	assert_eq!(output(call(hash(6), vec![])), [0u8; 64]);
+	assert_eq!(output(call(hash(6), [G1, MINUS_G1].concat())), [0u8; 64]);
+	// (1, 3) is not on the curve.
+	let mut invalid = G1;
+	invalid[63] = 3;
+	assert!(call(hash(6), [invalid, G1].concat()).is_err());
+}
+
+#[test]
+fn bn128_mul() {
+	assert_eq!(output(call(hash(7), [&G1[..], &u256_word(2)].concat())), TWO_G1);
+	assert_eq!(output(call(hash(7), [&G1[..], &u256_word(3)].concat())), THREE_G1);
+	assert_eq!(output(call(hash(7), [&G1[..], &u256_word(0)].concat())), [0u8; 64]);
+}
+
+#[test]
+fn bn128_pairing() {
+	let pairs = |pairs: &[([u8; 64], [u8; 128])]| -> Vec<u8> {
+		pairs.iter().flat_map(|(g1, g2)| g1.iter().chain(g2).copied()).collect()
+	};
+
+	// The empty product is one.
+	assert_eq!(output(call(hash(8), vec![])), u256_word(1));
+	// e(G1, G2) * e(-G1, G2) = 1
+	assert_eq!(output(call(hash(8), pairs(&[(G1, G2), (MINUS_G1, G2)]))), u256_word(1));
+	// e(G1, G2) * e(G1, G2) != 1
+	assert_eq!(output(call(hash(8), pairs(&[(G1, G2), (G1, G2)]))), u256_word(0));
+	// Input is a multiple of 192 bytes.
+	assert!(call(hash(8), pairs(&[(G1, G2)])[..191].to_vec()).is_err());
+}
+
+/// Input of Blake2F compressing "abc" as the single block of BLAKE2b-512.
+fn blake2f_input(rounds: u32, final_block: u8) -> Vec<u8> {
+	let mut input = rounds.to_be_bytes().to_vec();
+	input.extend_from_slice(&hex_literal::hex!(
+		"48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5"
+		"d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b"
+	));
+	let mut message = [0u8; 128];
+	message[..3].copy_from_slice(b"abc");
+	input.extend_from_slice(&message);
+	input.extend_from_slice(&3u128.to_le_bytes());
+	input.push(final_block);
+	input
+}
+
+#[test]
+fn blake2f() {
+	// Vectors 4 to 7 of EIP-152.
+	assert_eq!(
+		output(call(hash(9), blake2f_input(0, 1))),
+		hex_literal::hex!(
+			"08c9bcf367e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5"
+			"d282e6ad7f520e511f6c3e2b8c68059b9442be0454267ce079217e1319cde05b"
+		)
+	);
+	assert_eq!(
+		output(call(hash(9), blake2f_input(12, 1))),
+		hex_literal::hex!(
+			"ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1"
+			"7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+		)
+	);
+	assert_eq!(
+		output(call(hash(9), blake2f_input(12, 0))),
+		hex_literal::hex!(
+			"75ab69d3190a562c51aef8d88f1c2775876944407270c42c9844252c26d28752"
+			"98743e7f6d5ea2f2d3e8d226039cd31b4e426ac4f2d3d666a610c2116fde4735"
+		)
+	);
+	assert_eq!(
+		output(call(hash(9), blake2f_input(1, 1))),
+		hex_literal::hex!(
+			"b63a380cb2897d521994a85234ee2c181b5f844d2c624c002677e9703449d2fb"
+			"a551b3a8333bcdf5f2f7e08993d53923de3d64fcc68c034e717b9293fed7a421"
+		)
+	);
+	// Vectors 1 to 3: wrong length and invalid final block flag.
+	assert!(call(hash(9), blake2f_input(12, 1)[..212].to_vec()).is_err());
+	assert!(call(hash(9), [&blake2f_input(12, 1)[..], &[0]].concat()).is_err());
+	assert!(call(hash(9), blake2f_input(12, 2)).is_err());
+}
@@ -0,0 +1,325 @@
+//! Multi-block EVM simulation for `eth_simulateV1`.
+//!
+//! [`SimulateRuntimeApi::simulate`] runs blocks of calls in sequence on top of the state it is
+//! called at. Each block first applies its block and state overrides, then its calls through
+//! `pallet_evm`'s `Runner`, every call seeing the effects of the ones before it. The runtime
+//! API instance is thrown away afterwards, so nothing is persisted.
+//!
+//! The calls of a block share its gas limit and all the calls share the gas cap of the request,
+//! a call without a gas limit being given what remains of both.
+
+use alloc::{format, string::String, vec::Vec};
+
+use codec::{Decode, Encode};
+use fp_evm::Log;
+use frame_support::traits::{fungible::Mutate, Get};
+use pallet_evm::{AddressMapping, Runner};
+use scale_info::TypeInfo;
+use sp_core::{H160, H256, U256};
+
+use crate::{Balance, Balances, BlockNumber, Nonce, Runtime, MILLI_SECS_PER_BLOCK};
+
+/// Maximum number of slots an account override may set, and a `state` override may clear.
+pub const MAX_OVERRIDE_SLOTS: u32 = 1024;
+
+/// Replacement of parts of an account's state.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Encode, Decode, TypeInfo)]
+pub struct AccountOverride {
+	pub address: H160,
+	pub balance: Option<Balance>,
+	pub nonce: Option<Nonce>,
+	pub code: Option<Vec<u8>>,
+	/// Replaces the whole storage of the account, which must hold at most
+	/// [`MAX_OVERRIDE_SLOTS`] slots.
+	pub state: Option<Vec<(H256, H256)>>,
+	/// Replaces the given slots only.
+	pub state_diff: Option<Vec<(H256, H256)>>,
+}
+
+/// Replacement of the environment of a simulated block.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Encode, Decode, TypeInfo)]
+pub struct BlockOverrides {
+	/// Must be above the number of the previous block.
+	pub number: Option<BlockNumber>,
+	/// Timestamp in seconds.
+	pub time: Option<u64>,
+	pub base_fee_per_gas: Option<U256>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default, Encode, Decode, TypeInfo)]
+pub struct SimulatedCall {
+	pub from: H160,
+	/// `None` deploys `data` as init code.
+	pub to: Option<H160>,
+	pub data: Vec<u8>,
+	pub value: U256,
+	/// Defaults to the gas left in the block and in the request.
+	pub gas_limit: Option<u64>,
+	pub max_fee_per_gas: Option<U256>,
+	pub max_priority_fee_per_gas: Option<U256>,
+	pub nonce: Option<U256>,
+	pub access_list: Vec<(H160, Vec<H256>)>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default, Encode, Decode, TypeInfo)]
+pub struct SimulatedBlock {
+	pub block_overrides: BlockOverrides,
+	pub state_overrides: Vec<AccountOverride>,
+	pub calls: Vec<SimulatedCall>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub struct CallResult {
+	pub success: bool,
+	/// The output of a call, or the deployed code of a creation.
+	pub return_data: Vec<u8>,
+	pub gas_used: U256,
+	pub logs: Vec<Log>,
+	/// Why the call failed, `None` if it succeeded.
+	pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub enum SimulateError {
+	/// The `state` override of the account would clear more than [`MAX_OVERRIDE_SLOTS`] slots.
+	TooManySlots(H160),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode, TypeInfo)]
+pub struct BlockResult {
+	pub number: BlockNumber,
+	/// Timestamp in seconds.
+	pub timestamp: u64,
+	pub base_fee_per_gas: U256,
+	pub gas_used: U256,
+	pub calls: Vec<CallResult>,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Simulates sequences of EVM calls with state and block overrides.
+	pub trait SimulateRuntimeApi {
+		/// Run `blocks` in order on top of the current state.
+		///
+		/// With `validation`, calls are checked like transactions: nonces, balances and fees.
+		/// The calls use at most `gas_cap` gas altogether.
+		fn simulate(
+			blocks: Vec<SimulatedBlock>,
+			validation: bool,
+			gas_cap: u64,
+		) -> Result<Vec<BlockResult>, SimulateError>;
+	}
+}
+
+pub fn simulate(
+	blocks: Vec<SimulatedBlock>,
+	validation: bool,
+	gas_cap: u64,
+) -> Result<Vec<BlockResult>, SimulateError> {
+	let mut number = frame_system::Pallet::<Runtime>::block_number();
+	let mut timestamp = pallet_timestamp::Now::<Runtime>::get() / 1000;
+	let block_gas_limit = <Runtime as pallet_evm::Config>::BlockGasLimit::get().low_u64();
+	let mut gas_left = gas_cap;
+
+	blocks
+		.into_iter()
+		.map(|block| {
+			let overrides = block.block_overrides;
+			number = overrides.number.unwrap_or(number.saturating_add(1));
+			timestamp = overrides.time.unwrap_or(timestamp + MILLI_SECS_PER_BLOCK / 1000);
+			frame_system::Pallet::<Runtime>::set_block_number(number);
+			pallet_timestamp::Now::<Runtime>::put(timestamp.saturating_mul(1000));
+			if let Some(base_fee) = overrides.base_fee_per_gas {
+				pallet_base_fee::BaseFeePerGas::<Runtime>::put(base_fee);
+			}
+
+			for account in block.state_overrides {
+				override_account(account)?;
+			}
+
+			let mut block_gas_left = block_gas_limit;
+			let calls: Vec<_> = block
+				.calls
+				.into_iter()
+				.map(|call| {
+					let available = block_gas_left.min(gas_left);
+					let result = simulate_call(call, validation, available);
+					let used = result.gas_used.low_u64();
+					block_gas_left = block_gas_left.saturating_sub(used);
+					gas_left = gas_left.saturating_sub(used);
+					result
+				})
+				.collect();
+			Ok(BlockResult {
+				number,
+				timestamp,
+				base_fee_per_gas: pallet_base_fee::BaseFeePerGas::<Runtime>::get(),
+				gas_used: calls.iter().map(|call| call.gas_used).fold(U256::zero(), |a, b| a + b),
+				calls,
+			})
+		})
+		.collect()
+}
+
+fn override_account(account: AccountOverride) -> Result<(), SimulateError> {
+	let address = account.address;
+	let who = <Runtime as pallet_evm::Config>::AddressMapping::into_account_id(address);
+
+	if let Some(balance) = account.balance {
+		Balances::set_balance(&who, balance);
+	}
+	if let Some(nonce) = account.nonce {
+		frame_system::Account::<Runtime>::mutate(&who, |account| {
+			account.nonce = nonce
+		});
+	}
+	if let Some(code) = account.code {
+		pallet_evm::AccountCodesMetadata::<Runtime>::remove(address);
+		if code.is_empty() {
+			pallet_evm::AccountCodes::<Runtime>::remove(address);
+		} else {
+			pallet_evm::AccountCodes::<Runtime>::insert(address, code);
+		}
+	}
+	if let Some(state) = account.state {
+		let removal =
+			pallet_evm::AccountStorages::<Runtime>::clear_prefix(address, MAX_OVERRIDE_SLOTS, None);
+		if removal.maybe_cursor.is_some() {
+			return Err(SimulateError::TooManySlots(address));
+		}
+		set_slots(address, state);
+	}
+	if let Some(state_diff) = account.state_diff {
+		set_slots(address, state_diff);
+	}
+	Ok(())
+}
+
+fn set_slots(address: H160, slots: Vec<(H256, H256)>) {
+	for (index, value) in slots {
+		if value.is_zero() {
+			pallet_evm::AccountStorages::<Runtime>::remove(address, index);
+		} else {
+			pallet_evm::AccountStorages::<Runtime>::insert(address, index, value);
+		}
+	}
+}
+
+/// Run `call` with at most `available` gas.
+fn simulate_call(call: SimulatedCall, validation: bool, available: u64) -> CallResult {
+	let config = <Runtime as pallet_evm::Config>::config();
+	let gas_limit = call.gas_limit.unwrap_or(available);
+	if gas_limit > available {
+		return failure(format!("gas limit {gas_limit} exceeds the {available} gas left"));
+	}
+
+	let result = match call.to {
+		Some(to) => <Runtime as pallet_evm::Config>::Runner::call(
+			call.from,
+			to,
+			call.data,
+			call.value,
+			gas_limit,
+			call.max_fee_per_gas,
+			call.max_priority_fee_per_gas,
+			call.nonce,
+			call.access_list,
+			false,
+			validation,
+			None,
+			None,
+			config,
+		)
+		.map(|info| (info.exit_reason, info.value, info.used_gas.effective, info.logs)),
+		None => <Runtime as pallet_evm::Config>::Runner::create(
+			call.from,
+			call.data,
+			call.value,
+			gas_limit,
+			call.max_fee_per_gas,
+			call.max_priority_fee_per_gas,
+			call.nonce,
+			call.access_list,
+			false,
+			validation,
+			None,
+			None,
+			config,
+		)
+		.map(|info| {
+			let code = pallet_evm::AccountCodes::<Runtime>::get(info.value);
+			(info.exit_reason, code, info.used_gas.effective, info.logs)
+		}),
+	};
+
+	match result {
+		Ok((exit_reason, return_data, gas_used, logs)) => {
+			let error = match exit_reason {
+				fp_evm::ExitReason::Succeed(_) => None,
+				fp_evm::ExitReason::Revert(_) => Some("execution reverted".into()),
+				fp_evm::ExitReason::Error(error) => Some(format!("{error:?}")),
+				fp_evm::ExitReason::Fatal(fatal) => Some(format!("{fatal:?}")),
+			};
+			CallResult { success: error.is_none(), return_data, gas_used, logs, error }
+		},
+		Err(err) => failure(format!("{:?}", sp_runtime::DispatchError::from(err.error))),
+	}
+}
+
+/// A call that could not run.
+fn failure(error: String) -> CallResult {
+	CallResult {
+		success: false,
+		return_data: Vec::new(),
+		gas_used: U256::zero(),
+		logs: Vec::new(),
+		error: Some(error),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::new_test_ext;
+
+	const CONTRACT: H160 = H160::repeat_byte(0x42);
+
+	/// State with `slots` slots in the storage of [`CONTRACT`], committed to the backend as a
+	/// `clear_prefix` limit only counts the keys of the backend.
+	fn ext_with_storage(slots: u32) -> sp_io::TestExternalities {
+		let mut ext = new_test_ext();
+		ext.execute_with(|| {
+			for index in 0..slots {
+				let index = H256::from_low_u64_be(index.into());
+				let value = H256::repeat_byte(1);
+				pallet_evm::AccountStorages::<Runtime>::insert(CONTRACT, index, value);
+			}
+		});
+		ext.commit_all().expect("the overlay is committed; qed");
+		ext
+	}
+
+	fn replace_storage() -> Result<Vec<BlockResult>, SimulateError> {
+		let slot = (H256::repeat_byte(2), H256::repeat_byte(3));
+		let account =
+			AccountOverride { address: CONTRACT, state: Some(vec![slot]), ..Default::default() };
+		let block = SimulatedBlock { state_overrides: vec![account], ..Default::default() };
+		simulate(vec![block], false, 0)
+	}
+
+	#[test]
+	fn state_overrides_replace_the_storage() {
+		ext_with_storage(MAX_OVERRIDE_SLOTS).execute_with(|| {
+			assert!(replace_storage().is_ok());
+			let slots: Vec<_> =
+				pallet_evm::AccountStorages::<Runtime>::iter_prefix(CONTRACT).collect();
+			assert_eq!(slots, vec![(H256::repeat_byte(2), H256::repeat_byte(3))]);
+		})
+	}
+
+	#[test]
+	fn state_overrides_clear_a_bounded_storage() {
+		ext_with_storage(MAX_OVERRIDE_SLOTS + 1).execute_with(|| {
+			assert_eq!(replace_storage(), Err(SimulateError::TooManySlots(CONTRACT)));
+		})
+	}
+}
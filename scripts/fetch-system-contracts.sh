@@ -0,0 +1,47 @@
+#!/bin/bash
+
+# Vendor the runtime bytecode of the genesis system contracts in runtime/presets/contracts,
+# from the contracts deployed on Ethereum, with their code hash. Needs curl, xxd and OpenSSL 3.2 or
+# later for Keccak-256.
+#
+# Usage: ETH_RPC_URL=https://... ./scripts/fetch-system-contracts.sh
+
+set -euo pipefail
+
+ETH_RPC_URL="${ETH_RPC_URL:-https://ethereum-rpc.publicnode.com}"
+CONTRACTS_DIR="$(dirname "$0")/../runtime/presets/contracts"
+
+rpc() {
+    curl -sf -X POST -H 'Content-Type: application/json' \
+        --data "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"$1\",\"params\":$2}" \
+        "$ETH_RPC_URL"
+}
+
+fetch() {
+    local artifact="$1" address="$2"
+    local code code_hash
+    code=$(rpc eth_getCode "[\"$address\",\"latest\"]" \
+        | sed -n 's/.*"result":"\(0x[0-9a-fA-F]*\)".*/\1/p')
+    if [ -z "$code" ] || [ "$code" = "0x" ]; then
+        echo "❌ No bytecode at $address"
+        exit 1
+    fi
+    # The code hash of the account, which the runtime checks the bytecode against.
+    code_hash=$(rpc eth_getProof "[\"$address\",[],\"latest\"]" \
+        | sed -n 's/.*"codeHash":"\(0x[0-9a-fA-F]\{64\}\)".*/\1/p')
+    local keccak
+    keccak=$(printf '%s' "${code#0x}" | xxd -r -p | openssl dgst -keccak-256 -r | cut -d' ' -f1)
+    if [ "$code_hash" != "0x$keccak" ]; then
+        echo "❌ The bytecode at $address does not match its code hash"
+        exit 1
+    fi
+    echo "$code" > "$CONTRACTS_DIR/$artifact.hex"
+    echo "$code_hash" > "$CONTRACTS_DIR/$artifact.codehash"
+    echo "✅ $artifact: $(( (${#code} - 2) / 2 )) bytes from $address, code hash $code_hash"
+}
+
+fetch multicall3 0xcA11bde05977b3631167028862bE2a173976CA11
+fetch create2-deployer 0x4e59b44847b379578588920cA78FbF26c0B4956C
+fetch permit2 0x000000000022D473030F116dDEE9F6B43aC78BA3
+# WETH9
+fetch weth9 0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2